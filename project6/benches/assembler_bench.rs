@@ -160,6 +160,73 @@ fn bench_symbol_table(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark: Interned vs plain symbol storage with long mangled names
+fn bench_symbol_interning(c: &mut Criterion) {
+    let mut group = c.benchmark_group("symbol_interning");
+
+    // Compiler-style mangled names, each referenced many times
+    let names: Vec<String> = (0..32)
+        .map(|i| format!("Main.someVeryLongClassName.someEvenLongerFunctionName$while_loop_{i}"))
+        .collect();
+    let references: Vec<&str> = names
+        .iter()
+        .cycle()
+        .take(names.len() * 16)
+        .map(String::as_str)
+        .collect();
+
+    group.throughput(Throughput::Elements(references.len() as u64));
+    for (label, make_table) in [
+        ("plain", SymbolTable::new as fn() -> SymbolTable),
+        (
+            "interned",
+            SymbolTable::with_interning as fn() -> SymbolTable,
+        ),
+    ] {
+        group.bench_function(BenchmarkId::new("get_or_insert_long_names", label), |b| {
+            b.iter(|| {
+                let mut table = make_table();
+                let mut ram_address = 16;
+                for name in &references {
+                    black_box(table.get_or_insert(name, &mut ram_address));
+                }
+                black_box(table);
+            });
+        });
+
+        group.bench_function(BenchmarkId::new("lookup_long_names", label), |b| {
+            let mut table = make_table();
+            for (address, name) in (16..).zip(&names) {
+                table.add_entry(name, address);
+            }
+            b.iter(|| {
+                for name in &references {
+                    black_box(table.get_address(name));
+                }
+            });
+        });
+    }
+
+    // Interned ids resolved once, then every reference skips hashing the name
+    group.bench_function(BenchmarkId::new("lookup_long_names", "interned_ids"), |b| {
+        let mut table = SymbolTable::with_interning();
+        for (address, name) in (16..).zip(&names) {
+            table.add_entry(name, address);
+        }
+        let ids: Vec<_> = references
+            .iter()
+            .map(|name| table.intern(name).unwrap())
+            .collect();
+        b.iter(|| {
+            for &id in &ids {
+                black_box(table.address_of(id));
+            }
+        });
+    });
+
+    group.finish();
+}
+
 /// Benchmark: Parser performance (byte-level optimized)
 fn bench_parser(c: &mut Criterion) {
     let mut group = c.benchmark_group("parser");
@@ -492,6 +559,7 @@ criterion_group!(
     bench_code_lookups,
     bench_a_instruction,
     bench_symbol_table,
+    bench_symbol_interning,
     bench_parser,
     bench_full_assembly,
     bench_string_operations,
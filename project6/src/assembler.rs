@@ -0,0 +1,1015 @@
+//! The two-pass assembler as a library API
+//!
+//! - **Pass 1** ([`first_pass`]): records the ROM address of every label
+//! - **Pass 2** ([`second_pass`]): resolves symbols and encodes each
+//!   instruction as a 16-bit word
+//!
+//! [`assemble_full`] wraps both passes together with the opt-in lint and
+//! optimization stages, separating warnings (which never block output) from
+//! errors (which do). [`assemble`] and [`assemble_source`] are the in-memory
+//! shortcuts from source text to machine words and to `.hack` text.
+
+use crate::code;
+use crate::consts::{MAX_A_VALUE, VARIABLE_AREA_END};
+use crate::diagnostic::Diagnostic;
+use crate::emit::{self, OutputFormat};
+use crate::lint;
+use crate::optimize;
+use crate::parser::{CommandType, ParserError, ParserLines, read_source_lines, uses_symbols};
+use crate::program::Program;
+use crate::stats::AssemblyStats;
+use crate::symbol_table::SymbolTable;
+use std::fmt;
+use std::hash::{DefaultHasher, Hasher};
+
+/// Options controlling a call to [`assemble_full`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssemblerConfig {
+    /// Run the built-in lints and report their findings as warnings
+    pub lint: bool,
+    /// Names of lints to skip (see [`lint::rule_names`])
+    pub allowed_lints: Vec<String>,
+    /// Extra lint rules run after the built-in ones when `lint` is set
+    pub custom_lints: lint::CustomLints,
+    /// Apply the peephole optimizations before encoding
+    pub optimize: bool,
+    /// RAM address of the first variable, instead of just past the registers
+    pub variable_base: Option<u16>,
+    /// Warn when variables are allocated past `RAM[255]`, the end of the
+    /// static/variable area
+    pub warn_static_overflow: bool,
+}
+
+/// Everything produced by one assembly run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssembleResult {
+    /// Machine code, or `None` if any error was reported
+    pub instructions: Option<Vec<u16>>,
+    /// Problems that don't prevent output (e.g. lint findings)
+    pub warnings: Vec<Diagnostic>,
+    /// Problems that make the program unassemblable
+    pub errors: Vec<Diagnostic>,
+    /// Counts describing the output; all zero if assembly failed
+    pub stats: AssemblyStats,
+    /// Labels and variables of the program; empty if assembly failed
+    pub symbols: SymbolTable,
+    /// 1-based source line of each instruction, in step with `instructions`
+    ///
+    /// Empty if assembly failed, or with `optimize`, where the instructions
+    /// no longer correspond to source lines.
+    pub source_lines: Vec<usize>,
+}
+
+impl AssembleResult {
+    /// Returns `true` if assembly succeeded, regardless of warnings
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl From<ParserError> for Diagnostic {
+    fn from(error: ParserError) -> Self {
+        match error {
+            ParserError::Syntax {
+                line,
+                text,
+                message,
+            } => Self::new(format!("{message}: `{text}`")).with_line(line),
+            ParserError::Symbol { line, error } => Self::new(error.to_string()).with_line(line),
+            error => Self::new(error.to_string()),
+        }
+    }
+}
+
+/// First pass: Build symbol table with label addresses
+///
+/// Scans through all lines and records the ROM address of each label.
+/// Label definitions (L-commands) don't generate code, so they don't
+/// increment the ROM address counter. Defining a label twice, or naming one
+/// after a predefined symbol, is an error.
+///
+/// Errors don't stop the pass: every label is still recorded, and every
+/// error is returned in source order.
+pub fn first_pass(
+    lines: &[String],
+    symbol_table: &mut SymbolTable,
+) -> Result<(), Vec<AssembleError>> {
+    let errors = record_labels(lines, symbol_table);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.into_iter().map(AssembleError::from).collect())
+    }
+}
+
+/// The first pass, carrying on past malformed and duplicate labels
+///
+/// Returns every error in source order. Only running out of ROM addresses
+/// stops the pass.
+fn record_labels(lines: &[String], symbol_table: &mut SymbolTable) -> Vec<ParserError> {
+    let mut rom_address = 0u16;
+    let mut parser = ParserLines::from_lines(lines);
+    let mut errors = Vec::new();
+
+    while parser.advance() {
+        let command_type = match parser.command_type() {
+            Ok(command_type) => command_type,
+            Err(error) => {
+                errors.push(error);
+                continue;
+            }
+        };
+        match command_type {
+            CommandType::LCommand => {
+                // Labels mark the next instruction's address
+                let recorded = parser.symbol().and_then(|symbol| {
+                    symbol_table
+                        .try_add_label(symbol, rom_address)
+                        .map_err(|error| ParserError::Symbol {
+                            line: parser.current_line_number(),
+                            error,
+                        })
+                });
+                if let Err(error) = recorded {
+                    errors.push(error);
+                }
+            }
+            CommandType::ACommand | CommandType::CCommand => {
+                // Actual instructions increment the address
+                let Some(next) = rom_address.checked_add(1) else {
+                    errors.push(ParserError::InvalidState(
+                        "program is longer than the 16-bit address space",
+                    ));
+                    break;
+                };
+                rom_address = next;
+            }
+        }
+    }
+
+    errors
+}
+
+/// Second pass: Generate machine code
+///
+/// Translates each instruction to a 16-bit word:
+/// - A-commands: Resolve symbols to addresses
+/// - C-commands: Encode dest, comp, and jump fields
+/// - L-commands: Skip (already processed in pass 1)
+///
+/// Invalid instructions don't stop the pass: each is reported and encoded
+/// as a placeholder `0` so later addresses stay correct, and the caller can
+/// show every error at once.
+///
+/// Labels must already be in `symbol_table` from [`first_pass`]: otherwise a
+/// `@foo` before `(foo)` would be allocated as a variable. Every label whose
+/// recorded address isn't its ROM address is reported as an error.
+pub fn second_pass(
+    lines: &[String],
+    symbol_table: &mut SymbolTable,
+) -> Result<Vec<u16>, Vec<AssembleError>> {
+    encode_pass(lines, symbol_table, &[], &mut |_| {})
+        .map(|(words, _)| words)
+        .map_err(|errors| errors.into_iter().map(AssembleError).collect())
+}
+
+/// Instructions encoded between two progress reports
+const PROGRESS_INTERVAL: usize = 1024;
+
+/// The second pass, calling `progress` with the number of words encoded so far
+///
+/// Progress is reported every [`PROGRESS_INTERVAL`] instructions and once
+/// more when the pass completes. Alongside the words, returns the source
+/// line each one came from. Labels on `rejected_labels`, the lines pass 1
+/// already reported, aren't checked again.
+fn encode_pass(
+    lines: &[String],
+    symbol_table: &mut SymbolTable,
+    rejected_labels: &[usize],
+    progress: &mut dyn FnMut(usize),
+) -> Result<(Vec<u16>, Vec<usize>), Vec<Diagnostic>> {
+    let mut parser = ParserLines::from_lines(lines);
+    let mut words = Vec::with_capacity(lines.len());
+    let mut source_lines = Vec::with_capacity(lines.len());
+    let mut errors = Vec::new();
+    let mut reported = 0;
+
+    while parser.advance() {
+        let command_type = match parser.command_type() {
+            Ok(command_type) => command_type,
+            Err(error) => {
+                errors.push(error.into());
+                continue;
+            }
+        };
+        match command_type {
+            CommandType::ACommand => {
+                let address = match parser.symbol() {
+                    Ok(symbol) => resolve_address(symbol, symbol_table).unwrap_or_else(|message| {
+                        errors.push(
+                            Diagnostic::at(words.len(), message)
+                                .with_line(parser.current_line_number()),
+                        );
+                        0
+                    }),
+                    Err(error) => {
+                        errors.push(error.into());
+                        0
+                    }
+                };
+
+                words.push(code::encode_a_instruction_bits(address));
+                source_lines.push(parser.current_line_number());
+            }
+            CommandType::CCommand => {
+                let fields = parser
+                    .dest()
+                    .and_then(|dest| Ok((dest, parser.comp()?, parser.jump()?)));
+                let word = match fields {
+                    Ok((dest, comp, jump)) => code::encode_c_instruction_checked(
+                        dest.unwrap_or(""),
+                        comp.unwrap_or(""),
+                        jump.unwrap_or(""),
+                    )
+                    .unwrap_or_else(|error| {
+                        errors.push(
+                            Diagnostic::at(words.len(), error.to_string())
+                                .with_line(parser.current_line_number()),
+                        );
+                        0
+                    }),
+                    Err(error) => {
+                        errors.push(error.into());
+                        0
+                    }
+                };
+                words.push(word);
+                source_lines.push(parser.current_line_number());
+            }
+            CommandType::LCommand => {
+                // Labels were resolved in pass 1 and emit no code, but check
+                // that no earlier reference turned one into a variable
+                match parser.symbol() {
+                    Ok(_) if rejected_labels.contains(&parser.current_line_number()) => {}
+                    Ok(symbol) => {
+                        if let Some(message) = misplaced_label(symbol, words.len(), symbol_table) {
+                            errors.push(
+                                Diagnostic::at(words.len(), message)
+                                    .with_line(parser.current_line_number()),
+                            );
+                        }
+                    }
+                    Err(error) => errors.push(error.into()),
+                }
+            }
+        }
+
+        if words.len() >= reported + PROGRESS_INTERVAL {
+            reported = words.len();
+            progress(reported);
+        }
+    }
+
+    if reported != words.len() || words.is_empty() {
+        progress(words.len());
+    }
+
+    if errors.is_empty() {
+        Ok((words, source_lines))
+    } else {
+        Err(errors)
+    }
+}
+
+/// Adds the errors of pass 2 to those of pass 1, in source order
+///
+/// Only an error both passes reported word for word is dropped; different
+/// errors on the same line, or on no line, are all kept.
+fn merge_pass_errors(errors: &mut Vec<Diagnostic>, second_pass: Vec<Diagnostic>) {
+    let first_pass = errors.len();
+    for error in second_pass {
+        if !errors[..first_pass].contains(&error) {
+            errors.push(error);
+        }
+    }
+    // Both passes read the source top to bottom; the sort is stable
+    errors.sort_by_key(|error| error.line);
+}
+
+/// Describes a label the first pass didn't record at `rom_address`, if any
+fn misplaced_label(symbol: &str, rom_address: usize, symbol_table: &SymbolTable) -> Option<String> {
+    if !symbol_table.contains(symbol) {
+        return Some(format!(
+            "label `{symbol}` was not recorded by the first pass"
+        ));
+    }
+    let address = symbol_table.get_address(symbol);
+    (usize::from(address) != rom_address).then(|| {
+        format!(
+            "label `{symbol}` resolved to {address} instead of ROM {rom_address}; \
+             it was allocated as a variable before the first pass recorded it"
+        )
+    })
+}
+
+/// Resolves an A-instruction operand to the 15-bit value it loads
+///
+/// Numbers (see [`code::parse_a_constant`]) are constants and must fit in
+/// 15 bits; anything else is a label or variable, whose address must fit as
+/// well.
+fn resolve_address(symbol: &str, symbol_table: &mut SymbolTable) -> Result<u16, String> {
+    if let Some(constant) = code::parse_a_constant(symbol) {
+        return constant.map_err(|error| error.to_string());
+    }
+
+    // Variables start at RAM[16], or past any extra registers
+    let address = symbol_table.allocate_variable(symbol);
+    if address > MAX_A_VALUE {
+        return Err(format!(
+            "symbol `{symbol}` resolves to {address}, which does not fit in an A-instruction \
+             (maximum {MAX_A_VALUE})"
+        ));
+    }
+    Ok(address)
+}
+
+/// Assembles a program, reporting warnings and errors separately
+///
+/// Lints (when enabled) run on the source as written, before any
+/// optimization, so their addresses match the unoptimized program.
+///
+/// # Example
+/// ```
+/// use project6::{assemble_full, AssemblerConfig};
+///
+/// let lines: Vec<String> = ["@2", "D=A"].iter().map(ToString::to_string).collect();
+/// let config = AssemblerConfig { lint: true, ..AssemblerConfig::default() };
+/// let result = assemble_full(&lines, &config);
+///
+/// assert_eq!(result.instructions, Some(vec![0x0002, 0xEC10]));
+/// assert_eq!(result.warnings.len(), 1); // No terminating loop
+/// assert!(result.errors.is_empty());
+/// ```
+#[must_use]
+pub fn assemble_full(lines: &[String], config: &AssemblerConfig) -> AssembleResult {
+    assemble_full_with_progress(lines, config, None)
+}
+
+/// [`assemble_full`], reporting progress through the second pass
+///
+/// `progress` is called with `(current, total)`: the instructions encoded so
+/// far and the program's instruction count (after optimization, if enabled).
+/// It is called every 1024 instructions with increasing `current`,
+/// and a last time with `current == total` once encoding finishes. It is not
+/// called if assembly stops before the second pass.
+///
+/// # Example
+/// ```
+/// use project6::{assemble_full_with_progress, AssemblerConfig};
+///
+/// let lines: Vec<String> = ["@2", "D=A"].iter().map(ToString::to_string).collect();
+/// let mut calls = Vec::new();
+/// let mut record = |current, total| calls.push((current, total));
+/// let result = assemble_full_with_progress(&lines, &AssemblerConfig::default(), Some(&mut record));
+///
+/// assert!(result.is_ok());
+/// assert_eq!(calls, [(2, 2)]);
+/// ```
+#[must_use]
+pub fn assemble_full_with_progress(
+    lines: &[String],
+    config: &AssemblerConfig,
+    mut progress: Option<&mut dyn FnMut(usize, usize)>,
+) -> AssembleResult {
+    let mut result = AssembleResult::default();
+
+    // A malformed program can be neither linted nor optimized, but is still
+    // assembled as written so that every error in it is reported
+    let program = Program::from_lines(lines);
+    if config.lint
+        && let Ok(program) = &program
+    {
+        result.warnings =
+            lint::lint_program_with(program, &config.custom_lints, &config.allowed_lints);
+    }
+
+    let optimized;
+    let mut program_error = None;
+    let (lines, total) = match program {
+        Ok(mut program) if config.optimize => {
+            optimize::optimize(&mut program);
+            optimized = program.to_source_lines();
+            (optimized.as_slice(), program.instructions.len())
+        }
+        Ok(program) => (lines, program.instructions.len()),
+        Err(error) => {
+            program_error = Some(Diagnostic::from(error));
+            (lines, lines.len())
+        }
+    };
+
+    let mut symbol_table = match config.variable_base {
+        Some(base) => SymbolTable::with_variable_base(base),
+        None => SymbolTable::new(),
+    };
+
+    // Pass 1: Build symbol table (purely numeric programs have no labels).
+    // Malformed commands are left for pass 2, which reports them again.
+    let mut rejected_labels = Vec::new();
+    if uses_symbols(lines) {
+        for error in record_labels(lines, &mut symbol_table) {
+            match error {
+                ParserError::Syntax { .. } => {}
+                ParserError::Symbol { line, .. } => {
+                    rejected_labels.push(line);
+                    result.errors.push(error.into());
+                }
+                error => result.errors.push(error.into()),
+            }
+        }
+    }
+    let labels = symbol_table.user_symbol_count();
+
+    // Pass 2: Generate machine code
+    let mut report = |current| {
+        if let Some(progress) = progress.as_mut() {
+            progress(current, total);
+        }
+    };
+    match encode_pass(lines, &mut symbol_table, &rejected_labels, &mut report) {
+        Ok((words, source_lines)) if result.errors.is_empty() => {
+            let variables = symbol_table.user_symbol_count() - labels;
+            if config.warn_static_overflow
+                && symbol_table.next_variable_address() > VARIABLE_AREA_END + 1
+            {
+                result.warnings.push(Diagnostic::new(format!(
+                    "variables extend to RAM[{}], past the end of the static area at RAM[{VARIABLE_AREA_END}]",
+                    symbol_table.next_variable_address() - 1
+                )));
+            }
+            let a_instructions = words.iter().filter(|&&word| word & 0x8000 == 0).count();
+            result.stats = AssemblyStats {
+                a_instructions,
+                c_instructions: words.len() - a_instructions,
+                labels,
+                variables,
+                total_instructions: words.len(),
+                ram_high_water: u16::try_from(variables).ok().and_then(|count| {
+                    count
+                        .checked_sub(1)
+                        .and_then(|last| symbol_table.first_variable_address().checked_add(last))
+                }),
+                warnings: result.warnings.len(),
+            };
+            result.instructions = Some(words);
+            result.symbols = symbol_table;
+            if !config.optimize {
+                result.source_lines = source_lines;
+            }
+        }
+        Ok(_) => {}
+        Err(errors) => merge_pass_errors(&mut result.errors, errors),
+    }
+    if result.errors.is_empty()
+        && let Some(error) = program_error
+    {
+        result.instructions = None;
+        result.symbols = SymbolTable::new();
+        result.source_lines = Vec::new();
+        result.stats = AssemblyStats::default();
+        result.errors.push(error);
+    }
+    if config.optimize {
+        // Lines of the rewritten program don't match the source
+        for error in &mut result.errors {
+            error.line = None;
+        }
+    }
+
+    result
+}
+
+/// Checks that a program assembles, without keeping the machine code
+///
+/// Returns every error [`assemble_full`] would report with the default
+/// settings, or an empty list for valid Hack assembly. Symbols that are
+/// neither labels nor predefined are variables in Hack, so they are never
+/// errors here.
+///
+/// # Example
+/// ```
+/// use project6::validate_program;
+///
+/// let lines: Vec<String> = ["@i", "M=1", "D=Q"].iter().map(ToString::to_string).collect();
+/// let errors = validate_program(&lines);
+/// assert_eq!(errors.len(), 1);
+/// assert_eq!(errors[0].line, Some(3));
+/// ```
+#[must_use]
+pub fn validate_program(lines: &[String]) -> Vec<Diagnostic> {
+    assemble_full(lines, &AssemblerConfig::default()).errors
+}
+
+/// Assembles a program straight to machine words with the default settings
+///
+/// The shortest path from source to something [`Simulator::new`] can run.
+///
+/// [`Simulator::new`]: crate::simulator::Simulator::new
+///
+/// # Example
+/// ```
+/// use project6::assemble_to_u16;
+///
+/// let lines: Vec<String> = ["@2", "D=A"].iter().map(ToString::to_string).collect();
+/// assert_eq!(assemble_to_u16(&lines), Ok(vec![2, 0xEC10]));
+/// ```
+pub fn assemble_to_u16(lines: &[String]) -> Result<Vec<u16>, Vec<Diagnostic>> {
+    let result = assemble_full(lines, &AssemblerConfig::default());
+    result.instructions.ok_or(result.errors)
+}
+
+/// Assembles source text held in memory into `.hack` text
+///
+/// Uses the default [`AssemblerConfig`]. On failure every error is reported,
+/// one per line.
+///
+/// # Example
+/// ```
+/// use project6::assembler::assemble_source;
+///
+/// assert_eq!(
+///     assemble_source("@2\nD=A\n").unwrap(),
+///     "0000000000000010\n1110110000010000\n"
+/// );
+/// assert!(assemble_source("D=Q").is_err());
+/// ```
+pub fn assemble_source(source: &str) -> Result<String, String> {
+    let lines = read_source_lines(source.as_bytes()).map_err(|error| error.to_string())?;
+    let result = assemble_full(&lines, &AssemblerConfig::default());
+
+    let Some(words) = result.instructions else {
+        let messages: Vec<String> = result.errors.iter().map(ToString::to_string).collect();
+        return Err(messages.join("\n"));
+    };
+
+    let mut hack = Vec::new();
+    emit::write_words(&mut hack, &words, OutputFormat::Bits).map_err(|error| error.to_string())?;
+    String::from_utf8(hack).map_err(|error| error.to_string())
+}
+
+/// Returns `true` if assembling `source` would not reproduce `existing_hack`
+///
+/// Meant for incremental builds: the program is assembled in memory and a
+/// hash of its words is compared with a hash of the words in the `.hack`
+/// text, so an unchanged output file need not be rewritten. No word is
+/// formatted as text along the way. Line endings are ignored. A program
+/// that fails to assemble always needs reassembly, so that its errors get
+/// reported, as does a `.hack` text with a malformed line.
+///
+/// # Example
+/// ```
+/// use project6::{AssemblerConfig, needs_reassembly};
+///
+/// let source: Vec<String> = ["@2", "D=A"].iter().map(ToString::to_string).collect();
+/// let hack = "0000000000000010\r\n1110110000010000\r\n";
+/// assert!(!needs_reassembly(&source, hack, &AssemblerConfig::default()));
+/// ```
+#[must_use]
+pub fn needs_reassembly(source: &[String], existing_hack: &str, config: &AssemblerConfig) -> bool {
+    let Some(words) = assemble_full(source, config).instructions else {
+        return true;
+    };
+
+    let mut malformed = false;
+    let existing_hash = hash_words(existing_hack.lines().map_while(|line| {
+        let word = word_from_bits(line);
+        malformed |= word.is_none();
+        word
+    }));
+    malformed || hash_words(words) != existing_hash
+}
+
+/// Hashes a sequence of machine words, including how many there are
+fn hash_words(words: impl IntoIterator<Item = u16>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let mut count = 0;
+    for word in words {
+        hasher.write_u16(word);
+        count += 1;
+    }
+    hasher.write_usize(count);
+    hasher.finish()
+}
+
+/// Reads a line of `.hack` text as its word, if it is 16 binary digits
+fn word_from_bits(line: &str) -> Option<u16> {
+    (line.len() == 16 && line.bytes().all(|bit| bit == b'0' || bit == b'1')).then(|| {
+        line.bytes()
+            .fold(0, |word, bit| (word << 1) | u16::from(bit == b'1'))
+    })
+}
+
+/// One error that stops a program assembling, located as a [`Diagnostic`]
+///
+/// [`first_pass`], [`second_pass`] and [`assemble`] carry on past each error
+/// and return all of them, so a whole file can be fixed in one go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError(pub Diagnostic);
+
+impl std::error::Error for AssembleError {}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<ParserError> for AssembleError {
+    fn from(error: ParserError) -> Self {
+        Self(error.into())
+    }
+}
+
+impl From<AssembleError> for Diagnostic {
+    fn from(error: AssembleError) -> Self {
+        error.0
+    }
+}
+
+/// Assembles source text into machine words with the default settings
+///
+/// Runs both passes with a fresh [`SymbolTable`]: labels resolve to ROM
+/// addresses and new symbols become variables from RAM 16.
+///
+/// # Example
+/// ```
+/// use project6::assemble;
+///
+/// assert_eq!(assemble("(LOOP)\n@i\n@LOOP\n0;JMP\n"), Ok(vec![16, 0, 0xEA87]));
+///
+/// let errors = assemble("@2\nD=Q\n").unwrap_err();
+/// assert_eq!(errors[0].0.line, Some(2));
+/// ```
+pub fn assemble(source: &str) -> Result<Vec<u16>, Vec<AssembleError>> {
+    let lines = read_source_lines(source.as_bytes()).map_err(|error| vec![error.into()])?;
+    let result = assemble_full(&lines, &AssemblerConfig::default());
+    result
+        .instructions
+        .ok_or_else(|| result.errors.into_iter().map(AssembleError).collect())
+}
+
+/// [`assemble`], returning each word as a line of a `.hack` file
+///
+/// # Example
+/// ```
+/// use project6::assemble_to_strings;
+///
+/// assert_eq!(assemble_to_strings("@2").unwrap(), ["0000000000000010"]);
+/// ```
+pub fn assemble_to_strings(source: &str) -> Result<Vec<String>, Vec<AssembleError>> {
+    Ok(assemble(source)?
+        .into_iter()
+        .map(|word| format!("{word:016b}"))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(source: &[&str]) -> Vec<String> {
+        source.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_warning_still_yields_instructions() {
+        let config = AssemblerConfig {
+            lint: true,
+            ..AssemblerConfig::default()
+        };
+        let result = assemble_full(&lines(&["@2", "D=A", "@0", "M=D"]), &config);
+
+        assert!(result.is_ok());
+        assert_eq!(result.instructions, Some(vec![2, 0xEC10, 0, 0xE308]));
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].message.contains("infinite loop"));
+    }
+
+    #[test]
+    fn test_label_wins_over_earlier_reference() {
+        let source = lines(&["@foo", "0;JMP", "@counter", "M=0", "(foo)", "D=A"]);
+        let result = assemble_full(&source, &AssemblerConfig::default());
+
+        assert_eq!(
+            result.instructions,
+            Some(vec![4, 0xEA87, 16, 0xEA88, 0xEC10])
+        );
+        assert_eq!(result.stats.labels, 1);
+        assert_eq!(result.stats.variables, 1);
+        assert_eq!(result.symbols.get_address("foo"), 4);
+    }
+
+    #[test]
+    fn test_first_pass_reports_every_error() {
+        let source = lines(&["(A", "(B)", "(B)", "(SCREEN)", "(C)", "0;JMP"]);
+        let mut symbol_table = SymbolTable::new();
+        let errors = first_pass(&source, &mut symbol_table).unwrap_err();
+
+        let lines: Vec<_> = errors.iter().map(|error| error.0.line).collect();
+        assert_eq!(lines, [Some(1), Some(3), Some(4)]);
+        // Labels after an error are still recorded
+        assert!(symbol_table.contains("C"));
+    }
+
+    #[test]
+    fn test_second_pass_without_first_pass_is_reported() {
+        let source = lines(&["@foo", "0;JMP", "(foo)", "D=A"]);
+        let errors = second_pass(&source, &mut SymbolTable::new()).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0.line, Some(3));
+        assert!(
+            errors[0]
+                .0
+                .message
+                .contains("resolved to 16 instead of ROM 2")
+        );
+
+        let errors = second_pass(&lines(&["(bar)", "@bar"]), &mut SymbolTable::new()).unwrap_err();
+        assert!(
+            errors[0]
+                .0
+                .message
+                .contains("not recorded by the first pass")
+        );
+    }
+
+    #[test]
+    fn test_merge_pass_errors_keeps_distinct_errors() {
+        let label = Diagnostic::new("label 'X' is already defined").with_line(2);
+        let comp = Diagnostic::at(1, "invalid comp `Q`").with_line(2);
+        let mut errors = vec![Diagnostic::new("program too long"), label.clone()];
+        merge_pass_errors(
+            &mut errors,
+            vec![
+                Diagnostic::at(0, "invalid dest `X`").with_line(1),
+                comp.clone(),
+                label.clone(),
+                Diagnostic::new("input ended early"),
+            ],
+        );
+
+        let messages: Vec<_> = errors.iter().map(|error| error.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "program too long",
+                "input ended early",
+                "invalid dest `X`",
+                "label 'X' is already defined",
+                "invalid comp `Q`",
+            ]
+        );
+        assert_eq!(errors[3..], [label, comp]);
+    }
+
+    #[test]
+    fn test_assemble_reports_every_error() {
+        // Malformed and duplicate labels don't stop either pass
+        let errors = assemble("@99999\nD=Q\n(A\n(B)\n@B\n(B)\nD;JXX\n").unwrap_err();
+        let lines: Vec<_> = errors.iter().map(|error| error.0.line).collect();
+        assert_eq!(lines, [Some(1), Some(2), Some(3), Some(6), Some(7)]);
+        assert_eq!(errors[4].0.address, Some(3));
+
+        let errors = assemble("@99999\nD=Q\n").unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[1].to_string().starts_with("line 2: "));
+
+        assert_eq!(
+            assemble_to_strings("@i\nM=1\n").unwrap(),
+            ["0000000000010000", "1110111111001000"]
+        );
+    }
+
+    #[test]
+    fn test_needs_reassembly() {
+        let source = lines(&["(LOOP)", "@i", "M=M+1", "@LOOP", "0;JMP"]);
+        let hack = assemble_source(&source.join("\n")).unwrap();
+        let config = AssemblerConfig::default();
+
+        assert!(!needs_reassembly(&source, &hack, &config));
+
+        let mut modified = source.clone();
+        modified[2] = "M=M-1".to_string();
+        assert!(needs_reassembly(&modified, &hack, &config));
+        assert!(needs_reassembly(&source[..4], &hack, &config));
+        assert!(needs_reassembly(
+            &source,
+            &(hack.clone() + "0000000000000000\n"),
+            &config
+        ));
+        assert!(needs_reassembly(&lines(&["D=Q"]), "", &config));
+        // A malformed line always needs reassembly; line endings do not
+        assert!(needs_reassembly(
+            &source,
+            &hack.replacen('0', "x", 1),
+            &config
+        ));
+        assert!(!needs_reassembly(
+            &source,
+            &hack.replace('\n', "\r\n"),
+            &config
+        ));
+    }
+
+    #[test]
+    fn test_out_of_range_constant_is_an_error() {
+        let result = assemble_full(
+            &lines(&["@007", "@32767", "@32768", "@99999999999"]),
+            &AssemblerConfig::default(),
+        );
+
+        assert_eq!(result.instructions, None);
+        assert_eq!(result.errors.len(), 2);
+        assert_eq!(result.errors[0].address, Some(2));
+        assert!(result.errors[0].message.contains("32768"));
+        assert_eq!(result.errors[1].line, Some(4));
+
+        let result = assemble_full(&lines(&["@007", "@32767"]), &AssemblerConfig::default());
+        assert_eq!(result.instructions, Some(vec![7, 32767]));
+    }
+
+    #[test]
+    fn test_malformed_input_returns_errors_without_panicking() {
+        use std::panic::{AssertUnwindSafe, catch_unwind};
+
+        let mut long_program = lines(&["(HIGH)", "@HIGH"]);
+        long_program.resize(70_000, "D=0".to_string());
+        let cases = [
+            lines(&["("]),
+            lines(&["(LOOP"]),
+            lines(&["(LOOP) D=M"]),
+            lines(&["@99999999999"]),
+            lines(&["D=Q;JXX", "A+M", "=;"]),
+            long_program,
+        ];
+        let configs = [
+            AssemblerConfig::default(),
+            AssemblerConfig {
+                lint: true,
+                optimize: true,
+                ..AssemblerConfig::default()
+            },
+            AssemblerConfig {
+                variable_base: Some(u16::MAX),
+                ..AssemblerConfig::default()
+            },
+        ];
+
+        for source in &cases {
+            for config in &configs {
+                let result = catch_unwind(AssertUnwindSafe(|| assemble_full(source, config)))
+                    .unwrap_or_else(|_| panic!("assembling {:?} panicked", source[0]));
+                assert!(!result.is_ok(), "{:?}", source[0]);
+            }
+        }
+
+        let variables = lines(&["@i", "M=0", "@j", "M=0"]);
+        let result =
+            catch_unwind(AssertUnwindSafe(|| assemble_full(&variables, &configs[2]))).unwrap();
+        assert_eq!(result.errors.len(), 2);
+        assert!(result.errors[0].message.contains("`i` resolves to 65535"));
+        assert!(catch_unwind(|| assemble_source("(LOOP) D=M").is_err()).unwrap());
+    }
+
+    #[test]
+    fn test_duplicate_label_reports_second_definition() {
+        let source = lines(&["(LOOP)", "@LOOP", "// again", "(LOOP)", "0;JMP"]);
+        let result = assemble_full(&source, &AssemblerConfig::default());
+
+        assert_eq!(result.instructions, None);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].line, Some(4));
+        assert!(
+            result.errors[0]
+                .message
+                .contains("'LOOP' is already defined")
+        );
+
+        let result = assemble_full(&lines(&["(SP)", "0;JMP"]), &AssemblerConfig::default());
+        assert!(result.errors[0].message.contains("predefined"));
+
+        // Variables are only allocated in pass 2, after every label is defined
+        let result = assemble_full(
+            &lines(&["@x", "M=0", "(x2)", "@x2", "0;JMP"]),
+            &AssemblerConfig::default(),
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_radix_suffixes() {
+        let result = assemble_full(
+            &lines(&["@64h", "@1010b", "@100d", "@foobarh"]),
+            &AssemblerConfig::default(),
+        );
+        assert_eq!(result.instructions, Some(vec![100, 10, 100, 16]));
+    }
+
+    #[test]
+    fn test_progress_reports() {
+        let mut source = lines(&["(LOOP)", "@LOOP"]);
+        source.resize(3000, "D=D+1".to_string());
+        let mut calls = Vec::new();
+        let mut record = |current, total| calls.push((current, total));
+
+        let result =
+            assemble_full_with_progress(&source, &AssemblerConfig::default(), Some(&mut record));
+
+        assert!(result.is_ok());
+        assert_eq!(calls, [(1024, 2999), (2048, 2999), (2999, 2999)]);
+        assert!(calls.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn test_error_yields_no_instructions() {
+        let result = assemble_full(
+            &lines(&["@2", "D=A+M", "D=D+M1", "(END)", "@END", "0;JMP"]),
+            &AssemblerConfig::default(),
+        );
+
+        assert!(!result.is_ok());
+        assert_eq!(result.instructions, None);
+        assert_eq!(result.errors.len(), 2);
+        assert_eq!(result.errors[0].address, Some(1));
+        assert_eq!(result.errors[0].line, Some(2));
+        assert_eq!(result.errors[1].address, Some(2));
+    }
+
+    #[test]
+    fn test_lints_are_opt_in() {
+        let result = assemble_full(&lines(&["@0", "M=D"]), &AssemblerConfig::default());
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_labels_and_variables_resolve() {
+        let result = assemble_full(
+            &lines(&["@i", "M=0", "(LOOP)", "@LOOP", "0;JMP"]),
+            &AssemblerConfig::default(),
+        );
+        assert_eq!(result.instructions, Some(vec![16, 0xEA88, 2, 0xEA87]));
+    }
+
+    #[test]
+    fn test_optimize_config() {
+        let config = AssemblerConfig {
+            optimize: true,
+            ..AssemblerConfig::default()
+        };
+        let result = assemble_full(&lines(&["@5", "@5", "D=A"]), &config);
+        assert_eq!(result.instructions, Some(vec![5, 0xEC10]));
+    }
+
+    #[test]
+    fn test_assemble_source_reports_every_error() {
+        let error = assemble_source("@2\nD=Q\nM=X\n").unwrap_err();
+        assert_eq!(error.lines().count(), 2);
+    }
+
+    #[test]
+    fn test_variable_base_config() {
+        let config = AssemblerConfig {
+            variable_base: Some(64),
+            ..AssemblerConfig::default()
+        };
+        let result = assemble_full(&lines(&["@i", "M=0", "@j", "M=0"]), &config);
+        assert_eq!(result.instructions, Some(vec![64, 0xEA88, 65, 0xEA88]));
+        assert_eq!(result.stats.ram_high_water, Some(65));
+    }
+
+    #[test]
+    fn test_static_overflow_warning() {
+        let config = AssemblerConfig {
+            variable_base: Some(255),
+            warn_static_overflow: true,
+            ..AssemblerConfig::default()
+        };
+        let result = assemble_full(&lines(&["@i", "M=0"]), &config);
+        assert!(result.warnings.is_empty());
+
+        let result = assemble_full(&lines(&["@i", "M=0", "@j", "M=0"]), &config);
+        assert!(result.is_ok());
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].message.contains("RAM[256]"));
+        assert_eq!(result.stats.warnings, 1);
+
+        let quiet = AssemblerConfig {
+            warn_static_overflow: false,
+            ..config
+        };
+        let result = assemble_full(&lines(&["@i", "M=0", "@j", "M=0"]), &quiet);
+        assert!(result.warnings.is_empty());
+    }
+}
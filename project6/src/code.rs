@@ -8,80 +8,184 @@
 //! - String formatting uses standard library (optimized by LLVM)
 //! - Hot paths are inlined for better performance
 
+use crate::consts::MAX_A_VALUE;
 use phf::phf_map;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Errors reported when an instruction cannot be encoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodeError {
+    /// Unknown destination mnemonic
+    InvalidDest(String),
+    /// Unknown computation mnemonic
+    InvalidComp(String),
+    /// Unknown jump mnemonic
+    InvalidJump(String),
+    /// Computation that reads both A and M, which one instruction cannot do
+    MixedRegisters(String),
+    /// Raw bit field with the wrong width or a character other than `0`/`1`
+    InvalidBits {
+        field: &'static str,
+        expected_width: usize,
+        bits: String,
+    },
+    /// Numeric A-instruction constant above [`MAX_A_VALUE`]
+    AddressOutOfRange(String),
+    /// Radix prefix (`0x`, `0b` or `0o`) with no digits after it
+    MissingDigits(String),
+}
+
+impl std::error::Error for CodeError {}
+
+impl fmt::Display for CodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidDest(m) => write!(f, "invalid dest mnemonic '{m}'"),
+            Self::InvalidComp(m) => write!(f, "invalid comp mnemonic '{m}'"),
+            Self::InvalidJump(m) => write!(f, "invalid jump mnemonic '{m}'"),
+            Self::MixedRegisters(m) => {
+                write!(f, "cannot use both A and M in one computation: '{m}'")
+            }
+            Self::InvalidBits {
+                field,
+                expected_width,
+                bits,
+            } => write!(
+                f,
+                "{field} bits must be exactly {expected_width} binary digits, got '{bits}'"
+            ),
+            Self::AddressOutOfRange(value) => write!(
+                f,
+                "constant {value} does not fit in an A-instruction (maximum {MAX_A_VALUE})"
+            ),
+            Self::MissingDigits(value) => write!(f, "constant {value} has no digits"),
+        }
+    }
+}
+
+/// One encoded instruction field: its bit string and the same bits as a number
+///
+/// The bit strings in the tables below are the source of truth; the numeric
+/// value is derived from them at compile time so that numeric encoding never
+/// parses strings.
+#[derive(Debug, Clone, Copy)]
+struct Field {
+    bits: &'static str,
+    value: u16,
+}
+
+impl Field {
+    const fn new(bits: &'static str) -> Self {
+        let digits = bits.as_bytes();
+        let mut value = 0;
+        let mut i = 0;
+        while i < digits.len() {
+            value = (value << 1) | (digits[i] - b'0') as u16;
+            i += 1;
+        }
+        Self { bits, value }
+    }
+}
 
 /// Destination mnemonic to binary code mapping (3 bits)
 ///
 /// Maps destination mnemonics to their 3-bit binary representation.
 /// Empty string represents null destination.
-static DEST_MAP: phf::Map<&'static str, &'static str> = phf_map! {
-    "" => "000",
-    "M" => "001",
-    "D" => "010",
-    "MD" => "011",
-    "A" => "100",
-    "AM" => "101",
-    "AD" => "110",
-    "AMD" => "111",
+static DEST_MAP: phf::Map<&'static str, Field> = phf_map! {
+    "" => Field::new("000"),
+    "M" => Field::new("001"),
+    "D" => Field::new("010"),
+    "MD" => Field::new("011"),
+    "A" => Field::new("100"),
+    "AM" => Field::new("101"),
+    "AD" => Field::new("110"),
+    "AMD" => Field::new("111"),
 };
 
 /// Computation mnemonic to binary code mapping (7 bits: 1 bit 'a' + 6 bits 'cccccc')
 ///
 /// Includes both a=0 (A register) and a=1 (M register) variants.
 /// The first bit indicates which register to use: 0 for A, 1 for M.
-static COMP_MAP: phf::Map<&'static str, &'static str> = phf_map! {
+static COMP_MAP: phf::Map<&'static str, Field> = phf_map! {
     // a=0 (A register operations)
-    "0" => "0101010",
-    "1" => "0111111",
-    "-1" => "0111010",
-    "D" => "0001100",
-    "A" => "0110000",
-    "!D" => "0001101",
-    "!A" => "0110001",
-    "-D" => "0001111",
-    "-A" => "0110011",
-    "D+1" => "0011111",
-    "A+1" => "0110111",
-    "D-1" => "0001110",
-    "A-1" => "0110010",
-    "D+A" => "0000010",
-    "D-A" => "0010011",
-    "A-D" => "0000111",
-    "D&A" => "0000000",
-    "D|A" => "0010101",
+    "0" => Field::new("0101010"),
+    "1" => Field::new("0111111"),
+    "-1" => Field::new("0111010"),
+    "D" => Field::new("0001100"),
+    "A" => Field::new("0110000"),
+    "!D" => Field::new("0001101"),
+    "!A" => Field::new("0110001"),
+    "-D" => Field::new("0001111"),
+    "-A" => Field::new("0110011"),
+    "D+1" => Field::new("0011111"),
+    "A+1" => Field::new("0110111"),
+    "D-1" => Field::new("0001110"),
+    "A-1" => Field::new("0110010"),
+    "D+A" => Field::new("0000010"),
+    "D-A" => Field::new("0010011"),
+    "A-D" => Field::new("0000111"),
+    "D&A" => Field::new("0000000"),
+    "D|A" => Field::new("0010101"),
 
     // a=1 (M register operations)
-    "M" => "1110000",
-    "!M" => "1110001",
-    "-M" => "1110011",
-    "M+1" => "1110111",
-    "M-1" => "1110010",
-    "D+M" => "1000010",
-    "D-M" => "1010011",
-    "M-D" => "1000111",
-    "D&M" => "1000000",
-    "D|M" => "1010101",
+    "M" => Field::new("1110000"),
+    "!M" => Field::new("1110001"),
+    "-M" => Field::new("1110011"),
+    "M+1" => Field::new("1110111"),
+    "M-1" => Field::new("1110010"),
+    "D+M" => Field::new("1000010"),
+    "D-M" => Field::new("1010011"),
+    "M-D" => Field::new("1000111"),
+    "D&M" => Field::new("1000000"),
+    "D|M" => Field::new("1010101"),
+};
+
+/// Shift computations of the extended ALU (7 bits, same `a` + `cccccc` split)
+///
+/// These are the extended instructions of the `Nand2Tetris` CPU emulator. An
+/// extended C-instruction is `101accccccdddjjj`: bit 13 is cleared to mark
+/// it, and the comp bits below are only meaningful under that prefix (under
+/// `111` they would collide with `D&A` and friends). Bit 12 selects `M` over
+/// `A` as usual; bit 11 set means a left shift, bit 10 set means the operand
+/// is `D`. Dest and jump fields are unchanged.
+static COMP_MAP_EXTENDED: phf::Map<&'static str, Field> = phf_map! {
+    "A<<" => Field::new("0100000"),
+    "D<<" => Field::new("0110000"),
+    "M<<" => Field::new("1100000"),
+    "A>>" => Field::new("0000000"),
+    "D>>" => Field::new("0010000"),
+    "M>>" => Field::new("1000000"),
 };
 
+/// Prefix bits of an extended C-instruction, `101`, in place of `111`
+pub const EXTENDED_C_PREFIX: u16 = 0b101 << C_FIELD_BITS;
+
 /// Jump mnemonic to binary code mapping (3 bits)
 ///
 /// Maps jump mnemonics to their 3-bit binary representation.
 /// Empty string represents no jump.
-static JUMP_MAP: phf::Map<&'static str, &'static str> = phf_map! {
-    "" => "000",
-    "JGT" => "001",
-    "JEQ" => "010",
-    "JGE" => "011",
-    "JLT" => "100",
-    "JNE" => "101",
-    "JLE" => "110",
-    "JMP" => "111",
+static JUMP_MAP: phf::Map<&'static str, Field> = phf_map! {
+    "" => Field::new("000"),
+    "JGT" => Field::new("001"),
+    "JEQ" => Field::new("010"),
+    "JGE" => Field::new("011"),
+    "JLT" => Field::new("100"),
+    "JNE" => Field::new("101"),
+    "JLE" => Field::new("110"),
+    "JMP" => Field::new("111"),
 };
 
+/// Word width of the standard Hack machine, in bits
+pub const HACK_WORD_WIDTH: usize = 16;
+
+/// Bits of a C-instruction below its prefix: `acccccc ddd jjj`
+const C_FIELD_BITS: usize = 13;
+
 /// Default values for missing mnemonics
-const DEFAULT_DEST: &str = "000";
-const DEFAULT_COMP: &str = "0101010"; // Computes 0
-const DEFAULT_JUMP: &str = "000";
+const DEFAULT_DEST: Field = Field::new("000");
+const DEFAULT_COMP: Field = Field::new("0101010"); // Computes 0
+const DEFAULT_JUMP: Field = Field::new("000");
 
 /// Translates a destination mnemonic to its binary code
 ///
@@ -96,6 +200,11 @@ const DEFAULT_JUMP: &str = "000";
 #[inline]
 #[must_use]
 pub fn dest(mnemonic: &str) -> &'static str {
+    dest_field(mnemonic).bits
+}
+
+#[inline]
+fn dest_field(mnemonic: &str) -> Field {
     DEST_MAP.get(mnemonic).copied().unwrap_or(DEFAULT_DEST)
 }
 
@@ -112,6 +221,11 @@ pub fn dest(mnemonic: &str) -> &'static str {
 #[inline]
 #[must_use]
 pub fn comp(mnemonic: &str) -> &'static str {
+    comp_field(mnemonic).bits
+}
+
+#[inline]
+fn comp_field(mnemonic: &str) -> Field {
     COMP_MAP.get(mnemonic).copied().unwrap_or(DEFAULT_COMP)
 }
 
@@ -128,6 +242,11 @@ pub fn comp(mnemonic: &str) -> &'static str {
 #[inline]
 #[must_use]
 pub fn jump(mnemonic: &str) -> &'static str {
+    jump_field(mnemonic).bits
+}
+
+#[inline]
+fn jump_field(mnemonic: &str) -> Field {
     JUMP_MAP.get(mnemonic).copied().unwrap_or(DEFAULT_JUMP)
 }
 
@@ -168,6 +287,239 @@ pub fn encode_c_instruction(
     )
 }
 
+/// Encodes a C-instruction directly as its 16-bit word
+///
+/// The numeric counterpart of [`encode_c_instruction`]: the three fields are
+/// shifted into place with no string formatting, for binary emitters and
+/// other hot paths. Unknown mnemonics fall back to the same defaults.
+///
+/// # Example
+/// ```
+/// use project6::code::encode_c_instruction_bits;
+/// assert_eq!(encode_c_instruction_bits("D", "D+1", ""), 0b1110_0111_1101_0000);
+/// ```
+#[inline]
+#[must_use]
+pub fn encode_c_instruction_bits(
+    dest_mnemonic: &str,
+    comp_mnemonic: &str,
+    jump_mnemonic: &str,
+) -> u16 {
+    0b111 << C_FIELD_BITS
+        | comp_field(comp_mnemonic).value << 6
+        | dest_field(dest_mnemonic).value << 3
+        | jump_field(jump_mnemonic).value
+}
+
+/// Strict counterpart of [`dest`]: unknown mnemonics are an error
+///
+/// # Example
+/// ```
+/// use project6::code::{dest_checked, CodeError};
+/// assert_eq!(dest_checked("MD"), Ok("011"));
+/// assert_eq!(dest_checked("X"), Err(CodeError::InvalidDest("X".to_string())));
+/// ```
+#[inline]
+pub fn dest_checked(mnemonic: &str) -> Result<&'static str, CodeError> {
+    DEST_MAP
+        .get(mnemonic)
+        .map(|field| field.bits)
+        .ok_or_else(|| CodeError::InvalidDest(mnemonic.to_string()))
+}
+
+/// Strict counterpart of [`comp`]: unknown mnemonics are an error
+///
+/// A computation that reads both `A` and `M` is reported as
+/// [`CodeError::MixedRegisters`], since the ALU can only use one of them.
+///
+/// # Example
+/// ```
+/// use project6::code::{comp_checked, CodeError};
+/// assert_eq!(comp_checked("D+1"), Ok("0011111"));
+/// assert_eq!(comp_checked("D+2"), Err(CodeError::InvalidComp("D+2".to_string())));
+/// ```
+#[inline]
+pub fn comp_checked(mnemonic: &str) -> Result<&'static str, CodeError> {
+    COMP_MAP
+        .get(mnemonic)
+        .map(|field| field.bits)
+        .ok_or_else(|| {
+            if mnemonic.contains('A') && mnemonic.contains('M') {
+                CodeError::MixedRegisters(mnemonic.to_string())
+            } else {
+                CodeError::InvalidComp(mnemonic.to_string())
+            }
+        })
+}
+
+/// Strict counterpart of [`jump`]: unknown mnemonics are an error
+///
+/// # Example
+/// ```
+/// use project6::code::{jump_checked, CodeError};
+/// assert_eq!(jump_checked("JMP"), Ok("111"));
+/// assert_eq!(jump_checked("JMPP"), Err(CodeError::InvalidJump("JMPP".to_string())));
+/// ```
+#[inline]
+pub fn jump_checked(mnemonic: &str) -> Result<&'static str, CodeError> {
+    JUMP_MAP
+        .get(mnemonic)
+        .map(|field| field.bits)
+        .ok_or_else(|| CodeError::InvalidJump(mnemonic.to_string()))
+}
+
+/// Encodes a C-instruction as its 16-bit word, rejecting unknown mnemonics
+///
+/// The strict counterpart of [`encode_c_instruction_bits`], used by the
+/// assembler so that a typo halts assembly instead of silently becoming
+/// `0`. Fields are checked in `dest`, `comp`, `jump` order and the first
+/// invalid one is reported.
+///
+/// # Example
+/// ```
+/// use project6::code::{encode_c_instruction_checked, CodeError};
+/// assert_eq!(encode_c_instruction_checked("D", "D+1", ""), Ok(0b1110_0111_1101_0000));
+/// assert_eq!(
+///     encode_c_instruction_checked("D", "A+M", ""),
+///     Err(CodeError::MixedRegisters("A+M".to_string()))
+/// );
+/// ```
+#[inline]
+pub fn encode_c_instruction_checked(
+    dest_mnemonic: &str,
+    comp_mnemonic: &str,
+    jump_mnemonic: &str,
+) -> Result<u16, CodeError> {
+    validate_c_instruction(dest_mnemonic, comp_mnemonic, jump_mnemonic)?;
+    Ok(encode_c_instruction_bits(
+        dest_mnemonic,
+        comp_mnemonic,
+        jump_mnemonic,
+    ))
+}
+
+/// Translates an extended-ALU shift mnemonic to its comp bits
+///
+/// Returns `None` for anything else, including every standard computation;
+/// [`comp`] is unaffected by the extension. The bits only mean a shift under
+/// [`EXTENDED_C_PREFIX`] (see [`encode_c_instruction_extended`]).
+///
+/// # Example
+/// ```
+/// use project6::code::comp_extended;
+/// assert_eq!(comp_extended("D<<"), Some("0110000"));
+/// assert_eq!(comp_extended("M>>"), Some("1000000"));
+/// assert_eq!(comp_extended("D+1"), None);
+/// ```
+#[inline]
+#[must_use]
+pub fn comp_extended(mnemonic: &str) -> Option<&'static str> {
+    COMP_MAP_EXTENDED.get(mnemonic).map(|field| field.bits)
+}
+
+/// [`encode_c_instruction_checked`] that also accepts the extended-ALU shifts
+///
+/// A shift computation is encoded under [`EXTENDED_C_PREFIX`]; every other
+/// instruction encodes exactly as in the standard instruction set.
+///
+/// # Example
+/// ```
+/// use project6::code::encode_c_instruction_extended;
+/// assert_eq!(encode_c_instruction_extended("D", "D<<", ""), Ok(0b1010_1100_0001_0000));
+/// assert_eq!(encode_c_instruction_extended("D", "D+1", ""), Ok(0b1110_0111_1101_0000));
+/// assert!(encode_c_instruction_extended("D", "D<<<", "").is_err());
+/// ```
+pub fn encode_c_instruction_extended(
+    dest_mnemonic: &str,
+    comp_mnemonic: &str,
+    jump_mnemonic: &str,
+) -> Result<u16, CodeError> {
+    let Some(comp) = COMP_MAP_EXTENDED.get(comp_mnemonic) else {
+        return encode_c_instruction_checked(dest_mnemonic, comp_mnemonic, jump_mnemonic);
+    };
+    dest_checked(dest_mnemonic)?;
+    jump_checked(jump_mnemonic)?;
+    Ok(EXTENDED_C_PREFIX
+        | comp.value << 6
+        | dest_field(dest_mnemonic).value << 3
+        | jump_field(jump_mnemonic).value)
+}
+
+/// Encodes a C-instruction for a machine with `WIDTH`-bit words
+///
+/// The 13 comp/dest/jump bits keep their meaning; the all-ones prefix grows
+/// to fill the word. `encode_c_instruction_with_width::<16>` is identical
+/// to [`encode_c_instruction`].
+///
+/// # Example
+/// ```
+/// use project6::code::encode_c_instruction_with_width;
+/// assert_eq!(
+///     encode_c_instruction_with_width::<24>("D", "D+1", ""),
+///     "111111111110011111010000"
+/// );
+/// ```
+#[inline]
+#[must_use]
+pub fn encode_c_instruction_with_width<const WIDTH: usize>(
+    dest_mnemonic: &str,
+    comp_mnemonic: &str,
+    jump_mnemonic: &str,
+) -> String {
+    const {
+        assert!(
+            WIDTH >= HACK_WORD_WIDTH,
+            "words narrower than Hack's are not supported"
+        );
+    };
+
+    let mut word = "1".repeat(WIDTH - C_FIELD_BITS);
+    word.push_str(comp(comp_mnemonic));
+    word.push_str(dest(dest_mnemonic));
+    word.push_str(jump(jump_mnemonic));
+    word
+}
+
+/// Composes a C-instruction from raw bit strings, bypassing the mnemonic tables
+///
+/// The escape hatch for ALU control combinations that have no standard
+/// mnemonic. Fields are given in the order they appear in the instruction.
+///
+/// # Arguments
+/// * `comp_bits` - 7 bits: the 'a' bit followed by `cccccc`
+/// * `dest_bits` - 3 bits: `ddd`
+/// * `jump_bits` - 3 bits: `jjj`
+///
+/// # Example
+/// ```
+/// use project6::code::encode_c_bits;
+/// assert_eq!(encode_c_bits("1000010", "010", "000").unwrap(), "1111000010010000"); // D=D+M
+/// assert!(encode_c_bits("100001", "010", "000").is_err());
+/// ```
+pub fn encode_c_bits(
+    comp_bits: &str,
+    dest_bits: &str,
+    jump_bits: &str,
+) -> Result<String, CodeError> {
+    check_bits("comp", comp_bits, 7)?;
+    check_bits("dest", dest_bits, 3)?;
+    check_bits("jump", jump_bits, 3)?;
+    Ok(format!("111{comp_bits}{dest_bits}{jump_bits}"))
+}
+
+/// Ensures `bits` is exactly `width` characters of `0`/`1`
+fn check_bits(field: &'static str, bits: &str, width: usize) -> Result<(), CodeError> {
+    if bits.len() == width && bits.bytes().all(|b| b == b'0' || b == b'1') {
+        Ok(())
+    } else {
+        Err(CodeError::InvalidBits {
+            field,
+            expected_width: width,
+            bits: bits.to_string(),
+        })
+    }
+}
+
 /// Encodes an A-instruction
 ///
 /// A-instruction format: 0vvvvvvvvvvvvvvv (16 bits)
@@ -192,6 +544,146 @@ pub fn encode_a_instruction(address: u16) -> String {
     format!("{address:016b}")
 }
 
+/// Checks that a numeric A-instruction constant fits in 15 bits
+///
+/// # Errors
+/// Returns [`CodeError::AddressOutOfRange`] for values above [`MAX_A_VALUE`].
+///
+/// # Example
+/// ```
+/// use project6::code::validate_a_address;
+///
+/// assert_eq!(validate_a_address(32767), Ok(32767));
+/// assert!(validate_a_address(40000).is_err());
+/// ```
+pub fn validate_a_address(value: u32) -> Result<u16, CodeError> {
+    u16::try_from(value)
+        .ok()
+        .filter(|&address| address <= MAX_A_VALUE)
+        .ok_or_else(|| CodeError::AddressOutOfRange(value.to_string()))
+}
+
+/// Parses the operand of a numeric A-instruction
+///
+/// Numbers are decimal, or carry a one-letter radix suffix: `d` (decimal),
+/// `h` (hexadecimal) or `b` (binary), as in `@64h` or `@1010b`. A `0x`,
+/// `0b` or `0o` prefix works too, as in `@0x7FFF`. Numbers must start with a
+/// decimal digit, which no symbol can, so `@foobarh` or even `@abh` stay
+/// symbols, as does `@0xZ`. Returns `None` for anything that is not a number.
+///
+/// # Errors
+/// The inner result is [`CodeError::AddressOutOfRange`] for values above
+/// [`MAX_A_VALUE`], and [`CodeError::MissingDigits`] for a bare prefix such
+/// as `0b`, which is not read as binary `0` with a `b` suffix.
+///
+/// # Example
+/// ```
+/// use project6::code::parse_a_constant;
+///
+/// assert_eq!(parse_a_constant("007"), Some(Ok(7)));
+/// assert_eq!(parse_a_constant("64h"), Some(Ok(100)));
+/// assert_eq!(parse_a_constant("0x64"), Some(Ok(100)));
+/// assert!(matches!(parse_a_constant("0x"), Some(Err(_))));
+/// assert_eq!(parse_a_constant("LOOP"), None);
+/// ```
+#[must_use]
+pub fn parse_a_constant(text: &str) -> Option<Result<u16, CodeError>> {
+    if !text.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    if matches!(text, "0x" | "0b" | "0o") {
+        return Some(Err(CodeError::MissingDigits(text.to_string())));
+    }
+
+    let (digits, radix) = radix_prefix(text).unwrap_or_else(|| radix_suffix(text));
+    if !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+
+    Some(
+        u32::from_str_radix(digits, radix)
+            .map_err(|_| CodeError::AddressOutOfRange(text.to_string()))
+            .and_then(|value| {
+                validate_a_address(value)
+                    .map_err(|_| CodeError::AddressOutOfRange(text.to_string()))
+            }),
+    )
+}
+
+/// Splits a `0x`, `0b` or `0o` prefix off a number, if it has one
+///
+/// Only applies when the digits after the prefix are valid in its radix, so
+/// that `0bh` is still hexadecimal `0B`.
+fn radix_prefix(text: &str) -> Option<(&str, u32)> {
+    let radix = match text.get(..2)? {
+        "0x" => 16,
+        "0b" => 2,
+        "0o" => 8,
+        _ => return None,
+    };
+    let digits = &text[2..];
+    (!digits.is_empty() && digits.chars().all(|c| c.is_digit(radix))).then_some((digits, radix))
+}
+
+/// Splits a `d`, `h` or `b` suffix off a number; without one it is decimal
+fn radix_suffix(text: &str) -> (&str, u32) {
+    match text.as_bytes()[text.len() - 1] {
+        b'd' => (&text[..text.len() - 1], 10),
+        b'h' => (&text[..text.len() - 1], 16),
+        b'b' => (&text[..text.len() - 1], 2),
+        _ => (text, 10),
+    }
+}
+
+/// Encodes an A-instruction directly as its 16-bit word
+///
+/// The numeric counterpart of [`encode_a_instruction`]. As there, `address`
+/// should be at most 32767 so that the top bit stays clear.
+///
+/// # Example
+/// ```
+/// use project6::code::encode_a_instruction_bits;
+/// assert_eq!(encode_a_instruction_bits(100), 100);
+/// ```
+#[inline]
+#[must_use]
+pub const fn encode_a_instruction_bits(address: u16) -> u16 {
+    address
+}
+
+/// Encodes an A-instruction for a machine with `WIDTH`-bit words
+///
+/// The leading 0 stays a single bit, so the value field is `WIDTH - 1` bits
+/// wide. `encode_a_instruction_with_width::<16>` is identical to
+/// [`encode_a_instruction`].
+///
+/// # Panics
+/// Panics if `value` does not fit in `WIDTH - 1` bits.
+///
+/// # Example
+/// ```
+/// use project6::code::encode_a_instruction_with_width;
+/// assert_eq!(encode_a_instruction_with_width::<24>(100), "000000000000000001100100");
+/// assert_eq!(encode_a_instruction_with_width::<24>(0x7F_FFFF).len(), 24);
+/// ```
+#[inline]
+#[must_use]
+pub fn encode_a_instruction_with_width<const WIDTH: usize>(value: u32) -> String {
+    const {
+        assert!(
+            WIDTH >= HACK_WORD_WIDTH,
+            "words narrower than Hack's are not supported"
+        );
+    };
+    assert!(
+        u64::from(value) < 1 << (WIDTH - 1),
+        "A-instruction value {value} does not fit in {} bits",
+        WIDTH - 1
+    );
+
+    format!("{value:0WIDTH$b}")
+}
+
 /// Validates mnemonics for all three parts of a C-instruction
 ///
 /// Useful for error checking and validation.
@@ -225,10 +717,402 @@ pub fn validate_mnemonics(
     )
 }
 
+/// Validates a C-instruction, explaining the first invalid field
+///
+/// Unlike [`validate_mnemonics`], this recognizes comps that try to combine
+/// the A and M registers (e.g. `A+M`). The 'a' bit selects *either* A or M as
+/// the ALU's second input, so such a computation can never be encoded.
+///
+/// # Example
+/// ```
+/// use project6::code::{validate_c_instruction, CodeError};
+///
+/// assert!(validate_c_instruction("D", "D+M", "").is_ok());
+/// assert_eq!(
+///     validate_c_instruction("D", "A+M", ""),
+///     Err(CodeError::MixedRegisters("A+M".to_string()))
+/// );
+/// ```
+pub fn validate_c_instruction(
+    dest_mnemonic: &str,
+    comp_mnemonic: &str,
+    jump_mnemonic: &str,
+) -> Result<(), CodeError> {
+    dest_checked(dest_mnemonic)?;
+    comp_checked(comp_mnemonic)?;
+    jump_checked(jump_mnemonic)?;
+    Ok(())
+}
+
+/// Encodes a C-instruction only if all three mnemonics are valid
+///
+/// Bridges [`encode_c_instruction`] (which silently substitutes defaults) and
+/// [`validate_mnemonics`]: on failure the validity triple is returned so the
+/// caller knows exactly which field(s) need fixing.
+///
+/// # Returns
+/// The 16-bit binary string, or `Err((dest_valid, comp_valid, jump_valid))`
+///
+/// # Example
+/// ```
+/// use project6::code::try_encode_c_instruction;
+/// assert_eq!(try_encode_c_instruction("D", "D+1", ""), Ok("1110011111010000".to_string()));
+/// assert_eq!(try_encode_c_instruction("D", "D+M1", ""), Err((true, false, true)));
+/// ```
+#[inline]
+pub fn try_encode_c_instruction(
+    dest_mnemonic: &str,
+    comp_mnemonic: &str,
+    jump_mnemonic: &str,
+) -> Result<String, (bool, bool, bool)> {
+    match validate_mnemonics(dest_mnemonic, comp_mnemonic, jump_mnemonic) {
+        (true, true, true) => Ok(encode_c_instruction(
+            dest_mnemonic,
+            comp_mnemonic,
+            jump_mnemonic,
+        )),
+        validity => Err(validity),
+    }
+}
+
+/// Collects a mnemonic table into `(mnemonic, bits)` pairs ordered by code
+fn sorted_entries(
+    map: &'static phf::Map<&'static str, Field>,
+) -> Vec<(&'static str, &'static str)> {
+    let mut entries: Vec<_> = map.entries().collect();
+    entries.sort_by_key(|&(_, field)| field.value);
+    entries
+        .into_iter()
+        .map(|(&mnemonic, field)| (mnemonic, field.bits))
+        .collect()
+}
+
+/// Every dest mnemonic with its 3-bit code, ordered by code
+///
+/// The empty mnemonic (no destination) comes first.
+///
+/// # Example
+/// ```
+/// use project6::code::all_dest_mnemonics;
+/// assert_eq!(all_dest_mnemonics()[0], ("", "000"));
+/// assert_eq!(all_dest_mnemonics()[7], ("AMD", "111"));
+/// ```
+#[must_use]
+pub fn all_dest_mnemonics() -> &'static [(&'static str, &'static str)] {
+    static ENTRIES: OnceLock<Vec<(&'static str, &'static str)>> = OnceLock::new();
+    ENTRIES.get_or_init(|| sorted_entries(&DEST_MAP))
+}
+
+/// Every comp mnemonic with its 7-bit code, ordered by code
+///
+/// # Example
+/// ```
+/// use project6::code::all_comp_mnemonics;
+/// assert_eq!(all_comp_mnemonics().len(), 28);
+/// assert_eq!(all_comp_mnemonics()[0], ("D&A", "0000000"));
+/// ```
+#[must_use]
+pub fn all_comp_mnemonics() -> &'static [(&'static str, &'static str)] {
+    static ENTRIES: OnceLock<Vec<(&'static str, &'static str)>> = OnceLock::new();
+    ENTRIES.get_or_init(|| sorted_entries(&COMP_MAP))
+}
+
+/// Every jump mnemonic with its 3-bit code, ordered by code
+///
+/// The empty mnemonic (no jump) comes first.
+///
+/// # Example
+/// ```
+/// use project6::code::all_jump_mnemonics;
+/// assert_eq!(all_jump_mnemonics()[7], ("JMP", "111"));
+/// ```
+#[must_use]
+pub fn all_jump_mnemonics() -> &'static [(&'static str, &'static str)] {
+    static ENTRIES: OnceLock<Vec<(&'static str, &'static str)>> = OnceLock::new();
+    ENTRIES.get_or_init(|| sorted_entries(&JUMP_MAP))
+}
+
+/// Finds the mnemonic whose field value is `value` in one of the mnemonic tables
+///
+/// A linear scan, but the tables hold at most 28 entries and decoding is
+/// never on the assembly hot path.
+fn mnemonic_for_value(map: &phf::Map<&'static str, Field>, value: u16) -> Option<&'static str> {
+    map.entries()
+        .find(|&(_, field)| field.value == value)
+        .map(|(&mnemonic, _)| mnemonic)
+}
+
+/// A machine word decoded back into assembly, see [`decode_instruction`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodedInstruction {
+    /// `@value`
+    A(u16),
+    /// `dest=comp;jump`, with empty strings for absent fields
+    C {
+        dest: &'static str,
+        comp: &'static str,
+        jump: &'static str,
+    },
+    /// A word that no assembly instruction produces
+    Invalid(u16),
+}
+
+impl fmt::Display for DecodedInstruction {
+    /// Formats the instruction as assembly; invalid words become a comment
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::A(value) => write!(f, "@{value}"),
+            Self::C { dest, comp, jump } => {
+                if !dest.is_empty() {
+                    write!(f, "{dest}=")?;
+                }
+                write!(f, "{comp}")?;
+                if !jump.is_empty() {
+                    write!(f, ";{jump}")?;
+                }
+                Ok(())
+            }
+            Self::Invalid(word) => write!(f, "// invalid instruction {word:016b}"),
+        }
+    }
+}
+
+/// Decodes a machine word back into the assembly instruction it encodes
+///
+/// Words with the top bit clear are A-instructions. The rest must start with
+/// `111` and carry one of the 28 standard comp codes; anything else decodes
+/// to [`DecodedInstruction::Invalid`]. The mnemonic tables used for encoding
+/// are searched in reverse, so both directions share one source of truth.
+///
+/// # Example
+/// ```
+/// use project6::code::{DecodedInstruction, decode_instruction};
+///
+/// assert_eq!(decode_instruction(21), DecodedInstruction::A(21));
+/// assert_eq!(decode_instruction(0xF090).to_string(), "D=D+M");
+/// assert_eq!(decode_instruction(0xEA87).to_string(), "0;JMP");
+/// assert_eq!(decode_instruction(0xC000), DecodedInstruction::Invalid(0xC000));
+/// ```
+#[must_use]
+pub fn decode_instruction(word: u16) -> DecodedInstruction {
+    if word & 0x8000 == 0 {
+        return DecodedInstruction::A(word);
+    }
+    let prefix = 0b111 << C_FIELD_BITS;
+    if word & prefix != prefix {
+        return DecodedInstruction::Invalid(word);
+    }
+
+    let comp = (word >> 6) & 0x7F;
+    let dest = (word >> 3) & 0b111;
+    let jump = word & 0b111;
+    match (
+        mnemonic_for_value(&COMP_MAP, comp),
+        mnemonic_for_value(&DEST_MAP, dest),
+        mnemonic_for_value(&JUMP_MAP, jump),
+    ) {
+        (Some(comp), Some(dest), Some(jump)) => DecodedInstruction::C { dest, comp, jump },
+        _ => DecodedInstruction::Invalid(word),
+    }
+}
+
+/// Encodes a decoded instruction back into its machine word
+///
+/// The inverse of [`decode_instruction`]: for every word that decodes to a
+/// valid instruction, `encode_instruction(decode_instruction(word))` gives
+/// the word back. Returns `None` for [`DecodedInstruction::Invalid`] and for
+/// A-instructions above [`MAX_A_VALUE`].
+///
+/// # Example
+/// ```
+/// use project6::code::{DecodedInstruction, decode_instruction, encode_instruction};
+///
+/// assert_eq!(encode_instruction(decode_instruction(0xF090)), Some(0xF090));
+/// assert_eq!(encode_instruction(DecodedInstruction::A(21)), Some(21));
+/// assert_eq!(encode_instruction(DecodedInstruction::Invalid(0xC000)), None);
+/// ```
+#[must_use]
+pub fn encode_instruction(instruction: DecodedInstruction) -> Option<u16> {
+    match instruction {
+        DecodedInstruction::A(value) => (value <= MAX_A_VALUE).then_some(value),
+        DecodedInstruction::C { dest, comp, jump } => {
+            encode_c_instruction_checked(dest, comp, jump).ok()
+        }
+        DecodedInstruction::Invalid(_) => None,
+    }
+}
+
+/// Disassembles machine code into one line of assembly per word
+///
+/// Jump targets and variables come back as plain numbers, since symbol names
+/// are not stored in `.hack` files.
+///
+/// # Example
+/// ```
+/// use project6::code::disassemble;
+///
+/// assert_eq!(disassemble(&[2, 0xEC10, 0xEA87]), ["@2", "D=A", "0;JMP"]);
+/// ```
+#[must_use]
+pub fn disassemble(words: &[u16]) -> Vec<String> {
+    words
+        .iter()
+        .map(|&word| decode_instruction(word).to_string())
+        .collect()
+}
+
+/// Explains a machine word field by field, for teaching and debugging
+///
+/// The first line shows the word and the assembly it decodes to; each
+/// following line shows one field's bits and meaning.
+///
+/// # Example
+/// ```
+/// use project6::code::explain_instruction;
+///
+/// let explanation = explain_instruction(0b1111_0000_1001_0000);
+/// assert!(explanation.starts_with("1111000010010000 = D=D+M\n"));
+/// assert!(explanation.contains("comp: D+M"));
+/// ```
+#[must_use]
+pub fn explain_instruction(bits: u16) -> String {
+    let word = format!("{bits:016b}");
+
+    if bits & 0x8000 == 0 {
+        let value = bits & MAX_A_VALUE;
+        return format!(
+            "{word} = @{value}\n\
+             \x20 0                A-instruction prefix\n\
+             \x20 {}  value: {value}",
+            &word[1..]
+        );
+    }
+
+    let (prefix, comp_bits, dest_bits, jump_bits) =
+        (&word[..3], &word[3..10], &word[10..13], &word[13..]);
+    let comp_mnemonic = mnemonic_for_value(&COMP_MAP, (bits >> 6) & 0x7F);
+    // Every 3-bit pattern is a valid dest and jump
+    let dest_mnemonic = mnemonic_for_value(&DEST_MAP, (bits >> 3) & 0b111).unwrap_or_default();
+    let jump_mnemonic = mnemonic_for_value(&JUMP_MAP, bits & 0b111).unwrap_or_default();
+
+    let assembly = match comp_mnemonic {
+        Some(comp) => {
+            let mut assembly = String::new();
+            if !dest_mnemonic.is_empty() {
+                assembly.push_str(dest_mnemonic);
+                assembly.push('=');
+            }
+            assembly.push_str(comp);
+            if !jump_mnemonic.is_empty() {
+                assembly.push(';');
+                assembly.push_str(jump_mnemonic);
+            }
+            assembly
+        }
+        None => "invalid C-instruction".to_string(),
+    };
+    let register = if comp_bits.starts_with('1') { "M" } else { "A" };
+
+    format!(
+        "{word} = {assembly}\n\
+         \x20 {prefix}      C-instruction prefix{}\n\
+         \x20 {comp_bits}  comp: {} (a={}: reads {register})\n\
+         \x20 {dest_bits}      dest: {}\n\
+         \x20 {jump_bits}      jump: {}",
+        if prefix == "111" {
+            ""
+        } else {
+            " (unused bits should be 11)"
+        },
+        comp_mnemonic.unwrap_or("no standard mnemonic"),
+        &comp_bits[..1],
+        if dest_mnemonic.is_empty() {
+            "none"
+        } else {
+            dest_mnemonic
+        },
+        if jump_mnemonic.is_empty() {
+            "none"
+        } else {
+            jump_mnemonic
+        },
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_numeric_encoders_match_strings() {
+        for (dest, comp, jump) in [("", "0", "JMP"), ("AMD", "D|M", "JLE"), ("M", "-1", "")] {
+            let text = encode_c_instruction(dest, comp, jump);
+            assert_eq!(
+                encode_c_instruction_bits(dest, comp, jump),
+                u16::from_str_radix(&text, 2).unwrap()
+            );
+        }
+        assert_eq!(encode_a_instruction_bits(32767), 0x7FFF);
+    }
+
+    #[test]
+    #[allow(clippy::unusual_byte_groupings)] // Grouped as prefix, comp, dest, jump
+    fn test_extended_shifts() {
+        for (mnemonic, word) in [
+            ("A<<", 0b101_0100000_010_000),
+            ("D<<", 0b101_0110000_010_000),
+            ("M<<", 0b101_1100000_010_000),
+            ("A>>", 0b101_0000000_010_000),
+            ("D>>", 0b101_0010000_010_000),
+            ("M>>", 0b101_1000000_010_000),
+        ] {
+            assert_eq!(encode_c_instruction_extended("D", mnemonic, ""), Ok(word));
+            // The standard instruction set doesn't know them
+            assert_eq!(comp(mnemonic), DEFAULT_COMP.bits);
+            assert!(comp_checked(mnemonic).is_err());
+        }
+        assert_eq!(
+            encode_c_instruction_extended("AM", "M<<", "JGT"),
+            Ok(0b101_1100000_101_001)
+        );
+        assert_eq!(
+            encode_c_instruction_extended("X", "D<<", ""),
+            Err(CodeError::InvalidDest("X".to_string()))
+        );
+        // No standard computation has an extended encoding
+        for (mnemonic, _) in COMP_MAP.entries() {
+            assert_eq!(comp_extended(mnemonic), None);
+        }
+    }
+
+    #[test]
+    fn test_field_values_match_bits() {
+        for map in [&DEST_MAP, &COMP_MAP, &JUMP_MAP, &COMP_MAP_EXTENDED] {
+            for (mnemonic, field) in map.entries() {
+                assert_eq!(
+                    u16::from_str_radix(field.bits, 2).unwrap(),
+                    field.value,
+                    "{mnemonic}"
+                );
+            }
+        }
+        for comp in COMP_MAP.keys() {
+            assert_eq!(
+                format!("{:016b}", encode_c_instruction_bits("MD", comp, "JNE")),
+                encode_c_instruction("MD", comp, "JNE")
+            );
+        }
+        // Unknown mnemonics take the same defaults as the string encoder
+        assert_eq!(
+            format!("{:016b}", encode_c_instruction_bits("X", "Y", "Z")),
+            encode_c_instruction("X", "Y", "Z")
+        );
+        assert_eq!(
+            format!("{:016b}", encode_a_instruction_bits(1234)),
+            encode_a_instruction(1234)
+        );
+    }
+
     #[test]
     fn test_dest_translations() {
         assert_eq!(dest(""), "000");
@@ -316,6 +1200,161 @@ mod tests {
         assert!(d && c && j);
     }
 
+    #[test]
+    fn test_try_encode_c_instruction() {
+        assert_eq!(
+            try_encode_c_instruction("MD", "M-1", "JEQ"),
+            Ok("1111110010011010".to_string())
+        );
+
+        // Every combination of failing fields reports the matching triple
+        assert_eq!(
+            try_encode_c_instruction("X", "D", ""),
+            Err((false, true, true))
+        );
+        assert_eq!(
+            try_encode_c_instruction("D", "X", ""),
+            Err((true, false, true))
+        );
+        assert_eq!(
+            try_encode_c_instruction("D", "D", "X"),
+            Err((true, true, false))
+        );
+        assert_eq!(
+            try_encode_c_instruction("X", "X", ""),
+            Err((false, false, true))
+        );
+        assert_eq!(
+            try_encode_c_instruction("X", "D", "X"),
+            Err((false, true, false))
+        );
+        assert_eq!(
+            try_encode_c_instruction("D", "X", "X"),
+            Err((true, false, false))
+        );
+        assert_eq!(
+            try_encode_c_instruction("X", "X", "X"),
+            Err((false, false, false))
+        );
+    }
+
+    #[test]
+    fn test_mixed_register_comps_rejected() {
+        for comp in ["A+M", "A&M", "M-A"] {
+            let err = validate_c_instruction("D", comp, "").unwrap_err();
+            assert_eq!(err, CodeError::MixedRegisters(comp.to_string()));
+            assert_eq!(
+                err.to_string(),
+                format!("cannot use both A and M in one computation: '{comp}'")
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_c_instruction_generic_errors() {
+        assert!(validate_c_instruction("AM", "D|M", "JLE").is_ok());
+        assert_eq!(
+            validate_c_instruction("D", "D+M1", ""),
+            Err(CodeError::InvalidComp("D+M1".to_string()))
+        );
+        assert_eq!(
+            validate_c_instruction("X", "D", ""),
+            Err(CodeError::InvalidDest("X".to_string()))
+        );
+        assert_eq!(
+            validate_c_instruction("D", "D", "JMPP"),
+            Err(CodeError::InvalidJump("JMPP".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_checked_lookups_match_lenient_ones() {
+        for &mnemonic in DEST_MAP.keys() {
+            assert_eq!(dest_checked(mnemonic), Ok(dest(mnemonic)));
+        }
+        for &mnemonic in COMP_MAP.keys() {
+            assert_eq!(comp_checked(mnemonic), Ok(comp(mnemonic)));
+        }
+        for &mnemonic in JUMP_MAP.keys() {
+            assert_eq!(jump_checked(mnemonic), Ok(jump(mnemonic)));
+        }
+
+        // The lenient lookups default where the strict ones refuse
+        assert_eq!(comp("D+2"), "0101010");
+        assert!(comp_checked("D+2").is_err());
+        assert_eq!(
+            comp_checked("M+A"),
+            Err(CodeError::MixedRegisters("M+A".to_string()))
+        );
+        assert_eq!(
+            encode_c_instruction_checked("MD", "M-1", "JEQ").map(|word| format!("{word:016b}")),
+            Ok(encode_c_instruction("MD", "M-1", "JEQ"))
+        );
+        assert_eq!(
+            encode_c_instruction_checked("D", "D", "JMPP"),
+            Err(CodeError::InvalidJump("JMPP".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_mnemonic_listings() {
+        fn check(entries: &[(&str, &str)], count: usize, lookup: fn(&str) -> &'static str) {
+            assert_eq!(entries.len(), count);
+            assert!(entries.windows(2).all(|pair| pair[0].1 < pair[1].1));
+            for &(mnemonic, bits) in entries {
+                assert_eq!(lookup(mnemonic), bits, "{mnemonic}");
+            }
+        }
+
+        check(all_dest_mnemonics(), 8, dest);
+        check(all_comp_mnemonics(), 28, comp);
+        check(all_jump_mnemonics(), 8, jump);
+    }
+
+    #[test]
+    fn test_encode_c_bits() {
+        // Matches the mnemonic encoder for standard instructions
+        assert_eq!(
+            encode_c_bits("1110010", "011", "010").unwrap(),
+            encode_c_instruction("MD", "M-1", "JEQ")
+        );
+        // Non-standard ALU combination with no mnemonic
+        assert_eq!(
+            encode_c_bits("0000001", "000", "000").unwrap(),
+            "1110000001000000"
+        );
+    }
+
+    #[test]
+    fn test_encode_c_bits_malformed() {
+        assert_eq!(
+            encode_c_bits("100001", "010", "000"),
+            Err(CodeError::InvalidBits {
+                field: "comp",
+                expected_width: 7,
+                bits: "100001".to_string(),
+            })
+        );
+        assert!(matches!(
+            encode_c_bits("1000010", "0100", "000"),
+            Err(CodeError::InvalidBits { field: "dest", .. })
+        ));
+        assert!(matches!(
+            encode_c_bits("1000010", "010", "0x1"),
+            Err(CodeError::InvalidBits { field: "jump", .. })
+        ));
+        assert!(matches!(
+            encode_c_bits("10000 1", "010", "000"),
+            Err(CodeError::InvalidBits { field: "comp", .. })
+        ));
+        assert_eq!(
+            encode_c_bits("1000010", "01a", "000")
+                .unwrap_err()
+                .to_string(),
+            "dest bits must be exactly 3 binary digits, got '01a'"
+        );
+    }
+
     #[test]
     fn test_all_dest_mnemonics() {
         // Test that all 8 dest combinations work
@@ -337,4 +1376,187 @@ mod tests {
             assert!(result.chars().all(|c| c == '0' || c == '1'));
         }
     }
+
+    /// Encodes a tiny `@2 D=A @3 D=D+A @0 M=D` program at the given width
+    fn encode_add_program<const WIDTH: usize>() -> Vec<String> {
+        vec![
+            encode_a_instruction_with_width::<WIDTH>(2),
+            encode_c_instruction_with_width::<WIDTH>("D", "A", ""),
+            encode_a_instruction_with_width::<WIDTH>(3),
+            encode_c_instruction_with_width::<WIDTH>("D", "D+A", ""),
+            encode_a_instruction_with_width::<WIDTH>(0),
+            encode_c_instruction_with_width::<WIDTH>("M", "D", ""),
+        ]
+    }
+
+    #[test]
+    fn test_encode_with_width() {
+        let standard = encode_add_program::<HACK_WORD_WIDTH>();
+        assert!(standard.iter().all(|word| word.len() == 16));
+        assert_eq!(standard[0], encode_a_instruction(2));
+        assert_eq!(standard[3], encode_c_instruction("D", "D+A", ""));
+
+        let wide = encode_add_program::<24>();
+        assert!(wide.iter().all(|word| word.len() == 24));
+        for (narrow, wide) in standard.iter().zip(&wide) {
+            // Same low 15 value bits or 13 field bits; only the prefix grows
+            assert_eq!(&wide[8..], narrow);
+            let fill = if narrow.starts_with('0') {
+                "00000000"
+            } else {
+                "11111111"
+            };
+            assert_eq!(&wide[..8], fill);
+        }
+    }
+
+    #[test]
+    fn test_wide_a_instruction_range() {
+        assert_eq!(
+            encode_a_instruction_with_width::<24>(1 << 20),
+            format!("0001{}", "0".repeat(20))
+        );
+        let too_big = std::panic::catch_unwind(|| encode_a_instruction_with_width::<16>(1 << 15));
+        assert!(too_big.is_err());
+    }
+
+    #[test]
+    fn test_explain_a_instruction() {
+        let explanation = explain_instruction(100);
+        let lines: Vec<&str> = explanation.lines().collect();
+
+        assert_eq!(lines[0], "0000000001100100 = @100");
+        assert!(lines[1].contains("A-instruction prefix"));
+        assert_eq!(lines[2], "  000000001100100  value: 100");
+    }
+
+    #[test]
+    fn test_explain_c_instruction() {
+        let word = u16::from_str_radix(&encode_c_instruction("MD", "M+1", "JGT"), 2).unwrap();
+        let explanation = explain_instruction(word);
+        let lines: Vec<&str> = explanation.lines().collect();
+
+        assert_eq!(lines[0], "1111110111011001 = MD=M+1;JGT");
+        assert_eq!(lines[1], "  111      C-instruction prefix");
+        assert_eq!(lines[2], "  1110111  comp: M+1 (a=1: reads M)");
+        assert_eq!(lines[3], "  011      dest: MD");
+        assert_eq!(lines[4], "  001      jump: JGT");
+
+        let no_dest = explain_instruction(0b1110_1010_1000_0111); // 0;JMP
+        assert!(no_dest.starts_with("1110101010000111 = 0;JMP\n"));
+        assert!(no_dest.contains("dest: none"));
+        assert!(no_dest.contains("(a=0: reads A)"));
+    }
+
+    #[test]
+    fn test_explain_unusual_c_instruction() {
+        // comp 0000001 has no mnemonic; prefix 100 leaves the unused bits clear
+        let explanation = explain_instruction(0b1000_0000_0100_0000);
+        assert!(explanation.starts_with("1000000001000000 = invalid C-instruction\n"));
+        assert!(explanation.contains("comp: no standard mnemonic"));
+        assert!(explanation.contains("unused bits should be 11"));
+    }
+
+    #[test]
+    fn test_validate_a_address() {
+        assert_eq!(validate_a_address(0), Ok(0));
+        assert_eq!(validate_a_address(MAX_A_VALUE.into()), Ok(MAX_A_VALUE));
+        assert_eq!(
+            validate_a_address(32768),
+            Err(CodeError::AddressOutOfRange("32768".to_string()))
+        );
+        assert_eq!(
+            validate_a_address(40000).unwrap_err().to_string(),
+            "constant 40000 does not fit in an A-instruction (maximum 32767)"
+        );
+    }
+
+    #[test]
+    fn test_parse_a_constant_radix_suffixes() {
+        assert_eq!(parse_a_constant("64h"), Some(Ok(100)));
+        assert_eq!(parse_a_constant("1010b"), Some(Ok(10)));
+        assert_eq!(parse_a_constant("100d"), Some(Ok(100)));
+        assert_eq!(parse_a_constant("7FFFh"), Some(Ok(MAX_A_VALUE)));
+        assert_eq!(parse_a_constant("FFh"), None); // Needs a leading digit: `0FFh`
+        assert_eq!(parse_a_constant("0FFh"), Some(Ok(255)));
+        assert_eq!(
+            parse_a_constant("8000h"),
+            Some(Err(CodeError::AddressOutOfRange("8000h".to_string())))
+        );
+        assert!(matches!(parse_a_constant("99999999999"), Some(Err(_))));
+        assert_eq!(parse_a_constant("foobarh"), None);
+        assert_eq!(parse_a_constant("12b"), None);
+        assert_eq!(parse_a_constant(""), None);
+    }
+
+    #[test]
+    fn test_parse_a_constant_radix_prefixes() {
+        assert_eq!(parse_a_constant("0x7FFF"), Some(Ok(MAX_A_VALUE)));
+        assert_eq!(parse_a_constant("0x7fff"), Some(Ok(MAX_A_VALUE)));
+        assert_eq!(parse_a_constant("0b1010"), Some(Ok(10)));
+        assert_eq!(parse_a_constant("0o17"), Some(Ok(15)));
+        assert_eq!(parse_a_constant("0100"), Some(Ok(100)));
+        assert_eq!(
+            parse_a_constant("0x8000"),
+            Some(Err(CodeError::AddressOutOfRange("0x8000".to_string())))
+        );
+        assert!(matches!(
+            parse_a_constant("0b11111111111111111111111111111111111"),
+            Some(Err(_))
+        ));
+        // Suffixes still win when the prefix reading does not fit, but a
+        // bare prefix is an error rather than a suffixed `0`
+        assert_eq!(parse_a_constant("0bh"), Some(Ok(11)));
+        for prefix in ["0x", "0b", "0o"] {
+            assert_eq!(
+                parse_a_constant(prefix),
+                Some(Err(CodeError::MissingDigits(prefix.to_string())))
+            );
+        }
+        assert_eq!(
+            CodeError::MissingDigits("0b".to_string()).to_string(),
+            "constant 0b has no digits"
+        );
+        for symbol in ["0xZZ", "0b102", "0o8", "0x1Fh"] {
+            assert_eq!(parse_a_constant(symbol), None, "{symbol}");
+        }
+    }
+
+    #[test]
+    fn test_decode_instruction_round_trip() {
+        for comp in COMP_MAP.keys() {
+            for dest in DEST_MAP.keys() {
+                for jump in JUMP_MAP.keys() {
+                    let word = encode_c_instruction_bits(dest, comp, jump);
+                    let instruction = DecodedInstruction::C { dest, comp, jump };
+                    assert_eq!(decode_instruction(word), instruction);
+                    assert_eq!(encode_instruction(instruction), Some(word));
+                }
+            }
+        }
+        assert_eq!(decode_instruction(0), DecodedInstruction::A(0));
+        assert_eq!(
+            encode_instruction(DecodedInstruction::A(MAX_A_VALUE)),
+            Some(MAX_A_VALUE)
+        );
+        assert_eq!(decode_instruction(MAX_A_VALUE).to_string(), "@32767");
+    }
+
+    #[test]
+    fn test_decode_invalid_instructions() {
+        // 0b0000001 is not a standard comp code
+        let unused_comp = 0b1110_0000_0100_0000;
+        assert_eq!(
+            decode_instruction(unused_comp),
+            DecodedInstruction::Invalid(unused_comp)
+        );
+        // Top bit set but the unused bits are not `11`
+        for word in [0x8000 | 0x0C10, 0xA000 | 0x0C10, 0xC000 | 0x0C10] {
+            assert_eq!(decode_instruction(word), DecodedInstruction::Invalid(word));
+        }
+        assert_eq!(
+            decode_instruction(0x8000).to_string(),
+            "// invalid instruction 1000000000000000"
+        );
+    }
 }
@@ -0,0 +1,112 @@
+//! Project-wide assembler defaults from a `.hackasm.toml` file
+//!
+//! A project can keep its usual settings in [`CONFIG_FILE_NAME`] in the
+//! working directory instead of repeating them on every invocation:
+//!
+//! ```toml
+//! format = "hex"        # bits, padded32, hex, raw or ihex
+//! endian = "big"        # little or big
+//! var-base = 64         # RAM address of the first variable
+//! lint = true
+//! allow = ["unreachable"]
+//! optimize = false
+//! ```
+//!
+//! Every key is optional and command-line flags take precedence. Reading the
+//! file needs the `config` feature.
+
+use crate::assembler::AssemblerConfig;
+use crate::emit::{Endian, OutputFormat};
+
+/// Name of the configuration file looked up in the working directory
+pub const CONFIG_FILE_NAME: &str = ".hackasm.toml";
+
+/// Settings read from a configuration file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectConfig {
+    /// Output format, if the file sets one
+    pub format: Option<OutputFormat>,
+    /// Byte order of binary formats, if the file sets one
+    pub endian: Option<Endian>,
+    /// Lint, optimization and variable placement settings
+    pub assembler: AssemblerConfig,
+}
+
+/// The file's layout before the string-valued keys are validated
+#[cfg(feature = "config")]
+#[derive(Default, serde::Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct RawConfig {
+    format: Option<String>,
+    endian: Option<String>,
+    var_base: Option<u16>,
+    lint: bool,
+    allow: Vec<String>,
+    optimize: bool,
+}
+
+#[cfg(feature = "config")]
+impl ProjectConfig {
+    /// Parses the contents of a configuration file
+    ///
+    /// Unknown keys are rejected so that typos don't go unnoticed.
+    ///
+    /// # Example
+    /// ```
+    /// use project6::config::ProjectConfig;
+    /// use project6::emit::OutputFormat;
+    ///
+    /// let config = ProjectConfig::from_toml("format = \"hex\"\nlint = true").unwrap();
+    /// assert_eq!(config.format, Some(OutputFormat::Hex));
+    /// assert!(config.assembler.lint);
+    /// ```
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        let raw: RawConfig = toml::from_str(text).map_err(|error| error.to_string())?;
+        Ok(Self {
+            format: raw.format.as_deref().map(str::parse).transpose()?,
+            endian: raw.endian.as_deref().map(str::parse).transpose()?,
+            assembler: AssemblerConfig {
+                lint: raw.lint,
+                allowed_lints: raw.allow,
+                optimize: raw.optimize,
+                variable_base: raw.var_base,
+                ..AssemblerConfig::default()
+            },
+        })
+    }
+
+    /// Reads [`CONFIG_FILE_NAME`] from `dir`, or returns `None` if it is absent
+    pub fn load(dir: &std::path::Path) -> Result<Option<Self>, String> {
+        let path = dir.join(CONFIG_FILE_NAME);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(format!("{}: {error}", path.display())),
+        };
+        Self::from_toml(&text)
+            .map(Some)
+            .map_err(|error| format!("{}: {error}", path.display()))
+    }
+}
+
+#[cfg(all(test, feature = "config"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_toml() {
+        let config = ProjectConfig::from_toml(
+            "format = \"padded32\"\nendian = \"big\"\nvar-base = 64\nallow = [\"unreachable\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.format, Some(OutputFormat::Padded32));
+        assert_eq!(config.endian, Some(Endian::Big));
+        assert_eq!(config.assembler.variable_base, Some(64));
+        assert_eq!(config.assembler.allowed_lints, ["unreachable"]);
+        assert!(!config.assembler.optimize);
+
+        assert_eq!(ProjectConfig::from_toml(""), Ok(ProjectConfig::default()));
+        assert!(ProjectConfig::from_toml("format = \"octal\"").is_err());
+        assert!(ProjectConfig::from_toml("formt = \"hex\"").is_err());
+    }
+}
@@ -0,0 +1,67 @@
+//! Parameters of the standard Hack platform
+//!
+//! Every module that depends on the memory map or the word layout takes its
+//! numbers from here, so a platform variant only needs to change them once.
+
+/// Number of words in the instruction ROM
+pub const ROM_SIZE: usize = 32768;
+
+/// Words of RAM addressable by the 15-bit A register
+pub const RAM_SIZE: usize = 32768;
+
+/// First RAM address of the memory-mapped screen (`SCREEN`)
+pub const SCREEN_BASE: u16 = 16384;
+
+/// RAM address of the memory-mapped keyboard (`KBD`)
+pub const KBD_ADDR: u16 = 24576;
+
+/// RAM address of the first variable, just past `R0`-`R15`
+pub const VAR_BASE: u16 = 16;
+
+/// Last RAM address of the static/variable area (`RAM[16..=255]`)
+///
+/// Addresses above it belong to the VM stack on the full Hack platform.
+pub const VARIABLE_AREA_END: u16 = 255;
+
+/// Largest constant an A-instruction can load: the top bit marks C-instructions
+pub const MAX_A_VALUE: u16 = 0x7FFF;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::validate_a_address;
+    use crate::hack::{HackError, validate_hack};
+    use crate::symbol_table::{SymbolTable, predefined_address};
+
+    #[test]
+    fn test_documented_values() {
+        assert_eq!(ROM_SIZE, 1 << 15);
+        assert_eq!(RAM_SIZE, 1 << 15);
+        assert_eq!(SCREEN_BASE, 0x4000);
+        assert_eq!(KBD_ADDR, SCREEN_BASE + 8192);
+        assert_eq!(VAR_BASE, 16);
+        assert_eq!(VARIABLE_AREA_END, 255);
+        assert_eq!(usize::from(MAX_A_VALUE), RAM_SIZE - 1);
+    }
+
+    #[test]
+    fn test_validation_uses_the_constants() {
+        assert_eq!(predefined_address("SCREEN"), Some(SCREEN_BASE));
+        assert_eq!(predefined_address("KBD"), Some(KBD_ADDR));
+        assert_eq!(SymbolTable::new().first_variable_address(), VAR_BASE);
+
+        let max = u32::from(MAX_A_VALUE);
+        assert_eq!(validate_a_address(max), Ok(MAX_A_VALUE));
+        assert!(validate_a_address(max + 1).is_err());
+
+        let rom = vec!["0000000000000000"; ROM_SIZE];
+        assert!(validate_hack(&rom).is_ok());
+        let rom = vec!["0000000000000000"; ROM_SIZE + 1];
+        assert_eq!(
+            validate_hack(&rom),
+            Err(HackError::TooLong {
+                count: ROM_SIZE + 1
+            })
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! Diagnostics shared by the linter and the assembler
+//!
+//! Both lint warnings and assembly errors are reported as [`Diagnostic`]s so
+//! that tools can treat them uniformly.
+
+use std::fmt;
+
+/// A warning or error attached to a point in the program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// ROM address of the offending instruction, if there is one
+    pub address: Option<usize>,
+    /// 1-based source line of the offending command, if known
+    pub line: Option<usize>,
+    /// Human-readable description of the problem
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic that applies to the whole program
+    #[must_use]
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            address: None,
+            line: None,
+            message: message.into(),
+        }
+    }
+
+    /// Creates a diagnostic attached to the instruction at `address`
+    #[must_use]
+    pub fn at(address: usize, message: impl Into<String>) -> Self {
+        Self {
+            address: Some(address),
+            line: None,
+            message: message.into(),
+        }
+    }
+
+    /// Attaches the 1-based source line the diagnostic refers to
+    #[must_use]
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.address) {
+            (Some(line), _) => write!(f, "line {line}: {}", self.message),
+            (None, Some(address)) => write!(f, "instruction {address}: {}", self.message),
+            (None, None) => write!(f, "{}", self.message),
+        }
+    }
+}
@@ -0,0 +1,532 @@
+//! Output formats for assembled machine code
+//!
+//! The assembler collects the program as a `Vec<u16>` of instruction words
+//! and serializes it in one of the [`OutputFormat`]s below. The textual
+//! `.hack` format stays the default. Packed `.hackbin` ROM images are written
+//! by the [`binary`] submodule.
+
+pub mod binary;
+
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// How instruction words are written to the output file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Standard `.hack`: one line of 16 `0`/`1` characters per instruction
+    #[default]
+    Bits,
+    /// 32-bit records, the instruction zero-extended
+    ///
+    /// Intended for FPGA loaders that expect word-aligned 32-bit records.
+    /// Records are little-endian unless another [`Endian`] is requested.
+    Padded32,
+    /// One line of four uppercase hexadecimal digits per instruction
+    Hex,
+    /// Two raw bytes per instruction, little-endian unless another
+    /// [`Endian`] is requested
+    Raw,
+    /// Intel HEX records of up to eight instructions each
+    ///
+    /// Addresses count bytes, so instruction `n` lives at address `2n`, and
+    /// each instruction is two data bytes in the requested [`Endian`] order.
+    /// A full 32K ROM ends at address `0xFFFF`, so no extended address
+    /// records are needed. See [`parse_intel_hex`] to read it back.
+    IntelHex,
+}
+
+impl OutputFormat {
+    /// Number of bytes each instruction occupies in this format
+    ///
+    /// `None` for [`OutputFormat::IntelHex`], whose records add framing
+    /// around groups of instructions.
+    #[inline]
+    #[must_use]
+    pub const fn bytes_per_word(self) -> Option<usize> {
+        match self {
+            Self::Bits => Some(17),
+            Self::Padded32 => Some(4),
+            Self::Hex => Some(5),
+            Self::Raw => Some(2),
+            Self::IntelHex => None,
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bits" => Ok(Self::Bits),
+            "padded32" => Ok(Self::Padded32),
+            "hex" => Ok(Self::Hex),
+            "raw" => Ok(Self::Raw),
+            "ihex" => Ok(Self::IntelHex),
+            _ => Err(format!(
+                "unknown output format '{s}' (expected bits, padded32, hex, raw or ihex)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Bits => "bits",
+            Self::Padded32 => "padded32",
+            Self::Hex => "hex",
+            Self::Raw => "raw",
+            Self::IntelHex => "ihex",
+        })
+    }
+}
+
+/// Byte order of binary output formats
+///
+/// Textual formats are unaffected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endian {
+    /// Least significant byte first
+    #[default]
+    Little,
+    /// Most significant byte first
+    Big,
+}
+
+impl Endian {
+    /// Serializes a 16-bit word in this byte order
+    ///
+    /// # Example
+    /// ```
+    /// use project6::emit::Endian;
+    ///
+    /// assert_eq!(Endian::Little.u16_bytes(0x1234), [0x34, 0x12]);
+    /// assert_eq!(Endian::Big.u16_bytes(0x1234), [0x12, 0x34]);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn u16_bytes(self, word: u16) -> [u8; 2] {
+        match self {
+            Self::Little => word.to_le_bytes(),
+            Self::Big => word.to_be_bytes(),
+        }
+    }
+
+    /// Serializes a 32-bit record in this byte order
+    #[inline]
+    #[must_use]
+    pub fn u32_bytes(self, record: u32) -> [u8; 4] {
+        match self {
+            Self::Little => record.to_le_bytes(),
+            Self::Big => record.to_be_bytes(),
+        }
+    }
+}
+
+impl FromStr for Endian {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "little" => Ok(Self::Little),
+            "big" => Ok(Self::Big),
+            _ => Err(format!("unknown byte order '{s}' (expected little or big)")),
+        }
+    }
+}
+
+impl fmt::Display for Endian {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Little => "little",
+            Self::Big => "big",
+        })
+    }
+}
+
+/// Writes `words` to `writer` in the requested format
+///
+/// Binary formats use little-endian byte order; see
+/// [`write_words_with_endian`] to choose another.
+///
+/// # Example
+/// ```
+/// use project6::emit::{write_words, OutputFormat};
+///
+/// let mut out = Vec::new();
+/// write_words(&mut out, &[5, 0xEC10], OutputFormat::Bits).unwrap();
+/// assert_eq!(out, b"0000000000000101\n1110110000010000\n");
+/// ```
+pub fn write_words<W: Write>(
+    writer: &mut W,
+    words: &[u16],
+    format: OutputFormat,
+) -> io::Result<()> {
+    write_words_with_endian(writer, words, format, Endian::Little)
+}
+
+/// Writes `words` to `writer` in the requested format and byte order
+///
+/// # Example
+/// ```
+/// use project6::emit::{write_words_with_endian, Endian, OutputFormat};
+///
+/// let mut out = Vec::new();
+/// write_words_with_endian(&mut out, &[0x1234], OutputFormat::Padded32, Endian::Big).unwrap();
+/// assert_eq!(out, [0x00, 0x00, 0x12, 0x34]);
+/// ```
+pub fn write_words_with_endian<W: Write>(
+    writer: &mut W,
+    words: &[u16],
+    format: OutputFormat,
+    endian: Endian,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Bits => {
+            for word in words {
+                writeln!(writer, "{word:016b}")?;
+            }
+        }
+        OutputFormat::Padded32 => {
+            for &word in words {
+                writer.write_all(&endian.u32_bytes(u32::from(word)))?;
+            }
+        }
+        OutputFormat::Hex => {
+            for word in words {
+                writeln!(writer, "{word:04X}")?;
+            }
+        }
+        OutputFormat::Raw => {
+            for &word in words {
+                writer.write_all(&endian.u16_bytes(word))?;
+            }
+        }
+        OutputFormat::IntelHex => write_intel_hex(writer, words, endian)?,
+    }
+    writer.flush()
+}
+
+/// Instructions per Intel HEX data record (16 data bytes)
+const INTEL_HEX_WORDS_PER_RECORD: usize = 8;
+
+/// Length of a full data record line: `:`, 8 header digits, 32 data digits,
+/// 2 checksum digits and the newline
+const INTEL_HEX_RECORD_LEN: usize = 1 + 8 + 4 * INTEL_HEX_WORDS_PER_RECORD + 2 + 1;
+
+/// Intel HEX record types used here
+const INTEL_HEX_DATA: u8 = 0x00;
+const INTEL_HEX_EOF: u8 = 0x01;
+
+/// The end-of-file record that closes every Intel HEX file
+const INTEL_HEX_EOF_RECORD: &str = ":00000001FF";
+
+/// The two's complement of the byte sum, making the record sum to zero
+fn intel_hex_checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+        .wrapping_neg()
+}
+
+fn write_intel_hex<W: Write>(writer: &mut W, words: &[u16], endian: Endian) -> io::Result<()> {
+    for (record, chunk) in words.chunks(INTEL_HEX_WORDS_PER_RECORD).enumerate() {
+        let address = u16::try_from(record * 2 * INTEL_HEX_WORDS_PER_RECORD).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "program is too large for 16-bit Intel HEX addresses",
+            )
+        })?;
+        let [address_high, address_low] = address.to_be_bytes();
+        let data: Vec<u8> = chunk
+            .iter()
+            .flat_map(|&word| endian.u16_bytes(word))
+            .collect();
+
+        // At most 16 data bytes, so the length always fits
+        #[allow(clippy::cast_possible_truncation)]
+        let mut record = vec![data.len() as u8, address_high, address_low, INTEL_HEX_DATA];
+        record.extend(&data);
+        record.push(intel_hex_checksum(&record));
+
+        write!(writer, ":")?;
+        for byte in record {
+            write!(writer, "{byte:02X}")?;
+        }
+        writeln!(writer)?;
+    }
+    writeln!(writer, "{INTEL_HEX_EOF_RECORD}")
+}
+
+/// Reads Intel HEX text back into instruction words
+///
+/// The inverse of [`OutputFormat::IntelHex`] with the same `endian`: checks
+/// every checksum, places data by address and stops at the end-of-file
+/// record. Addresses never written read back as 0.
+///
+/// # Example
+/// ```
+/// use project6::emit::{parse_intel_hex, write_words, Endian, OutputFormat};
+///
+/// let mut out = Vec::new();
+/// write_words(&mut out, &[2, 0xEC10], OutputFormat::IntelHex).unwrap();
+/// let text = String::from_utf8(out).unwrap();
+/// assert_eq!(parse_intel_hex(&text, Endian::Little), Ok(vec![2, 0xEC10]));
+/// ```
+pub fn parse_intel_hex(text: &str, endian: Endian) -> Result<Vec<u16>, String> {
+    let mut bytes = Vec::new();
+
+    for (number, line) in text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+    {
+        if line.is_empty() {
+            continue;
+        }
+        let digits = line
+            .strip_prefix(':')
+            .ok_or_else(|| format!("line {number}: record does not start with ':'"))?;
+        if digits.len() % 2 != 0 || digits.len() < 10 {
+            return Err(format!("line {number}: malformed record"));
+        }
+        let record = (0..digits.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| format!("line {number}: invalid hex digit"))?;
+
+        let length = usize::from(record[0]);
+        if record.len() != length + 5 {
+            return Err(format!("line {number}: length does not match the data"));
+        }
+        if intel_hex_checksum(&record) != 0 {
+            return Err(format!("line {number}: checksum mismatch"));
+        }
+
+        match record[3] {
+            INTEL_HEX_DATA => {
+                let address = usize::from(u16::from_be_bytes([record[1], record[2]]));
+                let end = address + length;
+                if bytes.len() < end {
+                    bytes.resize(end, 0);
+                }
+                bytes[address..end].copy_from_slice(&record[4..4 + length]);
+            }
+            INTEL_HEX_EOF => break,
+            kind => return Err(format!("line {number}: unsupported record type {kind:02X}")),
+        }
+    }
+
+    Ok(bytes
+        .chunks(2)
+        .map(|pair| match endian {
+            Endian::Little => u16::from_le_bytes([pair[0], pair.get(1).copied().unwrap_or(0)]),
+            Endian::Big => u16::from_be_bytes([pair[0], pair.get(1).copied().unwrap_or(0)]),
+        })
+        .collect())
+}
+
+/// Returns the byte offset of each of `count` instructions in `format` output
+///
+/// Entry `n` is where ROM address `n` starts in the written file, for tools
+/// that patch single instructions in place. Fixed-width formats are evenly
+/// spaced, textual ones including the newline. For Intel HEX the offset is
+/// that of the instruction's data digits inside its record; a patch there
+/// must also update the record's checksum.
+///
+/// # Example
+/// ```
+/// use project6::emit::{binary_offset_map, OutputFormat};
+///
+/// assert_eq!(binary_offset_map(OutputFormat::Padded32, 3), [0, 4, 8]);
+/// ```
+#[must_use]
+pub fn binary_offset_map(format: OutputFormat, count: usize) -> Vec<usize> {
+    (0..count)
+        .map(|address| {
+            if let Some(width) = format.bytes_per_word() {
+                return address * width;
+            }
+            let record = address / INTEL_HEX_WORDS_PER_RECORD;
+            let slot = address % INTEL_HEX_WORDS_PER_RECORD;
+            // Skip the `:` and the length, address and type digits
+            record * INTEL_HEX_RECORD_LEN + 1 + 8 + slot * 4
+        })
+        .collect()
+}
+
+/// Returns how many bytes `instruction_count` instructions take in `format`
+///
+/// Computed without serializing anything, e.g. to show a file's size before
+/// writing it. A `.hackbin` image is the size of [`OutputFormat::Raw`].
+///
+/// # Example
+/// ```
+/// use project6::emit::{output_size_bytes, OutputFormat};
+///
+/// assert_eq!(output_size_bytes(100, OutputFormat::Bits), 1700);
+/// assert_eq!(output_size_bytes(100, OutputFormat::Raw), 200);
+/// ```
+#[must_use]
+pub fn output_size_bytes(instruction_count: usize, format: OutputFormat) -> usize {
+    if let Some(width) = format.bytes_per_word() {
+        return instruction_count * width;
+    }
+
+    let full_records = instruction_count / INTEL_HEX_WORDS_PER_RECORD;
+    let remainder = instruction_count % INTEL_HEX_WORDS_PER_RECORD;
+    let partial_record = if remainder == 0 {
+        0
+    } else {
+        INTEL_HEX_RECORD_LEN - 4 * (INTEL_HEX_WORDS_PER_RECORD - remainder)
+    };
+    full_records * INTEL_HEX_RECORD_LEN + partial_record + INTEL_HEX_EOF_RECORD.len() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_size_matches_written_size() {
+        for count in [0, 1, 7, 8, 9, 20] {
+            let words: Vec<u16> = (0..count).map(|n| 0xEC10 ^ n).collect();
+            for format in [
+                OutputFormat::Bits,
+                OutputFormat::Padded32,
+                OutputFormat::Hex,
+                OutputFormat::Raw,
+                OutputFormat::IntelHex,
+            ] {
+                let mut out = Vec::new();
+                write_words(&mut out, &words, format).unwrap();
+                assert_eq!(
+                    output_size_bytes(words.len(), format),
+                    out.len(),
+                    "{format} with {count} instructions"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_parsing() {
+        assert_eq!("bits".parse(), Ok(OutputFormat::Bits));
+        assert_eq!("padded32".parse(), Ok(OutputFormat::Padded32));
+        assert_eq!("hex".parse(), Ok(OutputFormat::Hex));
+        assert_eq!("raw".parse(), Ok(OutputFormat::Raw));
+        assert_eq!("ihex".parse(), Ok(OutputFormat::IntelHex));
+        assert!("elf".parse::<OutputFormat>().is_err());
+        assert_eq!(OutputFormat::default(), OutputFormat::Bits);
+    }
+
+    #[test]
+    fn test_padded32_layout() {
+        // @2, D=A, @3
+        let mut out = Vec::new();
+        write_words(&mut out, &[0x0002, 0xEC10, 0x0003], OutputFormat::Padded32).unwrap();
+        assert_eq!(
+            out,
+            [
+                0x02, 0x00, 0x00, 0x00, // @2
+                0x10, 0xEC, 0x00, 0x00, // D=A
+                0x03, 0x00, 0x00, 0x00, // @3
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hex_lines() {
+        let mut out = Vec::new();
+        write_words(&mut out, &[0x0002, 0xEC10], OutputFormat::Hex).unwrap();
+        assert_eq!(out, b"0002\nEC10\n");
+    }
+
+    #[test]
+    fn test_offset_maps() {
+        assert_eq!(binary_offset_map(OutputFormat::Bits, 3), [0, 17, 34]);
+        assert_eq!(binary_offset_map(OutputFormat::Raw, 4), [0, 2, 4, 6]);
+        assert!(binary_offset_map(OutputFormat::Hex, 0).is_empty());
+
+        // Each offset is where that word's own encoding starts
+        let words = [0x0002, 0xEC10, 0x0003];
+        for format in [
+            OutputFormat::Bits,
+            OutputFormat::Padded32,
+            OutputFormat::Hex,
+            OutputFormat::Raw,
+        ] {
+            let mut out = Vec::new();
+            write_words(&mut out, &words, format).unwrap();
+            let mut second = Vec::new();
+            write_words(&mut second, &words[1..2], format).unwrap();
+
+            let offsets = binary_offset_map(format, words.len());
+            assert_eq!(out[offsets[1]..offsets[2]], second, "{format}");
+            assert_eq!(
+                out.len(),
+                offsets[2] + format.bytes_per_word().unwrap(),
+                "{format}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_intel_hex_records() {
+        let mut out = Vec::new();
+        write_words(&mut out, &[0x0002, 0xEC10], OutputFormat::IntelHex).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            ":04000000020010ECFE\n:00000001FF\n"
+        );
+    }
+
+    #[test]
+    fn test_intel_hex_round_trip() {
+        // Spans two records, the second one partial
+        let words: Vec<u16> = (0..11).map(|n| 0x1111 * n).collect();
+        for endian in [Endian::Little, Endian::Big] {
+            let mut out = Vec::new();
+            write_words_with_endian(&mut out, &words, OutputFormat::IntelHex, endian).unwrap();
+            let text = String::from_utf8(out).unwrap();
+
+            assert_eq!(text.lines().count(), 3);
+            assert_eq!(parse_intel_hex(&text, endian), Ok(words.clone()));
+
+            let offsets = binary_offset_map(OutputFormat::IntelHex, words.len());
+            let [high, low] = endian.u16_bytes(words[9]);
+            assert_eq!(
+                &text[offsets[9]..offsets[9] + 4],
+                format!("{high:02X}{low:02X}")
+            );
+        }
+
+        assert!(parse_intel_hex(":04000000020010ECFF\n", Endian::Little).is_err());
+        assert!(parse_intel_hex("04000000020010ECFE\n", Endian::Little).is_err());
+    }
+
+    #[test]
+    fn test_byte_orders() {
+        assert_eq!(Endian::Little.u16_bytes(0x1234), [0x34, 0x12]);
+        assert_eq!(Endian::Big.u16_bytes(0x1234), [0x12, 0x34]);
+        assert_eq!("big".parse(), Ok(Endian::Big));
+        assert!("middle".parse::<Endian>().is_err());
+        assert_eq!(Endian::default(), Endian::Little);
+
+        let write = |endian| {
+            let mut out = Vec::new();
+            write_words_with_endian(&mut out, &[0x1234], OutputFormat::Padded32, endian).unwrap();
+            out
+        };
+        assert_eq!(write(Endian::Little), [0x34, 0x12, 0x00, 0x00]);
+        assert_eq!(write(Endian::Big), [0x00, 0x00, 0x12, 0x34]);
+
+        // Text is byte-order independent
+        let mut text = Vec::new();
+        write_words_with_endian(&mut text, &[0x1234], OutputFormat::Bits, Endian::Big).unwrap();
+        assert_eq!(text, b"0001001000110100\n");
+    }
+}
@@ -0,0 +1,46 @@
+//! Packed `.hackbin` ROM images
+//!
+//! A `.hackbin` file holds each instruction as two raw bytes, most
+//! significant byte first, with no header: instruction `n` is at byte `2n`.
+//! It is an eighth the size of the textual `.hack` format and can be loaded
+//! by an emulator without any parsing.
+
+use std::io::{self, Write};
+
+/// File extension of packed ROM images
+pub const HACKBIN_EXTENSION: &str = "hackbin";
+
+/// Writes `words` as a `.hackbin` image: big-endian `u16`s, back to back
+///
+/// # Example
+/// ```
+/// use project6::emit::binary::write_binary_rom;
+///
+/// let mut out = Vec::new();
+/// write_binary_rom(&mut out, &[2, 0xEC10]).unwrap();
+/// assert_eq!(out, [0x00, 0x02, 0xEC, 0x10]);
+/// ```
+pub fn write_binary_rom<W: Write>(writer: &mut W, words: &[u16]) -> io::Result<()> {
+    for &word in words {
+        writer.write_all(&word.to_be_bytes())?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emit::{Endian, OutputFormat, write_words_with_endian};
+
+    #[test]
+    fn test_matches_big_endian_raw_output() {
+        let words = [0, 0x7FFF, 0xEA87, 0x1234];
+        let mut packed = Vec::new();
+        write_binary_rom(&mut packed, &words).unwrap();
+        let mut raw = Vec::new();
+        write_words_with_endian(&mut raw, &words, OutputFormat::Raw, Endian::Big).unwrap();
+
+        assert_eq!(packed.len(), 2 * words.len());
+        assert_eq!(packed, raw);
+    }
+}
@@ -0,0 +1,143 @@
+//! Reading and validating Hack machine code (`.hack` files)
+//!
+//! This is the input side of the toolchain: a loader or verifier consuming
+//! assembler output can check a file is well-formed before trusting it.
+
+use std::fmt;
+
+use crate::consts::ROM_SIZE;
+
+/// Width of a Hack instruction word in binary digits
+const WORD_WIDTH: usize = 16;
+
+/// A malformed `.hack` file
+///
+/// Line numbers are 1-based, matching what an editor shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HackError {
+    /// A line that is not exactly 16 characters long
+    WrongWidth { line: usize, content: String },
+    /// A 16-character line containing something other than `0` or `1`
+    NonBinary { line: usize, content: String },
+    /// More instructions than fit in ROM
+    TooLong { count: usize },
+}
+
+impl std::error::Error for HackError {}
+
+impl fmt::Display for HackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::WrongWidth { line, content } => write!(
+                f,
+                "line {line}: expected {WORD_WIDTH} binary digits, got {} characters: '{content}'",
+                content.chars().count()
+            ),
+            Self::NonBinary { line, content } => {
+                write!(f, "line {line}: non-binary character in '{content}'")
+            }
+            Self::TooLong { count } => write!(
+                f,
+                "program has {count} instructions but ROM holds only {ROM_SIZE}"
+            ),
+        }
+    }
+}
+
+/// Checks that every line is a 16-digit binary word and the program fits in ROM
+///
+/// The first malformed line is reported with its content; the ROM limit is
+/// only checked once every line is well-formed.
+///
+/// # Example
+/// ```
+/// use project6::hack::{validate_hack, HackError};
+///
+/// assert!(validate_hack(&["0000000000000010", "1110110000010000"]).is_ok());
+/// assert_eq!(
+///     validate_hack(&["0000000000000010", "111011000001000"]),
+///     Err(HackError::WrongWidth { line: 2, content: "111011000001000".to_string() })
+/// );
+/// ```
+pub fn validate_hack(lines: &[&str]) -> Result<(), HackError> {
+    for (index, &content) in lines.iter().enumerate() {
+        let line = index + 1;
+        // Characters, not bytes, so the count matches the error message
+        if content.chars().count() != WORD_WIDTH {
+            return Err(HackError::WrongWidth {
+                line,
+                content: content.to_string(),
+            });
+        }
+        if !content.bytes().all(|bit| bit == b'0' || bit == b'1') {
+            return Err(HackError::NonBinary {
+                line,
+                content: content.to_string(),
+            });
+        }
+    }
+
+    if lines.len() > ROM_SIZE {
+        return Err(HackError::TooLong { count: lines.len() });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_file() {
+        assert_eq!(
+            validate_hack(&["0000000000000000", "1110101010000111"]),
+            Ok(())
+        );
+        assert_eq!(validate_hack(&[]), Ok(()));
+    }
+
+    #[test]
+    fn test_wrong_width() {
+        let error = validate_hack(&["0000000000000000", "00000000000000001"]).unwrap_err();
+        assert_eq!(
+            error,
+            HackError::WrongWidth {
+                line: 2,
+                content: "00000000000000001".to_string()
+            }
+        );
+        assert!(error.to_string().contains("got 17 characters"));
+    }
+
+    #[test]
+    fn test_non_binary_character() {
+        assert_eq!(
+            validate_hack(&["0000000000000000", "000000000000000x"]),
+            Err(HackError::NonBinary {
+                line: 2,
+                content: "000000000000000x".to_string()
+            })
+        );
+        assert!(matches!(
+            validate_hack(&["000000000000000é"]),
+            Err(HackError::NonBinary { line: 1, .. })
+        ));
+        // Sixteen bytes but fifteen characters
+        let error = validate_hack(&["00000000000000é"]).unwrap_err();
+        assert!(matches!(error, HackError::WrongWidth { line: 1, .. }));
+        assert!(error.to_string().contains("got 15 characters"));
+    }
+
+    #[test]
+    fn test_over_length_program() {
+        let lines = vec!["0000000000000000"; ROM_SIZE + 1];
+        assert_eq!(
+            validate_hack(&lines),
+            Err(HackError::TooLong {
+                count: ROM_SIZE + 1
+            })
+        );
+        assert!(validate_hack(&lines[..ROM_SIZE]).is_ok());
+    }
+}
@@ -11,6 +11,25 @@
 //! - [`symbol_table`]: Symbol management with predefined symbols
 //! - [`macros`]: Compile-time optimizations and utilities
 //!
+//! Whole-program tooling builds on top of them:
+//! - [`assembler`]: The two assembly passes behind a single library entry point
+//! - [`config`]: Project defaults read from `.hackasm.toml`
+//! - [`diagnostic`]: Warnings and errors tied to an instruction
+//! - [`program`]: Owned instruction list for analysis and rewriting
+//! - [`lint`]: Opt-in static checks reported as diagnostics
+//! - [`optimize`]: Opt-in peephole optimizations that shrink the output
+//! - [`emit`]: Serialization of instruction words in several output formats
+//! - [`hack`]: Validation of assembled machine code
+//! - [`stats`]: Instruction and symbol counts for one assembly run
+//! - [`simulator`]: A minimal Hack CPU for running assembled programs
+//! - `wasm`: `wasm-bindgen` entry points (with the `wasm` feature)
+//!
+//! # Error Handling
+//!
+//! The assembly entry points ([`assemble_full`], [`assemble_to_u16`] and
+//! [`assemble_source`]) report malformed source as errors and never panic on
+//! it, so they are safe to call on untrusted input, e.g. inside a server.
+//!
 //! # Performance Optimizations
 //!
 //! - **PHF (Perfect Hash Functions)**: O(1) compile-time hash maps for instruction encoding
@@ -57,13 +76,34 @@
 #[macro_use]
 pub mod macros;
 
+pub mod assembler;
 pub mod code;
+pub mod config;
+pub mod consts;
+pub mod diagnostic;
+pub mod emit;
+pub mod hack;
+pub mod lint;
+pub mod optimize;
 pub mod parser;
+pub mod program;
+pub mod simulator;
+pub mod stats;
 pub mod symbol_table;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 // Re-export commonly used types for convenience
-pub use parser::{CommandType, ParserError, ParserLines};
-pub use symbol_table::SymbolTable;
+pub use assembler::{
+    AssembleError, AssembleResult, AssemblerConfig, assemble, assemble_full,
+    assemble_full_with_progress, assemble_source, assemble_to_strings, assemble_to_u16,
+    needs_reassembly, validate_program,
+};
+pub use diagnostic::Diagnostic;
+pub use parser::{Command, CommandKind, CommandType, ParserError, ParserLines};
+pub use program::{Instruction, Program};
+pub use stats::AssemblyStats;
+pub use symbol_table::{SymbolTable, SymbolTableDiff};
 
 #[cfg(test)]
 mod tests {
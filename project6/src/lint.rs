@@ -0,0 +1,698 @@
+//! Opt-in static checks for Hack assembly programs
+//!
+//! Lints never change the generated machine code; they only report likely
+//! mistakes as [`Diagnostic`]s. The assembler runs them when invoked with
+//! `--lint`; any rule can be silenced by name with `--allow NAME`. Further
+//! rules can be plugged in by implementing [`LintRule`].
+//!
+//! | Name                 | Finds                                              |
+//! |----------------------|----------------------------------------------------|
+//! | `terminating-loop`   | programs that run off the end of ROM               |
+//! | `unreachable`        | instructions no path from ROM 0 reaches            |
+//! | `overwritten-result` | a constant assigned over a value just computed     |
+//! | `variable-usage`     | variables only ever written, or only ever read     |
+//! | `data-jump-target`   | jumps to a RAM address such as `SCREEN` or `R1`    |
+
+use crate::code::parse_a_constant;
+use crate::diagnostic::Diagnostic;
+use crate::program::{Instruction, Program};
+use crate::symbol_table::is_predefined;
+use std::fmt;
+use std::sync::Arc;
+
+/// Runs every built-in lint over `program`
+///
+/// # Example
+/// ```
+/// use project6::{lint, program::Program};
+///
+/// let lines = vec!["@0".to_string(), "M=D".to_string()];
+/// let program = Program::from_lines(&lines).unwrap();
+/// assert_eq!(lint::lint_program(&program).len(), 1);
+/// ```
+#[must_use]
+pub fn lint_program(program: &Program) -> Vec<Diagnostic> {
+    lint_program_allowing(program, &[])
+}
+
+/// A check over a whole program, reporting into a shared diagnostics list
+///
+/// Implement this to add project-specific lints without changing the crate,
+/// then register the rule with [`CustomLints::push`].
+///
+/// # Example
+/// ```
+/// use project6::code::parse_a_constant;
+/// use project6::diagnostic::Diagnostic;
+/// use project6::lint::LintRule;
+/// use project6::program::Program;
+///
+/// struct NoConstants;
+///
+/// impl LintRule for NoConstants {
+///     fn name(&self) -> &str {
+///         "no-constants"
+///     }
+///
+///     fn check(&self, program: &Program, diagnostics: &mut Vec<Diagnostic>) {
+///         for (index, instruction) in program.instructions.iter().enumerate() {
+///             if instruction.symbol().is_some_and(|s| parse_a_constant(s).is_some()) {
+///                 diagnostics.push(Diagnostic::at(index, "numeric constant"));
+///             }
+///         }
+///     }
+/// }
+/// ```
+pub trait LintRule {
+    /// Name used to silence the rule with `--allow`
+    fn name(&self) -> &str;
+
+    /// Appends a diagnostic for every problem found in `program`
+    fn check(&self, program: &Program, diagnostics: &mut Vec<Diagnostic>);
+}
+
+/// Declares a built-in rule as a unit struct backed by a check function
+macro_rules! builtin_rule {
+    ($(#[$doc:meta])* $rule:ident, $name:literal, $check:ident) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+        pub struct $rule;
+
+        impl LintRule for $rule {
+            fn name(&self) -> &str {
+                $name
+            }
+
+            fn check(&self, program: &Program, diagnostics: &mut Vec<Diagnostic>) {
+                $check(program, diagnostics);
+            }
+        }
+    };
+}
+
+builtin_rule!(
+    /// `terminating-loop`: the program must end in an infinite loop
+    TerminatingLoop,
+    "terminating-loop",
+    check_terminating_loop
+);
+builtin_rule!(
+    /// `unreachable`: every instruction must be reachable from ROM 0
+    Unreachable,
+    "unreachable",
+    check_reachability
+);
+builtin_rule!(
+    /// `overwritten-result`: a computed value must not be clobbered at once
+    OverwrittenResult,
+    "overwritten-result",
+    check_overwritten_result
+);
+builtin_rule!(
+    /// `variable-usage`: variables must be both written and read
+    VariableUsage,
+    "variable-usage",
+    check_variable_usage
+);
+
+builtin_rule!(
+    /// `data-jump-target`: jumps must not target predefined RAM addresses
+    DataJumpTarget,
+    "data-jump-target",
+    check_data_jump_target
+);
+
+/// Every built-in lint, in the order they run
+const RULES: &[&dyn LintRule] = &[
+    &TerminatingLoop,
+    &Unreachable,
+    &OverwrittenResult,
+    &VariableUsage,
+    &DataJumpTarget,
+];
+
+/// Returns the names of all built-in lints, as accepted by `--allow`
+#[must_use]
+pub fn rule_names() -> Vec<&'static str> {
+    RULES.iter().map(|rule| rule.name()).collect()
+}
+
+/// User-supplied lint rules, run after the built-in ones
+///
+/// Equality and `Debug` go by rule name, so that configurations holding
+/// custom rules can still be compared and printed.
+#[derive(Clone, Default)]
+pub struct CustomLints(Vec<Arc<dyn LintRule>>);
+
+impl CustomLints {
+    /// Registers `rule` to run on every linted program
+    pub fn push(&mut self, rule: impl LintRule + 'static) {
+        self.0.push(Arc::new(rule));
+    }
+
+    /// Iterates over the registered rules in registration order
+    pub fn iter(&self) -> impl Iterator<Item = &dyn LintRule> {
+        self.0.iter().map(AsRef::as_ref)
+    }
+}
+
+impl fmt::Debug for CustomLints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.iter().map(LintRule::name))
+            .finish()
+    }
+}
+
+impl PartialEq for CustomLints {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter()
+            .map(LintRule::name)
+            .eq(other.iter().map(LintRule::name))
+    }
+}
+
+impl Eq for CustomLints {}
+
+/// Runs every built-in lint except those named in `allowed`
+///
+/// # Example
+/// ```
+/// use project6::{lint, program::Program};
+///
+/// let lines = vec!["@0".to_string(), "M=D".to_string()];
+/// let program = Program::from_lines(&lines).unwrap();
+/// let allowed = ["terminating-loop".to_string()];
+/// assert!(lint::lint_program_allowing(&program, &allowed).is_empty());
+/// ```
+#[must_use]
+pub fn lint_program_allowing(program: &Program, allowed: &[String]) -> Vec<Diagnostic> {
+    run_rules(program, RULES.iter().copied(), allowed)
+}
+
+/// Runs the built-in lints followed by `custom`, skipping those in `allowed`
+#[must_use]
+pub fn lint_program_with(
+    program: &Program,
+    custom: &CustomLints,
+    allowed: &[String],
+) -> Vec<Diagnostic> {
+    run_rules(program, RULES.iter().copied().chain(custom.iter()), allowed)
+}
+
+/// Runs `rules` in order except those named in `allowed`
+fn run_rules<'r>(
+    program: &Program,
+    rules: impl IntoIterator<Item = &'r dyn LintRule>,
+    allowed: &[String],
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for rule in rules {
+        if !allowed.iter().any(|allow| allow == rule.name()) {
+            rule.check(program, &mut diagnostics);
+        }
+    }
+    diagnostics
+}
+
+/// Warns when the program does not end in an infinite loop
+///
+/// A Hack program has no halt instruction: once the last instruction executes
+/// the CPU carries on with whatever is in ROM next. The idiomatic ending is
+/// `(END) @END 0;JMP`, i.e. an unconditional jump back to itself or to any
+/// earlier instruction.
+fn check_terminating_loop(program: &Program, diagnostics: &mut Vec<Diagnostic>) {
+    let count = program.instructions.len();
+    let Some(last) = program.instructions.last() else {
+        return;
+    };
+
+    let loops_back = last.is_unconditional_jump()
+        && count >= 2
+        && program.instructions[count - 2]
+            .symbol()
+            .and_then(|symbol| program.jump_target(symbol))
+            .is_some_and(|target| target < count);
+
+    if !loops_back {
+        diagnostics.push(Diagnostic::at(
+            count - 1,
+            "program does not end in an infinite loop and may not terminate cleanly \
+             (end it with e.g. `(END) @END 0;JMP`)",
+        ));
+    }
+}
+
+/// Returns the ROM address the jump at `index` goes to, when statically known
+///
+/// The target is whatever the A register holds, which is only known when
+/// the preceding instruction loads a constant or a label.
+fn static_jump_target(program: &Program, index: usize) -> Option<usize> {
+    program
+        .instructions
+        .get(index.checked_sub(1)?)?
+        .symbol()
+        .and_then(|symbol| program.jump_target(symbol))
+}
+
+/// Reports instructions that no execution path from ROM 0 can reach
+///
+/// Control flows from each instruction to the next unless it is an
+/// unconditional jump, and from every jump to its target. A jump whose target
+/// is computed at run time (e.g. `A=M;JMP`) could land on any address the
+/// program ever loads, so every label and constant used in an A-instruction
+/// then counts as reachable. Each dead region is reported once, naming the
+/// labels inside it.
+fn check_reachability(program: &Program, diagnostics: &mut Vec<Diagnostic>) {
+    let count = program.instructions.len();
+    if count == 0 {
+        return;
+    }
+
+    let is_jump = |instruction: &Instruction| matches!(instruction, Instruction::C { jump, .. } if !jump.is_empty());
+    let has_dynamic_jump = (0..count).any(|index| {
+        is_jump(&program.instructions[index]) && static_jump_target(program, index).is_none()
+    });
+
+    let mut reachable = vec![false; count];
+    let mut pending = vec![0];
+    if has_dynamic_jump {
+        pending.extend(
+            program
+                .instructions
+                .iter()
+                .filter_map(Instruction::symbol)
+                .filter_map(|symbol| program.jump_target(symbol)),
+        );
+    }
+
+    while let Some(index) = pending.pop() {
+        if index >= count || reachable[index] {
+            continue;
+        }
+        reachable[index] = true;
+
+        let instruction = &program.instructions[index];
+        if is_jump(instruction) {
+            pending.extend(static_jump_target(program, index));
+        }
+        if !instruction.is_unconditional_jump() {
+            pending.push(index + 1);
+        }
+    }
+
+    let mut start = 0;
+    while start < count {
+        if reachable[start] {
+            start += 1;
+            continue;
+        }
+        let end = (start..count).find(|&i| reachable[i]).unwrap_or(count);
+
+        let labels: Vec<&str> = program
+            .labels
+            .iter()
+            .filter(|&&(_, index)| (start..end).contains(&index))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let range = if end - start == 1 {
+            format!("instruction {start} is unreachable")
+        } else {
+            format!("instructions {start}-{} are unreachable", end - 1)
+        };
+        let message = if labels.is_empty() {
+            range
+        } else {
+            format!("{range} (dead label {})", labels.join(", "))
+        };
+        diagnostics.push(Diagnostic::at(start, message));
+
+        start = end;
+    }
+}
+
+/// Returns `true` for comps that ignore both D and A/M
+fn is_constant_comp(comp: &str) -> bool {
+    matches!(comp, "0" | "1" | "-1")
+}
+
+/// Warns when a constant overwrites a register the previous instruction computed
+///
+/// In `D=D+1` followed by `D=0` the increment is thrown away, which is usually
+/// a slip. Only adjacent instructions are compared, a label in between (a
+/// jump may arrive there) suppresses the warning, and `M` only counts when
+/// the first instruction did not also move A.
+fn check_overwritten_result(program: &Program, diagnostics: &mut Vec<Diagnostic>) {
+    for index in 1..program.instructions.len() {
+        let (
+            Instruction::C {
+                dest: previous_dest,
+                comp: previous_comp,
+                ..
+            },
+            Instruction::C { dest, comp, .. },
+        ) = (
+            &program.instructions[index - 1],
+            &program.instructions[index],
+        )
+        else {
+            continue;
+        };
+        if !is_constant_comp(comp) || is_constant_comp(previous_comp) || program.has_label_at(index)
+        {
+            continue;
+        }
+
+        let overwritten = dest.chars().find(|&register| {
+            previous_dest.contains(register) && !(register == 'M' && previous_dest.contains('A'))
+        });
+        if let Some(register) = overwritten {
+            diagnostics.push(Diagnostic::at(
+                index,
+                format!(
+                    "`{dest}={comp}` overwrites {register}, discarding the `{previous_comp}` \
+                     just computed into it"
+                ),
+            ));
+        }
+    }
+}
+
+/// How a variable is accessed through M, with the first index of each
+#[derive(Default)]
+struct VariableAccess {
+    first_read: Option<usize>,
+    first_write: Option<usize>,
+}
+
+/// Warns about variables that are written but never read, or the reverse
+///
+/// After `@x`, each following C-instruction reads `x` if its comp uses `M`
+/// and writes it if its dest does, until A is reloaded or changed, or a label
+/// lets control arrive with some other A. Accesses through pointers are
+/// invisible to this, so it only approximates what the program does.
+fn check_variable_usage(program: &Program, diagnostics: &mut Vec<Diagnostic>) {
+    let mut variables: Vec<(&str, VariableAccess)> = Vec::new();
+
+    for (index, instruction) in program.instructions.iter().enumerate() {
+        let Some(symbol) = instruction.symbol() else {
+            continue;
+        };
+        if parse_a_constant(symbol).is_some()
+            || is_predefined(symbol)
+            || program.label_address(symbol).is_some()
+        {
+            continue;
+        }
+
+        let position = variables
+            .iter()
+            .position(|&(name, _)| name == symbol)
+            .unwrap_or_else(|| {
+                variables.push((symbol, VariableAccess::default()));
+                variables.len() - 1
+            });
+        let access = &mut variables[position].1;
+
+        for next in index + 1..program.instructions.len() {
+            let Instruction::C { dest, comp, .. } = &program.instructions[next] else {
+                break;
+            };
+            if program.has_label_at(next) {
+                break;
+            }
+            if comp.contains('M') {
+                access.first_read.get_or_insert(next);
+            }
+            if dest.contains('M') {
+                access.first_write.get_or_insert(next);
+            }
+            if dest.contains('A') {
+                break;
+            }
+        }
+    }
+
+    for (name, access) in variables {
+        match (access.first_read, access.first_write) {
+            (None, Some(index)) => diagnostics.push(Diagnostic::at(
+                index,
+                format!("variable `{name}` is written but never read"),
+            )),
+            (Some(index), None) => diagnostics.push(Diagnostic::at(
+                index,
+                format!("variable `{name}` is read but never written"),
+            )),
+            _ => {}
+        }
+    }
+}
+
+/// Warns when a jump's target is a predefined symbol
+///
+/// Every predefined symbol (`SCREEN`, `KBD`, `R0`-`R15`, `SP`, ...) names a
+/// RAM address, so `@SCREEN 0;JMP` almost certainly confuses data with code.
+/// Jumps that also write A (`A=M;JMP`) follow a pointer and are not judged,
+/// nor are jumps behind a label, where A may have been loaded elsewhere.
+fn check_data_jump_target(program: &Program, diagnostics: &mut Vec<Diagnostic>) {
+    for index in 1..program.instructions.len() {
+        let Instruction::C { dest, jump, .. } = &program.instructions[index] else {
+            continue;
+        };
+        if jump.is_empty() || dest.contains('A') || program.has_label_at(index) {
+            continue;
+        }
+        let Some(symbol) = program.instructions[index - 1].symbol() else {
+            continue;
+        };
+        if is_predefined(symbol) {
+            diagnostics.push(Diagnostic::at(
+                index,
+                format!(
+                    "jump target `{symbol}` is a RAM address, not a ROM address \
+                     (did you mean to jump to a label?)"
+                ),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(source: &[&str]) -> Vec<Diagnostic> {
+        let lines: Vec<String> = source.iter().map(ToString::to_string).collect();
+        lint_program(&Program::from_lines(&lines).unwrap())
+    }
+
+    #[test]
+    fn test_terminating_loop_accepted() {
+        assert!(lint(&["@0", "M=D", "(END)", "@END", "0;JMP"]).is_empty());
+    }
+
+    #[test]
+    fn test_jump_to_preceding_label_accepted() {
+        assert!(lint(&["(LOOP)", "@0", "M=M+1", "@LOOP", "0;JMP"]).is_empty());
+    }
+
+    #[test]
+    fn test_missing_terminating_loop_warns() {
+        let diagnostics = lint(&["@0", "D=M", "@1", "M=D"]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].address, Some(3));
+        assert!(diagnostics[0].message.contains("infinite loop"));
+    }
+
+    #[test]
+    fn test_forward_or_conditional_jump_warns() {
+        assert_eq!(lint(&["@END", "0;JMP", "(END)"]).len(), 1);
+        assert_eq!(lint(&["(END)", "@END", "D;JGT"]).len(), 1);
+    }
+
+    #[test]
+    fn test_empty_program_is_silent() {
+        assert!(lint(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_unreachable_block_after_jump() {
+        let diagnostics = lint(&[
+            "@END", "0;JMP", // Skips the block below
+            "(DEAD)", "@1", "M=D", "@DEAD", "0;JMP", //
+            "(END)", "@END", "0;JMP",
+        ]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].address, Some(2));
+        assert_eq!(
+            diagnostics[0].message,
+            "instructions 2-5 are unreachable (dead label DEAD)"
+        );
+    }
+
+    #[test]
+    fn test_conditional_jump_keeps_fallthrough() {
+        assert!(
+            lint(&[
+                "@0", "D=M", "@SKIP", "D;JEQ", "@1", "M=D", "(SKIP)", "(END)", "@END", "0;JMP",
+            ])
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_dynamic_jump_to_loaded_constant() {
+        // No labels: ROM 4 is only reachable because `@4` loads its address
+        let source = ["@4", "D=A", "@R15", "A=M;JMP", "@4", "0;JMP"];
+        assert!(lint(&source).is_empty());
+    }
+
+    #[test]
+    fn test_dynamic_jump_makes_labels_reachable() {
+        // `A=M;JMP` could land on RETURN, so it is not dead
+        let source = [
+            "@RETURN", "D=A", "@R15", "M=D", "@R15", "A=M;JMP", //
+            "(RETURN)", "(END)", "@END", "0;JMP",
+        ];
+        assert!(lint(&source).is_empty());
+    }
+
+    #[test]
+    fn test_overwritten_result_can_be_allowed() {
+        let source: Vec<String> = ["@0", "D=M", "D=D+1", "D=0", "(END)", "@END", "0;JMP"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let program = Program::from_lines(&source).unwrap();
+
+        let diagnostics = lint_program(&program);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].address, Some(3));
+        assert_eq!(
+            diagnostics[0].message,
+            "`D=0` overwrites D, discarding the `D+1` just computed into it"
+        );
+
+        let allowed = ["overwritten-result".to_string()];
+        assert!(lint_program_allowing(&program, &allowed).is_empty());
+    }
+
+    #[test]
+    fn test_constant_after_unrelated_write_accepted() {
+        // Different register, pointer moved by AM=, and a label in between
+        assert!(lint(&["@0", "D=M", "M=0", "(END)", "@END", "0;JMP"]).is_empty());
+        assert!(lint(&["@SP", "AM=M-1", "M=0", "(END)", "@END", "0;JMP"]).is_empty());
+        assert!(lint(&["@0", "D=M", "(L)", "D=0", "@L", "0;JMP"]).is_empty());
+    }
+
+    #[test]
+    fn test_write_only_variable() {
+        let diagnostics = lint(&[
+            "@x", "M=1", "@y", "M=0", "@y", "D=M", "(END)", "@END", "0;JMP",
+        ]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].address, Some(1));
+        assert_eq!(
+            diagnostics[0].message,
+            "variable `x` is written but never read"
+        );
+    }
+
+    #[test]
+    fn test_read_only_variable() {
+        let diagnostics = lint(&["@count", "D=M", "@0", "M=D", "(END)", "@END", "0;JMP"]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].address, Some(1));
+        assert_eq!(
+            diagnostics[0].message,
+            "variable `count` is read but never written"
+        );
+    }
+
+    #[test]
+    fn test_radix_constants_are_not_variables() {
+        let diagnostics = lint(&[
+            "@0x10", "M=1", "@20h", "D=M", "@0b11", "M=D", "@0o7", "D=M", "(END)", "@END", "0;JMP",
+        ]);
+        assert_eq!(diagnostics, []);
+    }
+
+    #[test]
+    fn test_variable_access_stops_when_a_changes() {
+        // `A=M` follows the pointer, so `M=D` writes the pointee, not `ptr`;
+        // `D=A` only takes the address and is neither a read nor a write
+        assert!(
+            lint(&[
+                "@ptr", "M=D", "A=M", "M=D", "@ptr", "D=M", "@buf", "D=A", "(END)", "@END",
+                "0;JMP",
+            ])
+            .is_empty()
+        );
+        assert_eq!(
+            lint(&["@p", "A=M", "M=D", "(END)", "@END", "0;JMP"]).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_jump_to_ram_address_warns() {
+        let lines = vec!["@SCREEN".to_string(), "0;JMP".to_string()];
+        let mut diagnostics = Vec::new();
+        DataJumpTarget.check(&Program::from_lines(&lines).unwrap(), &mut diagnostics);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].address, Some(1));
+        assert!(diagnostics[0].message.contains("`SCREEN` is a RAM address"));
+
+        assert_eq!(lint(&["@R1", "D;JGT", "(END)", "@END", "0;JMP"]).len(), 1);
+        assert!(lint(&["@SCREEN", "M=0", "(END)", "@END", "0;JMP"]).is_empty());
+    }
+
+    #[test]
+    fn test_rule_names() {
+        assert_eq!(
+            rule_names(),
+            [
+                "terminating-loop",
+                "unreachable",
+                "overwritten-result",
+                "variable-usage",
+                "data-jump-target"
+            ]
+        );
+    }
+
+    /// Flags programs longer than a fixed number of instructions
+    struct MaxInstructions(usize);
+
+    impl LintRule for MaxInstructions {
+        fn name(&self) -> &'static str {
+            "max-instructions"
+        }
+
+        fn check(&self, program: &Program, diagnostics: &mut Vec<Diagnostic>) {
+            if program.instructions.len() > self.0 {
+                diagnostics.push(Diagnostic::new(format!(
+                    "program has more than {} instructions",
+                    self.0
+                )));
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_rule() {
+        let source: Vec<String> = ["@1", "@2", "@3", "(END)", "@END", "0;JMP"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let program = Program::from_lines(&source).unwrap();
+        let mut custom = CustomLints::default();
+        custom.push(MaxInstructions(4));
+
+        let diagnostics = lint_program_with(&program, &custom, &[]);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("more than 4"));
+        assert!(lint_program_with(&program, &custom, &["max-instructions".to_string()]).is_empty());
+        assert_eq!(format!("{custom:?}"), "[\"max-instructions\"]");
+    }
+}
@@ -8,147 +8,767 @@
 //!
 //! # Usage
 //! ```bash
-//! cargo run <input.asm> [output.hack]
+//! cargo run <input.asm> [output.hack] [--lint] [--allow LINT] [--optimize] [--where SYMBOL] [--format FORMAT]
+//!           [--endian ORDER] [--binary] [--var-base ADDR] [--stats] [--stats-json FILE]
+//!           [--dump-symbols] [--sort-by KEY] [--symbols] [--quiet]
+//! cargo run <directory> [--verify-existing]
+//! cargo run <input.asm | directory> --check
+//! cargo run --banks <a.asm:ADDR>... -o <image.bin>
 //! ```
+//!
+//! Defaults for these flags can be kept in a `.hackasm.toml` in the working
+//! directory (with the `config` feature); flags on the command line win.
 
 #![warn(clippy::all, clippy::pedantic)]
 #![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
 
 use std::env;
-use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::process;
 
-mod code;
-mod parser;
-mod symbol_table;
+use project6::config::CONFIG_FILE_NAME;
+use project6::consts::ROM_SIZE;
+use project6::emit::{self, Endian, OutputFormat, binary};
+use project6::symbol_table::{SymbolOrder, predefined_address};
+use project6::{
+    AssembleError, AssemblerConfig, AssemblyStats, Diagnostic, ParserError, SymbolTable,
+    assemble_full, assembler, parser,
+};
+
+/// Everything that can make a run of the assembler fail
+#[derive(Debug)]
+enum AssemblerError {
+    /// Reading or writing a file failed
+    Io(io::Error),
+    /// The source could not be split into commands, or its labels defined
+    Parse(Vec<AssembleError>),
+    /// `--where` named a symbol the program never uses
+    UnresolvedSymbol(String),
+    /// The `--banks` image is larger than ROM
+    RomOverflow { words: usize },
+    /// Two `--banks` programs claim the same ROM addresses
+    BankOverlap {
+        bank: String,
+        start: usize,
+        previous: String,
+        end: usize,
+    },
+    /// The program has errors; each one has already been printed
+    Validation(Vec<Diagnostic>),
+    /// `--verify-existing` found this many stale or missing outputs
+    OutOfDate(usize),
+    /// The configuration file is malformed
+    #[cfg_attr(not(feature = "config"), allow(dead_code))] // Only read with the feature
+    Config(String),
+    /// A flag needs a cargo feature this build was made without
+    #[cfg_attr(feature = "serde", allow(dead_code))] // Only `--stats-json` needs one
+    MissingFeature {
+        flag: &'static str,
+        feature: &'static str,
+    },
+}
+
+impl fmt::Display for AssemblerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "I/O error: {error}"),
+            Self::Parse(errors) => {
+                for (index, error) in errors.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{error}")?;
+                }
+                Ok(())
+            }
+            Self::UnresolvedSymbol(name) => {
+                write!(f, "symbol '{name}' is not used in the program")
+            }
+            Self::RomOverflow { words } => {
+                write!(f, "the image needs {words} words but ROM holds {ROM_SIZE}")
+            }
+            Self::BankOverlap {
+                bank,
+                start,
+                previous,
+                end,
+            } => write!(
+                f,
+                "bank {bank} at ROM {start} overlaps {previous}, which ends at ROM {end}"
+            ),
+            Self::Validation(errors) => write!(f, "{} error(s) found", errors.len()),
+            Self::OutOfDate(count) => write!(f, "{count} output file(s) are not up to date"),
+            Self::Config(message) => write!(f, "{message}"),
+            Self::MissingFeature { flag, feature } => {
+                write!(f, "{flag} requires building with `--features {feature}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssemblerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Parse(errors) => errors.first().map(|error| error as _),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for AssemblerError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
 
-use parser::{CommandType, ParserLines};
-use symbol_table::SymbolTable;
+impl From<ParserError> for AssemblerError {
+    fn from(error: ParserError) -> Self {
+        match error {
+            ParserError::IoError(error) => Self::Io(error),
+            error => Self::Parse(vec![error.into()]),
+        }
+    }
+}
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+impl From<Vec<AssembleError>> for AssemblerError {
+    fn from(errors: Vec<AssembleError>) -> Self {
+        Self::Parse(errors)
+    }
+}
+
+type Result<T> = std::result::Result<T, AssemblerError>;
 
 /// Reads assembly file into memory
-fn read_lines(path: &str) -> Result<Vec<String>> {
+fn read_lines(path: impl AsRef<Path>) -> Result<Vec<String>> {
     let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    reader
-        .lines()
-        .collect::<std::io::Result<Vec<_>>>()
-        .map_err(Into::into)
+    Ok(parser::read_source_lines(BufReader::new(file))?)
+}
+
+/// Determines the output file path
+fn output_path(input: &str, explicit_output: Option<&str>, extension: &str) -> String {
+    explicit_output.map_or_else(
+        || input.replace(".asm", &format!(".{extension}")),
+        std::string::ToString::to_string,
+    )
 }
 
-/// First pass: Build symbol table with label addresses
+/// A program placed at a fixed ROM address by `--banks`, written `path:ADDR`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bank {
+    path: String,
+    /// ROM address of the program's first instruction
+    offset: u16,
+}
+
+impl std::str::FromStr for Bank {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (path, offset) = s
+            .rsplit_once(':')
+            .ok_or_else(|| format!("bank '{s}' is missing its :ADDR"))?;
+        let offset = offset
+            .parse()
+            .map_err(|_| format!("bank '{s}' has an invalid address"))?;
+        Ok(Self {
+            path: path.to_string(),
+            offset,
+        })
+    }
+}
+
+/// Command-line options
+#[derive(Debug, Default, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)] // Independent command-line flags
+struct Options {
+    input: String,
+    output: Option<String>,
+    lint: bool,
+    allowed_lints: Vec<String>,
+    optimize: bool,
+    where_symbol: Option<String>,
+    /// Output format, `None` if neither `--format` nor the config chose one
+    format: Option<OutputFormat>,
+    endian: Endian,
+    var_base: Option<u16>,
+    verify_existing: bool,
+    stats: bool,
+    stats_json: Option<String>,
+    /// Write a packed `.hackbin` image instead of `format`
+    binary: bool,
+    /// Don't print success messages; errors still go to stderr
+    quiet: bool,
+    /// Print the symbol table after assembling
+    dump_symbols: bool,
+    /// Order of the `--dump-symbols` listing
+    sort_by: SymbolOrder,
+    /// Write a `.sym` file of user symbols next to the output
+    symbols: bool,
+    /// Write a `.hack.txt` listing of each word with its source line
+    annotate: bool,
+    /// Only report errors; write no output
+    check: bool,
+    /// Programs to combine into one ROM image; empty unless `--banks` is given
+    banks: Vec<Bank>,
+}
+
+impl Options {
+    /// Extension of the files written with these options
+    fn output_extension(&self) -> &'static str {
+        if self.binary {
+            binary::HACKBIN_EXTENSION
+        } else {
+            "hack"
+        }
+    }
+}
+
+/// Parses command-line arguments (excluding the program name)
 ///
-/// Scans through all lines and records the ROM address of each label.
-/// Label definitions (L-commands) don't generate code, so they don't
-/// increment the ROM address counter.
-fn first_pass(lines: &[String], symbol_table: &mut SymbolTable) -> Result<()> {
-    let mut rom_address = 0u16;
-    let mut parser = ParserLines::from_lines(lines);
-
-    while parser.advance() {
-        match parser.command_type()? {
-            CommandType::LCommand => {
-                // Labels mark the next instruction's address
-                let symbol = parser.symbol()?;
-                symbol_table.add_entry(symbol, rom_address);
+/// Flags override the corresponding fields of `defaults`. Returns `None` if
+/// the arguments don't form a valid invocation.
+///
+/// With `--banks` every positional argument is a `path:ADDR` bank and the
+/// image is named with `-o`, which is then required.
+fn parse_args(defaults: Options, args: &[String]) -> Option<Options> {
+    let mut options = defaults;
+    let mut positional = Vec::new();
+    let mut banks = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lint" => options.lint = true,
+            "--allow" => options.allowed_lints.push(args.next()?.clone()),
+            "--optimize" => options.optimize = true,
+            "--where" => options.where_symbol = Some(args.next()?.clone()),
+            "--format" => options.format = Some(args.next()?.parse().ok()?),
+            "--endian" => options.endian = args.next()?.parse().ok()?,
+            "--var-base" => options.var_base = Some(args.next()?.parse().ok()?),
+            "--verify-existing" => options.verify_existing = true,
+            "--stats" => options.stats = true,
+            "--stats-json" => options.stats_json = Some(args.next()?.clone()),
+            "--binary" => options.binary = true,
+            "--quiet" => options.quiet = true,
+            "--dump-symbols" => options.dump_symbols = true,
+            "--sort-by" => options.sort_by = args.next()?.parse().ok()?,
+            "--symbols" => options.symbols = true,
+            "--annotate" => options.annotate = true,
+            "--check" => options.check = true,
+            "--banks" => banks = true,
+            "-o" => options.output = Some(args.next()?.clone()),
+            flag if flag.starts_with("--") => return None,
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    if banks {
+        options.output.as_ref()?;
+        options.banks = positional
+            .iter()
+            .map(|bank| bank.parse().ok())
+            .collect::<Option<_>>()?;
+        if options.banks.is_empty() {
+            return None;
+        }
+    } else {
+        let mut positional = positional.into_iter();
+        options.input = positional.next()?;
+        if let Some(output) = positional.next() {
+            if options.output.is_some() {
+                return None;
             }
-            CommandType::ACommand | CommandType::CCommand => {
-                // Actual instructions increment the address
-                rom_address += 1;
+            options.output = Some(output);
+        }
+        if positional.next().is_some() {
+            return None;
+        }
+    }
+    if let Some(output) = &options.output
+        && Path::new(output)
+            .extension()
+            .is_some_and(|ext| ext == binary::HACKBIN_EXTENSION)
+    {
+        options.binary = true;
+    }
+    // A `.bin` image holds the bare words, unless a format was chosen
+    if options.format.is_none()
+        && let Some(output) = &options.output
+        && Path::new(output)
+            .extension()
+            .is_some_and(|ext| ext == "bin")
+    {
+        options.format = Some(OutputFormat::Raw);
+    }
+
+    Some(options)
+}
+
+/// Reads the starting options from `.hackasm.toml` in `dir`, if there is one
+#[cfg(feature = "config")]
+fn config_defaults(dir: &Path) -> Result<Options> {
+    let Some(config) =
+        project6::config::ProjectConfig::load(dir).map_err(AssemblerError::Config)?
+    else {
+        return Ok(Options::default());
+    };
+    Ok(Options {
+        lint: config.assembler.lint,
+        allowed_lints: config.assembler.allowed_lints,
+        optimize: config.assembler.optimize,
+        format: config.format,
+        endian: config.endian.unwrap_or_default(),
+        var_base: config.assembler.variable_base,
+        ..Options::default()
+    })
+}
+
+#[cfg(not(feature = "config"))]
+#[allow(clippy::unnecessary_wraps)] // Matches the signature with the feature enabled
+fn config_defaults(dir: &Path) -> Result<Options> {
+    if dir.join(CONFIG_FILE_NAME).exists() {
+        eprintln!("warning: ignoring {CONFIG_FILE_NAME}; reading it requires `--features config`");
+    }
+    Ok(Options::default())
+}
+
+/// Resolves a single symbol and describes where it lives
+///
+/// Labels only need pass 1, but variables are allocated during pass 2, so the
+/// full assembly runs (discarding its output) before giving up on a symbol.
+fn query_symbol(lines: &[String], name: &str) -> Result<String> {
+    if let Some(address) = predefined_address(name) {
+        return Ok(format!("{name}: RAM {address} (predefined)"));
+    }
+
+    let mut symbol_table = SymbolTable::new();
+    assembler::first_pass(lines, &mut symbol_table)?;
+    if symbol_table.contains(name) {
+        let address = symbol_table.get_address(name);
+        return Ok(format!("{name}: ROM {address} (label)"));
+    }
+
+    // Errors don't matter here: variables are allocated regardless
+    let _ = assembler::second_pass(lines, &mut symbol_table);
+    if symbol_table.contains(name) {
+        let address = symbol_table.get_address(name);
+        return Ok(format!("{name}: RAM {address} (variable)"));
+    }
+
+    Err(AssemblerError::UnresolvedSymbol(name.to_string()))
+}
+
+/// Formats an assembly error, leading with its source line when known
+fn describe_error(error: &Diagnostic) -> String {
+    match error.line {
+        Some(line) => format!("error at line {line}: {}", error.message),
+        None => format!("error: {error}"),
+    }
+}
+
+/// Reads one source file and assembles it, honouring `--lint`/`--optimize`
+///
+/// Warnings and errors are printed to stderr; any error fails the file.
+fn assemble_file(
+    path: impl AsRef<Path>,
+    options: &Options,
+) -> Result<(Vec<u16>, AssemblyStats, SymbolTable, Vec<usize>)> {
+    let lines = read_lines(path)?;
+    let config = AssemblerConfig {
+        lint: options.lint,
+        allowed_lints: options.allowed_lints.clone(),
+        optimize: options.optimize,
+        variable_base: options.var_base,
+        warn_static_overflow: options.lint,
+        ..AssemblerConfig::default()
+    };
+    let result = assemble_full(&lines, &config);
+
+    for warning in &result.warnings {
+        eprintln!("warning: {warning}");
+    }
+    for error in &result.errors {
+        eprintln!("{}", describe_error(error));
+    }
+
+    let words = result
+        .instructions
+        .ok_or(AssemblerError::Validation(result.errors))?;
+    Ok((words, result.stats, result.symbols, result.source_lines))
+}
+
+/// Lists every symbol with its address for `--dump-symbols`, one per line
+fn format_symbols(symbols: &SymbolTable, order: SymbolOrder) -> String {
+    use std::fmt::Write as _;
+
+    let mut listing = String::new();
+    for (name, address) in symbols.sorted_symbols(order) {
+        let _ = writeln!(listing, "{address:>5}  {name}");
+    }
+    listing
+}
+
+/// Lists the user symbols for a `--symbols` file, one `NAME address SPACE`
+/// per line
+///
+/// SPACE is `ROM` for labels and `RAM` for variables. Labels come first,
+/// then variables, each by address. Label names are recovered by running
+/// pass 1 over `lines` again, since the symbol table doesn't record them.
+fn format_symbol_file(lines: &[String], symbols: &SymbolTable) -> Result<String> {
+    use std::fmt::Write as _;
+
+    let mut labels = SymbolTable::new();
+    assembler::first_pass(lines, &mut labels)?;
+
+    let mut entries: Vec<(&str, u16, &str)> = symbols
+        .iter()
+        .map(|(name, address)| {
+            if labels.contains(name) {
+                (name, address, "ROM")
+            } else {
+                (name, address, "RAM")
             }
+        })
+        .collect();
+    entries.sort_unstable_by_key(|&(name, address, space)| (space != "ROM", address, name));
+
+    let mut listing = String::new();
+    for (name, address, space) in entries {
+        let _ = writeln!(listing, "{name} {address} {space}");
+    }
+    Ok(listing)
+}
+
+/// Lists each word in binary with the source line it came from, for
+/// `--annotate`
+///
+/// Lines look like `0000000000000101 // line 7: @5`, with the source
+/// trimmed and its comment dropped. `source_lines` gives each word's 1-based
+/// line; words without one (e.g. after `--optimize`) get no comment.
+fn format_annotated(words: &[u16], lines: &[String], source_lines: &[usize]) -> String {
+    use std::fmt::Write as _;
+
+    let mut listing = String::new();
+    for (index, word) in words.iter().enumerate() {
+        let _ = write!(listing, "{word:016b}");
+        if let Some(&line) = source_lines.get(index) {
+            let text = lines.get(line - 1).map_or("", |text| {
+                text.split("//").next().unwrap_or_default().trim()
+            });
+            let _ = write!(listing, " // line {line}: {text}");
         }
+        listing.push('\n');
     }
+    listing
+}
 
+/// Writes `stats` to `path` as JSON for `--stats-json`
+#[cfg(feature = "serde")]
+fn write_stats_json(path: &str, stats: &AssemblyStats) -> Result<()> {
+    fs::write(path, stats.to_json() + "\n")?;
     Ok(())
 }
 
-/// Second pass: Generate machine code
+#[cfg(not(feature = "serde"))]
+fn write_stats_json(_path: &str, _stats: &AssemblyStats) -> Result<()> {
+    Err(AssemblerError::MissingFeature {
+        flag: "--stats-json",
+        feature: "serde",
+    })
+}
+
+/// Writes `words` in the format selected by `options`
+fn write_output<W: Write>(writer: &mut W, words: &[u16], options: &Options) -> io::Result<()> {
+    if options.binary {
+        binary::write_binary_rom(writer, words)
+    } else {
+        emit::write_words_with_endian(
+            writer,
+            words,
+            options.format.unwrap_or_default(),
+            options.endian,
+        )
+    }
+}
+
+/// Assembles each bank on its own and lays them out in one ROM image
+///
+/// Every program gets a fresh symbol table, so labels and variables don't
+/// leak between banks, and labels resolve as if the bank started at ROM 0,
+/// where bank-switching hardware maps it. The gaps before and between banks
+/// are zero-filled.
+/// Banks may be listed in any order but must not overlap.
+fn assemble_banks(banks: &[Bank], options: &Options) -> Result<Vec<u16>> {
+    let mut programs = Vec::with_capacity(banks.len());
+    for bank in banks {
+        let (words, ..) = assemble_file(&bank.path, options)?;
+        programs.push((bank, words));
+    }
+    programs.sort_by_key(|(bank, _)| bank.offset);
+
+    let mut image = Vec::new();
+    let mut previous: Option<&Bank> = None;
+    for (bank, words) in programs {
+        let start = usize::from(bank.offset);
+        if let Some(previous) = previous
+            && image.len() > start
+        {
+            return Err(AssemblerError::BankOverlap {
+                bank: bank.path.clone(),
+                start,
+                previous: previous.path.clone(),
+                end: image.len() - 1,
+            });
+        }
+        image.resize(start, 0);
+        image.extend(words);
+        previous = Some(bank);
+    }
+
+    if image.len() > ROM_SIZE {
+        return Err(AssemblerError::RomOverflow { words: image.len() });
+    }
+    Ok(image)
+}
+
+/// Outcome of comparing fresh output with an existing `.hack` file
+#[derive(Debug, PartialEq, Eq)]
+enum Verification {
+    UpToDate,
+    OutOfDate,
+    Missing,
+}
+
+/// Compares freshly assembled `words` with the file at `path`
+///
+/// Textual output is compared ignoring `\r`, so committed files with Windows
+/// line endings still count as up to date.
+fn verify_existing(words: &[u16], options: &Options, path: &Path) -> Result<Verification> {
+    if !path.exists() {
+        return Ok(Verification::Missing);
+    }
+
+    let mut expected = Vec::new();
+    write_output(&mut expected, words, options)?;
+
+    let mut existing = fs::read(path)?;
+    if !options.binary && options.format.unwrap_or_default() == OutputFormat::Bits {
+        existing.retain(|&byte| byte != b'\r');
+    }
+
+    Ok(if existing == expected {
+        Verification::UpToDate
+    } else {
+        Verification::OutOfDate
+    })
+}
+
+/// Lists the `.asm` files directly inside `dir`, sorted by name
+fn asm_files_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "asm") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Assembles each of `sources` for `--check`, writing nothing
 ///
-/// Translates each instruction to binary:
-/// - A-commands: Resolve symbols to addresses
-/// - C-commands: Encode dest, comp, and jump fields
-/// - L-commands: Skip (already processed in pass 1)
-fn second_pass(
-    lines: &[String],
-    symbol_table: &mut SymbolTable,
-    writer: &mut BufWriter<File>,
-) -> Result<()> {
-    let mut ram_address = 16u16; // Variables start at RAM[16]
-    let mut parser = ParserLines::from_lines(lines);
-
-    while parser.advance() {
-        match parser.command_type()? {
-            CommandType::ACommand => {
-                let symbol = parser.symbol()?;
-
-                // Try to parse as number first, then lookup/insert as symbol
-                let address = symbol
-                    .parse::<u16>()
-                    .unwrap_or_else(|_| symbol_table.get_or_insert(symbol, &mut ram_address));
-
-                let instruction = code::encode_a_instruction(address);
-                writeln!(writer, "{instruction}")?;
+/// Every file is checked even after one fails, so a whole directory of
+/// submissions is reported in one run. Errors are printed as they are found,
+/// followed by a count per failing file; the run fails with all of them.
+fn check_sources(sources: &[PathBuf], options: &Options) -> Result<()> {
+    let mut errors = Vec::new();
+
+    for source in sources {
+        match assemble_file(source, options) {
+            Ok(_) => {}
+            Err(AssemblerError::Validation(found)) => {
+                eprintln!("{}: {} error(s) found", source.display(), found.len());
+                errors.extend(found);
             }
-            CommandType::CCommand => {
-                let dest = parser.dest()?.unwrap_or("");
-                let comp = parser.comp()?.unwrap_or("");
-                let jump = parser.jump()?.unwrap_or("");
+            Err(error) => return Err(error),
+        }
+    }
 
-                let instruction = code::encode_c_instruction(dest, comp, jump);
-                writeln!(writer, "{instruction}")?;
+    if !errors.is_empty() {
+        return Err(AssemblerError::Validation(errors));
+    }
+    if !options.quiet {
+        println!("{} file(s) checked, no errors found", sources.len());
+    }
+    Ok(())
+}
+
+/// Assembles every `.asm` file in `dir` next to its source
+///
+/// Existing `.hack` files are never treated as input. With
+/// `--verify-existing` nothing is written; each output is compared against the
+/// `.hack` already present and the run fails if any is stale or missing.
+fn assemble_directory(dir: &Path, options: &Options) -> Result<()> {
+    let mut stale = 0;
+
+    for source in asm_files_in(dir)? {
+        let (words, ..) = assemble_file(&source, options)?;
+        let target = source.with_extension(options.output_extension());
+
+        if options.verify_existing {
+            let verification = verify_existing(&words, options, &target)?;
+            match verification {
+                Verification::UpToDate if options.quiet => {}
+                Verification::UpToDate => println!("up to date: {}", target.display()),
+                Verification::OutOfDate => println!("out of date: {}", target.display()),
+                Verification::Missing => println!("missing: {}", target.display()),
+            }
+            if verification != Verification::UpToDate {
+                stale += 1;
             }
-            CommandType::LCommand => {
-                panic!("L command not supported");
+        } else {
+            let mut writer = BufWriter::new(File::create(&target)?);
+            write_output(&mut writer, &words, options)?;
+            if !options.quiet {
+                println!("Assembled {} -> {}", source.display(), target.display());
             }
         }
     }
 
-    writer.flush()?;
+    if stale > 0 {
+        return Err(AssemblerError::OutOfDate(stale));
+    }
     Ok(())
 }
 
-/// Determines the output file path
-fn output_path(input: &str, explicit_output: Option<&str>) -> String {
-    explicit_output.map_or_else(
-        || input.replace(".asm", ".hack"),
-        std::string::ToString::to_string,
-    )
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {error}");
+        process::exit(1);
+    }
 }
 
-fn main() -> Result<()> {
+/// Prints the command-line help to stderr
+fn print_usage(program: &str) {
+    eprintln!(
+        "Usage: {program} <input.asm> [output.hack] [--lint] [--optimize] [--where SYMBOL] [--format FORMAT]"
+    );
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!(
+        "  --lint        Warn about likely mistakes (e.g. no terminating loop, variables past RAM[255])"
+    );
+    eprintln!(
+        "  --allow LINT  Skip one lint: {}",
+        project6::lint::rule_names().join(", ")
+    );
+    eprintln!("  --optimize    Remove redundant instructions before assembling");
+    eprintln!("  --where SYM   Print the resolved address of SYM and exit");
+    eprintln!("  --format FMT  Output format: bits (default), padded32, hex, raw or ihex");
+    eprintln!("  --endian ORD  Byte order of binary formats: little (default) or big");
+    eprintln!("  --binary      Write a packed .hackbin image (big-endian words);");
+    eprintln!("                implied by an output file ending in .hackbin");
+    eprintln!("  --var-base N  Allocate variables from RAM N instead of 16");
+    eprintln!("  --stats       Print instruction and symbol counts");
+    eprintln!("  --stats-json FILE");
+    eprintln!("                Write the same counts as JSON (needs the serde feature)");
+    eprintln!("  --verify-existing");
+    eprintln!("                With a directory, check .hack files instead of writing them");
+    eprintln!("  --dump-symbols");
+    eprintln!("                Print every symbol with its address");
+    eprintln!("  --sort-by KEY Order of --dump-symbols: name (default) or address");
+    eprintln!("  --symbols     Write labels and variables to a .sym file next to the output");
+    eprintln!("  --annotate    Write a .hack.txt listing of each word with its source line");
+    eprintln!("  --check       Report every error in a file or directory; write nothing");
+    eprintln!("  --quiet       Only print errors, warnings and requested output");
+    eprintln!("  --banks       Combine path:ADDR programs into one image, each at ROM ADDR");
+    eprintln!("  -o FILE       Output file; a .bin file holds raw words unless a format is set");
+    eprintln!();
+    eprintln!("Examples:");
+    eprintln!("  {program} Add.asm");
+    eprintln!("  {program} Add.asm Add.hack");
+    eprintln!("  {program} programs/ --verify-existing");
+    eprintln!("  {program} --banks boot.asm:0 game.asm:1024 -o image.bin");
+    eprintln!();
+    eprintln!("Defaults can be set in {CONFIG_FILE_NAME} in the working directory.");
+}
+
+fn run() -> Result<()> {
     let args: Vec<String> = env::args().collect();
 
     // Validate arguments
-    if !(2..=3).contains(&args.len()) {
-        eprintln!("Usage: {} <input.asm> [output.hack]", args[0]);
-        eprintln!();
-        eprintln!("Examples:");
-        eprintln!("  {} Add.asm", args[0]);
-        eprintln!("  {} Add.asm Add.hack", args[0]);
+    let defaults = config_defaults(Path::new("."))?;
+    let Some(options) = parse_args(defaults, &args[1..]) else {
+        print_usage(&args[0]);
         process::exit(1);
+    };
+
+    if !options.banks.is_empty() {
+        let image = assemble_banks(&options.banks, &options)?;
+        let output = options.output.as_deref().unwrap_or_default();
+        let mut writer = BufWriter::new(File::create(output)?);
+        write_output(&mut writer, &image, &options)?;
+        if !options.quiet {
+            println!("Assembled {} bank(s) into {output}", options.banks.len());
+        }
+        return Ok(());
     }
 
-    let input_path = &args[1];
+    let input_path = &options.input;
 
-    // Read source file
-    let lines = read_lines(input_path)?;
+    if options.check {
+        let sources = if Path::new(input_path).is_dir() {
+            asm_files_in(Path::new(input_path))?
+        } else {
+            vec![PathBuf::from(input_path)]
+        };
+        return check_sources(&sources, &options);
+    }
+    if Path::new(input_path).is_dir() {
+        return assemble_directory(Path::new(input_path), &options);
+    }
 
-    // Initialize symbol table with predefined symbols
-    let mut symbol_table = SymbolTable::new();
+    if let Some(name) = &options.where_symbol {
+        println!("{}", query_symbol(&read_lines(input_path)?, name)?);
+        return Ok(());
+    }
 
-    // Pass 1: Build symbol table
-    first_pass(&lines, &mut symbol_table)?;
+    let (words, stats, symbols, source_lines) = assemble_file(input_path, &options)?;
+    if let Some(path) = &options.stats_json {
+        write_stats_json(path, &stats)?;
+    }
 
-    // Pass 2: Generate machine code
-    let output = output_path(input_path, args.get(2).map(String::as_str));
+    let output = output_path(
+        input_path,
+        options.output.as_deref(),
+        options.output_extension(),
+    );
     let output_file = File::create(&output)?;
     let mut writer = BufWriter::new(output_file);
+    write_output(&mut writer, &words, &options)?;
 
-    second_pass(&lines, &mut symbol_table, &mut writer)?;
-
-    println!("Assembly completed. Output written to {output}");
+    if options.symbols {
+        let listing = format_symbol_file(&read_lines(input_path)?, &symbols)?;
+        fs::write(Path::new(&output).with_extension("sym"), listing)?;
+    }
+    if options.annotate {
+        let listing = format_annotated(&words, &read_lines(input_path)?, &source_lines);
+        fs::write(format!("{output}.txt"), listing)?;
+    }
+    if !options.quiet {
+        println!("Assembly completed. Output written to {output}");
+    }
+    if options.stats {
+        println!("{stats}");
+    }
+    if options.dump_symbols {
+        print!("{}", format_symbols(&symbols, options.sort_by));
+    }
     Ok(())
 }
 
@@ -156,20 +776,366 @@ fn main() -> Result<()> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_format_symbols_order() {
+        let lines: Vec<String> = ["(LOOP)", "@i", "M=0", "@LOOP", "0;JMP"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let symbols = assemble_full(&lines, &AssemblerConfig::default()).symbols;
+
+        let by_address = format_symbols(&symbols, SymbolOrder::Address);
+        let mut listed = by_address.lines();
+        assert_eq!(listed.next(), Some("    0  LOOP"));
+        assert_eq!(listed.next(), Some("    0  R0"));
+        assert_eq!(listed.next(), Some("    0  SP"));
+        assert!(by_address.ends_with("   16  i\n16384  SCREEN\n24576  KBD\n"));
+
+        let by_name = format_symbols(&symbols, SymbolOrder::Name);
+        assert!(by_name.starts_with("    2  ARG\n24576  KBD\n    1  LCL\n    0  LOOP\n"));
+        assert!(by_name.ends_with("   16  i\n"));
+    }
+
+    #[test]
+    fn test_format_symbol_file() {
+        let lines: Vec<String> = ["@i", "M=0", "(LOOP)", "@sum", "@LOOP", "(END)", "0;JMP"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let symbols = assemble_full(&lines, &AssemblerConfig::default()).symbols;
+
+        assert_eq!(
+            format_symbol_file(&lines, &symbols).unwrap(),
+            "LOOP 2 ROM\nEND 4 ROM\ni 16 RAM\nsum 17 RAM\n"
+        );
+    }
+
+    #[test]
+    fn test_format_annotated() {
+        let lines: Vec<String> = [
+            "// Adds",
+            "@5 // five",
+            "",
+            "  D=A",
+            "(END)",
+            "@END",
+            "0;JMP",
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+        let result = assemble_full(&lines, &AssemblerConfig::default());
+        let words = result.instructions.unwrap();
+
+        assert_eq!(
+            format_annotated(&words, &lines, &result.source_lines),
+            "0000000000000101 // line 2: @5\n\
+             1110110000010000 // line 4: D=A\n\
+             0000000000000010 // line 6: @END\n\
+             1110101010000111 // line 7: 0;JMP\n"
+        );
+        assert_eq!(
+            format_annotated(&words[..1], &lines, &[]),
+            "0000000000000101\n"
+        );
+    }
+
+    #[test]
+    fn test_assembler_error_display_and_source() {
+        use std::error::Error as _;
+
+        let io = AssemblerError::from(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+        assert_eq!(io.to_string(), "I/O error: no such file");
+        assert_eq!(io.source().unwrap().to_string(), "no such file");
+
+        let lines: Vec<String> = vec!["(LOOP".to_string(), "(SP)".to_string()];
+        let parse = AssemblerError::from(
+            assembler::first_pass(&lines, &mut SymbolTable::new()).unwrap_err(),
+        );
+        assert_eq!(
+            parse.to_string(),
+            "line 1: label is missing its closing ')': `(LOOP`\n\
+             line 2: 'SP' is a predefined symbol; defining it again is ignored"
+        );
+        assert!(parse.source().unwrap().is::<AssembleError>());
+
+        // Read errors surface as I/O errors, not parse errors
+        let read = AssemblerError::from(ParserError::IoError(io::Error::other("disk")));
+        assert!(matches!(read, AssemblerError::Io(_)));
+
+        let unsourced = [
+            (
+                AssemblerError::UnresolvedSymbol("x".to_string()),
+                "symbol 'x' is not used in the program",
+            ),
+            (
+                AssemblerError::RomOverflow { words: 40000 },
+                "the image needs 40000 words but ROM holds 32768",
+            ),
+            (
+                AssemblerError::BankOverlap {
+                    bank: "b.asm".to_string(),
+                    start: 3,
+                    previous: "a.asm".to_string(),
+                    end: 4,
+                },
+                "bank b.asm at ROM 3 overlaps a.asm, which ends at ROM 4",
+            ),
+            (
+                AssemblerError::Validation(vec![Diagnostic::new("a"), Diagnostic::new("b")]),
+                "2 error(s) found",
+            ),
+            (
+                AssemblerError::OutOfDate(1),
+                "1 output file(s) are not up to date",
+            ),
+            (
+                AssemblerError::Config(".hackasm.toml: unknown field".to_string()),
+                ".hackasm.toml: unknown field",
+            ),
+            (
+                AssemblerError::MissingFeature {
+                    flag: "--stats-json",
+                    feature: "serde",
+                },
+                "--stats-json requires building with `--features serde`",
+            ),
+        ];
+        for (error, message) in unsourced {
+            assert_eq!(error.to_string(), message);
+            assert!(error.source().is_none(), "{message}");
+        }
+    }
+
+    #[test]
+    fn test_describe_error() {
+        let error = Diagnostic::at(3, "invalid comp mnemonic 'X'");
+        assert_eq!(
+            describe_error(&error),
+            "error: instruction 3: invalid comp mnemonic 'X'"
+        );
+        assert_eq!(
+            describe_error(&error.with_line(42)),
+            "error at line 42: invalid comp mnemonic 'X'"
+        );
+    }
+
     #[test]
     fn test_output_path_generation() {
-        assert_eq!(output_path("test.asm", None), "test.hack");
-        assert_eq!(output_path("test.asm", Some("custom.hack")), "custom.hack");
-        assert_eq!(output_path("dir/file.asm", None), "dir/file.hack");
-        assert_eq!(output_path("path/to/file.asm", None), "path/to/file.hack");
+        assert_eq!(output_path("test.asm", None, "hack"), "test.hack");
+        assert_eq!(
+            output_path("test.asm", Some("custom.hack"), "hack"),
+            "custom.hack"
+        );
+        assert_eq!(output_path("dir/file.asm", None, "hack"), "dir/file.hack");
+        assert_eq!(
+            output_path("path/to/file.asm", None, "hack"),
+            "path/to/file.hack"
+        );
     }
 
     #[test]
     fn test_output_path_explicit() {
-        assert_eq!(output_path("any.asm", Some("out.hack")), "out.hack");
+        assert_eq!(output_path("any.asm", Some("out.hack"), "hack"), "out.hack");
         assert_eq!(
-            output_path("any.asm", Some("path/to/out.hack")),
+            output_path("any.asm", Some("path/to/out.hack"), "hack"),
             "path/to/out.hack"
         );
     }
+
+    #[test]
+    fn test_query_symbol() {
+        let lines: Vec<String> = ["@i", "M=0", "(LOOP)", "@LOOP", "0;JMP"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        assert_eq!(query_symbol(&lines, "LOOP").unwrap(), "LOOP: ROM 2 (label)");
+        assert_eq!(query_symbol(&lines, "i").unwrap(), "i: RAM 16 (variable)");
+        assert_eq!(
+            query_symbol(&lines, "KBD").unwrap(),
+            "KBD: RAM 24576 (predefined)"
+        );
+        assert!(query_symbol(&lines, "missing").is_err());
+    }
+
+    #[test]
+    fn test_verify_existing() {
+        let dir = env::temp_dir().join(format!("project6_verify_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let words = [2, 0xEC10];
+
+        let current = dir.join("current.hack");
+        fs::write(&current, "0000000000000010\r\n1110110000010000\r\n").unwrap();
+        let stale = dir.join("stale.hack");
+        fs::write(&stale, "0000000000000011\n1110110000010000\n").unwrap();
+
+        let check = |path: &Path| verify_existing(&words, &Options::default(), path).unwrap();
+        assert_eq!(check(&current), Verification::UpToDate);
+        assert_eq!(check(&stale), Verification::OutOfDate);
+        assert_eq!(check(&dir.join("absent.hack")), Verification::Missing);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_binary_output() {
+        let args = |list: &[&str]| list.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        let options = parse_args(Options::default(), &args(&["in.asm", "--binary"])).unwrap();
+        assert!(options.binary);
+        assert_eq!(
+            output_path("in.asm", None, options.output_extension()),
+            "in.hackbin"
+        );
+        let options = parse_args(Options::default(), &args(&["in.asm", "rom.hackbin"])).unwrap();
+        assert!(options.binary);
+        assert!(
+            !parse_args(Options::default(), &args(&["in.asm"]))
+                .unwrap()
+                .binary
+        );
+
+        let mut out = Vec::new();
+        write_output(&mut out, &[2, 0xEC10], &options).unwrap();
+        assert_eq!(out, [0x00, 0x02, 0xEC, 0x10]);
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = |list: &[&str]| list.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        let options =
+            parse_args(Options::default(), &args(&["in.asm", "out.hack", "--lint"])).unwrap();
+        assert_eq!(options.input, "in.asm");
+        assert_eq!(options.output.as_deref(), Some("out.hack"));
+        assert!(options.lint);
+        assert!(!options.optimize);
+
+        let options = parse_args(
+            Options::default(),
+            &args(&["in.asm", "--allow", "a", "--allow", "b"]),
+        )
+        .unwrap();
+        assert_eq!(options.allowed_lints, ["a", "b"]);
+
+        assert!(
+            parse_args(Options::default(), &args(&["--optimize", "in.asm"]))
+                .unwrap()
+                .optimize
+        );
+
+        let options = parse_args(Options::default(), &args(&["in.asm"])).unwrap();
+        assert_eq!(options.output, None);
+        assert!(!options.lint);
+
+        let options =
+            parse_args(Options::default(), &args(&["in.asm", "--where", "LOOP"])).unwrap();
+        assert_eq!(options.where_symbol.as_deref(), Some("LOOP"));
+        assert!(parse_args(Options::default(), &args(&["in.asm", "--where"])).is_none());
+
+        let options = parse_args(
+            Options::default(),
+            &args(&["--banks", "a.asm:0", "dir/b.asm:1024", "-o", "image.bin"]),
+        )
+        .unwrap();
+        assert_eq!(
+            options.banks,
+            [
+                Bank {
+                    path: "a.asm".to_string(),
+                    offset: 0
+                },
+                Bank {
+                    path: "dir/b.asm".to_string(),
+                    offset: 1024
+                }
+            ]
+        );
+        assert_eq!(options.format, Some(OutputFormat::Raw));
+        assert!(parse_args(Options::default(), &args(&["--banks", "a.asm:0"])).is_none());
+        assert!(parse_args(Options::default(), &args(&["--banks", "a.asm", "-o", "x"])).is_none());
+        assert!(parse_args(Options::default(), &args(&["a.asm", "b.hack", "-o", "c"])).is_none());
+
+        let options = parse_args(
+            Options::default(),
+            &args(&["in.asm", "--format", "padded32"]),
+        )
+        .unwrap();
+        assert_eq!(options.format, Some(OutputFormat::Padded32));
+        // An explicit format wins over the one a `.bin` output implies
+        let options = parse_args(
+            Options::default(),
+            &args(&["in.asm", "--format", "hex", "-o", "x.bin"]),
+        )
+        .unwrap();
+        assert_eq!(options.format, Some(OutputFormat::Hex));
+        let defaults = Options {
+            format: Some(OutputFormat::IntelHex),
+            ..Options::default()
+        };
+        let options = parse_args(defaults, &args(&["in.asm", "-o", "x.bin"])).unwrap();
+        assert_eq!(options.format, Some(OutputFormat::IntelHex));
+        assert!(parse_args(Options::default(), &args(&["in.asm", "--format", "nope"])).is_none());
+        let options =
+            parse_args(Options::default(), &args(&["in.asm", "--endian", "big"])).unwrap();
+        assert_eq!(options.endian, Endian::Big);
+        assert!(parse_args(Options::default(), &args(&["in.asm", "--endian", "pdp"])).is_none());
+        let options =
+            parse_args(Options::default(), &args(&["in.asm", "--var-base", "64"])).unwrap();
+        assert_eq!(options.var_base, Some(64));
+        assert!(parse_args(Options::default(), &args(&["in.asm", "--var-base", "-1"])).is_none());
+        assert!(
+            parse_args(Options::default(), &args(&["dir", "--verify-existing"]))
+                .unwrap()
+                .verify_existing
+        );
+
+        let options = parse_args(
+            Options::default(),
+            &args(&["in.asm", "--stats", "--stats-json", "s.json"]),
+        )
+        .unwrap();
+        assert!(options.stats);
+        assert_eq!(options.stats_json.as_deref(), Some("s.json"));
+        assert!(parse_args(Options::default(), &args(&["in.asm", "--stats-json"])).is_none());
+
+        assert!(parse_args(Options::default(), &args(&[])).is_none());
+        assert!(parse_args(Options::default(), &args(&["a.asm", "b.hack", "c"])).is_none());
+        assert!(parse_args(Options::default(), &args(&["a.asm", "--bogus"])).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "config")]
+    fn test_config_file_defaults() {
+        let dir = env::temp_dir().join(format!("project6_config_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(CONFIG_FILE_NAME), "format = \"hex\"\n").unwrap();
+        let args = |list: &[&str]| list.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let output = |options: &Options| {
+            let mut out = Vec::new();
+            emit::write_words_with_endian(
+                &mut out,
+                &[2, 0xEC10],
+                options.format.unwrap_or_default(),
+                options.endian,
+            )
+            .unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let options = parse_args(config_defaults(&dir).unwrap(), &args(&["in.asm"])).unwrap();
+        assert_eq!(options.format, Some(OutputFormat::Hex));
+        assert_eq!(output(&options), "0002\nEC10\n");
+
+        let options = parse_args(
+            config_defaults(&dir).unwrap(),
+            &args(&["in.asm", "--format", "bits"]),
+        )
+        .unwrap();
+        assert_eq!(options.format, Some(OutputFormat::Bits));
+        assert_eq!(output(&options), "0000000000000010\n1110110000010000\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,216 @@
+//! Opt-in peephole optimizations over a parsed [`Program`]
+//!
+//! Every pass preserves the program's behaviour. Removing an instruction
+//! shifts the ROM address of everything after it, so passes edit the
+//! [`Program`] (whose labels are index-based) rather than the emitted code,
+//! and label addresses are recomputed automatically when it is reassembled.
+
+use crate::code::parse_a_constant;
+use crate::program::{Instruction, Program};
+use crate::symbol_table::predefined_address;
+
+/// Runs every optimization pass, returning the number of instructions removed
+pub fn optimize(program: &mut Program) -> usize {
+    collapse_duplicate_a_commands(program) + elide_redundant_a_reloads(program)
+}
+
+/// Drops an A-command that reloads the address the previous A-command loaded
+///
+/// In `@100 @100` the second load cannot change the A register, so it is
+/// removed. A label between the two keeps the second load, since a jump may
+/// land on it with a different A value.
+///
+/// # Example
+/// ```
+/// use project6::{optimize, program::Program};
+///
+/// let lines: Vec<String> = ["@100", "@100", "D=A"].iter().map(ToString::to_string).collect();
+/// let mut program = Program::from_lines(&lines).unwrap();
+/// assert_eq!(optimize::collapse_duplicate_a_commands(&mut program), 1);
+/// assert_eq!(program.to_source_lines(), ["@100", "D=A"]);
+/// ```
+pub fn collapse_duplicate_a_commands(program: &mut Program) -> usize {
+    let mut removed = 0;
+    let mut index = 1;
+
+    while index < program.instructions.len() {
+        let redundant = !program.has_label_at(index)
+            && match (
+                &program.instructions[index - 1],
+                &program.instructions[index],
+            ) {
+                (Instruction::A(previous), Instruction::A(current)) => {
+                    same_address(program, previous, current)
+                }
+                _ => false,
+            };
+
+        if redundant {
+            program.remove_instruction(index);
+            removed += 1;
+        } else {
+            index += 1;
+        }
+    }
+
+    removed
+}
+
+/// Drops an A-command that reloads the value A already holds
+///
+/// Generalizes [`collapse_duplicate_a_commands`] across C-instructions: in
+/// `@i D=M @i M=D+1` the second `@i` is redundant because `D=M` leaves A
+/// alone. The last loaded value is forgotten at every label (a jump may
+/// arrive with any A) and after any C-instruction whose dest writes A.
+///
+/// # Example
+/// ```
+/// use project6::{optimize, program::Program};
+///
+/// let lines: Vec<String> = ["@i", "D=M", "@i", "M=D+1"].iter().map(ToString::to_string).collect();
+/// let mut program = Program::from_lines(&lines).unwrap();
+/// assert_eq!(optimize::elide_redundant_a_reloads(&mut program), 1);
+/// assert_eq!(program.to_source_lines(), ["@i", "D=M", "M=D+1"]);
+/// ```
+pub fn elide_redundant_a_reloads(program: &mut Program) -> usize {
+    let mut removed = 0;
+    let mut index = 0;
+    let mut loaded: Option<String> = None;
+
+    while index < program.instructions.len() {
+        if program.has_label_at(index) {
+            loaded = None;
+        }
+
+        match &program.instructions[index] {
+            Instruction::A(symbol) => {
+                if loaded
+                    .as_deref()
+                    .is_some_and(|previous| same_address(program, previous, symbol))
+                {
+                    program.remove_instruction(index);
+                    removed += 1;
+                    continue;
+                }
+                loaded = Some(symbol.clone());
+            }
+            Instruction::C { dest, .. } => {
+                if dest.contains('A') {
+                    loaded = None;
+                }
+            }
+        }
+        index += 1;
+    }
+
+    removed
+}
+
+/// Returns `true` if two A-command symbols are guaranteed to load the same value
+///
+/// Labels only compare equal to labels at the same index: comparing a label
+/// against a constant would break as soon as an earlier removal moved it.
+fn same_address(program: &Program, first: &str, second: &str) -> bool {
+    if first == second {
+        return true;
+    }
+
+    match (constant_value(first), constant_value(second)) {
+        (Some(a), Some(b)) => a == b,
+        (None, None) => program
+            .label_address(first)
+            .zip(program.label_address(second))
+            .is_some_and(|(a, b)| a == b),
+        _ => false,
+    }
+}
+
+/// Resolves numeric and predefined symbols, whose values never move
+///
+/// Numbers are read as the assembler reads them, so `@0x10` and `@16` load
+/// the same constant.
+fn constant_value(symbol: &str) -> Option<u16> {
+    match parse_a_constant(symbol) {
+        Some(constant) => constant.ok(),
+        None => predefined_address(symbol),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program(source: &[&str]) -> Program {
+        let lines: Vec<String> = source.iter().map(ToString::to_string).collect();
+        Program::from_lines(&lines).unwrap()
+    }
+
+    #[test]
+    fn test_duplicate_pair_removed_and_jumps_resolve() {
+        let mut program = program(&["@100", "@100", "D=A", "(LOOP)", "@LOOP", "0;JMP"]);
+
+        assert_eq!(collapse_duplicate_a_commands(&mut program), 1);
+        assert_eq!(program.instructions.len(), 4);
+        assert_eq!(program.label_address("LOOP"), Some(2));
+        assert_eq!(program.instructions[2], Instruction::A("LOOP".to_string()));
+    }
+
+    #[test]
+    fn test_equal_constants_and_aliases_collapse() {
+        let mut program = program(&["@SP", "@R0", "@0", "M=0"]);
+        assert_eq!(collapse_duplicate_a_commands(&mut program), 2);
+        assert_eq!(program.to_source_lines(), ["@SP", "M=0"]);
+    }
+
+    #[test]
+    fn test_radix_constants_collapse() {
+        let mut program = program(&["@0x10", "@16", "@10h", "@0b10000", "D=A"]);
+        assert_eq!(collapse_duplicate_a_commands(&mut program), 3);
+        assert_eq!(program.to_source_lines(), ["@0x10", "D=A"]);
+    }
+
+    #[test]
+    fn test_intervening_label_keeps_duplicate() {
+        let mut program = program(&["@100", "(TARGET)", "@100", "D=A"]);
+        assert_eq!(collapse_duplicate_a_commands(&mut program), 0);
+    }
+
+    #[test]
+    fn test_label_never_equals_constant() {
+        let mut program = program(&["@2", "@START", "(START)", "D=A"]);
+        assert_eq!(collapse_duplicate_a_commands(&mut program), 0);
+    }
+
+    #[test]
+    fn test_different_addresses_kept() {
+        let mut program = program(&["@1", "@2", "@i", "@j", "D=A"]);
+        assert_eq!(optimize(&mut program), 0);
+    }
+
+    #[test]
+    fn test_reload_after_non_clobbering_c_removed() {
+        let mut program = program(&[
+            "@i", "D=M", "@i", "M=D+1", "@END", "D;JGT", "@i", "M=0", "(END)", "@END", "0;JMP",
+        ]);
+
+        assert_eq!(elide_redundant_a_reloads(&mut program), 1);
+        assert_eq!(
+            program.to_source_lines(),
+            [
+                "@i", "D=M", "M=D+1", "@END", "D;JGT", "@i", "M=0", "(END)", "@END", "0;JMP"
+            ]
+        );
+        // END moved up by one and still labels the final loop
+        assert_eq!(program.label_address("END"), Some(7));
+        assert_eq!(program.jump_target("END"), Some(7));
+    }
+
+    #[test]
+    fn test_reload_after_a_write_kept() {
+        let mut clobbered = program(&["@i", "AM=M-1", "@i", "D=M"]);
+        assert_eq!(elide_redundant_a_reloads(&mut clobbered), 0);
+
+        let mut labelled = program(&["@i", "D=M", "(LOOP)", "@i", "M=D"]);
+        assert_eq!(elide_redundant_a_reloads(&mut labelled), 0);
+    }
+}
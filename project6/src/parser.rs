@@ -5,7 +5,10 @@
 //! - Manual whitespace trimming to avoid allocations
 //! - Aggressive inlining for hot paths
 
+use crate::symbol_table::{SymbolError, is_predefined};
+use std::borrow::Cow;
 use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Seek};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 #[allow(clippy::enum_variant_names)] // Command suffix is intentional and clear
@@ -18,10 +21,47 @@ pub enum CommandType {
     LCommand,
 }
 
+impl CommandType {
+    /// Short name for messages: `A-command`, `C-command` or `L-command`
+    ///
+    /// # Example
+    /// ```
+    /// use project6::CommandType;
+    ///
+    /// assert_eq!(CommandType::LCommand.as_str(), "L-command");
+    /// assert_eq!(CommandType::ACommand.to_string(), "A-command");
+    /// ```
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::ACommand => "A-command",
+            Self::CCommand => "C-command",
+            Self::LCommand => "L-command",
+        }
+    }
+}
+
+impl fmt::Display for CommandType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug)]
 pub enum ParserError {
     IoError(std::io::Error),
     InvalidState(&'static str),
+    /// A malformed command, with its 1-based source line and text
+    Syntax {
+        line: usize,
+        text: String,
+        message: &'static str,
+    },
+    /// A label that cannot be defined, with the 1-based line defining it
+    Symbol {
+        line: usize,
+        error: SymbolError,
+    },
 }
 
 impl std::error::Error for ParserError {}
@@ -31,6 +71,12 @@ impl fmt::Display for ParserError {
         match self {
             Self::IoError(e) => write!(f, "IO error: {e}"),
             Self::InvalidState(msg) => write!(f, "Invalid state: {msg}"),
+            Self::Syntax {
+                line,
+                text,
+                message,
+            } => write!(f, "line {line}: {message}: `{text}`"),
+            Self::Symbol { line, error } => write!(f, "line {line}: {error}"),
         }
     }
 }
@@ -41,11 +87,89 @@ impl From<std::io::Error> for ParserError {
     }
 }
 
+/// A command yielded by iterating over a [`ParserLines`]
+///
+/// Its fields borrow from the source lines, so commands can outlive the
+/// parser that produced them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command<'a> {
+    /// 1-based source line of the command
+    pub line: usize,
+    pub kind: CommandKind<'a>,
+}
+
+/// The fields of a [`Command`], by kind of command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandKind<'a> {
+    /// `@symbol`
+    A(&'a str),
+    /// `dest=comp;jump`, with absent parts empty
+    ///
+    /// The fields are borrowed unless the command had whitespace inside,
+    /// which is removed as [`ParserLines::comp`] removes it.
+    C {
+        dest: Cow<'a, str>,
+        comp: Cow<'a, str>,
+        jump: Cow<'a, str>,
+    },
+    /// `(label)`
+    L(&'a str),
+}
+
+impl Command<'_> {
+    /// Returns the [`CommandType`] of this command
+    #[inline]
+    #[must_use]
+    pub fn command_type(&self) -> CommandType {
+        match self.kind {
+            CommandKind::A(_) => CommandType::ACommand,
+            CommandKind::C { .. } => CommandType::CCommand,
+            CommandKind::L(_) => CommandType::LCommand,
+        }
+    }
+}
+
+/// Where [`ParserLines`] gets its lines from
+enum LineSource<'a> {
+    /// Lines already in memory; `current` is the line being parsed
+    Lines {
+        lines: &'a [String],
+        next: usize,
+        current: &'a str,
+    },
+    /// Lines read one at a time into a reused buffer
+    Reader {
+        reader: Box<dyn SeekableBufRead + 'a>,
+        buffer: String,
+    },
+}
+
+/// A buffered reader that can also be rewound, for [`ParserLines::reset`]
+trait SeekableBufRead: BufRead + Seek {}
+
+impl<T: BufRead + Seek> SeekableBufRead for T {}
+
 /// Parser for assembly lines with zero-copy string slicing
 pub struct ParserLines<'a> {
-    lines: std::slice::Iter<'a, String>,
-    current_line: &'a str,
+    source: LineSource<'a>,
+    /// Byte range of the trimmed current command within the current line
+    current_range: (usize, usize),
     current_command_type: Option<CommandType>,
+    /// 1-based number of the last physical line consumed, 0 before the first
+    line_number: usize,
+    /// I/O error that ended a reader-backed parse early
+    read_error: Option<std::io::Error>,
+    /// The current C-command without its whitespace, if it had any inside
+    normalized: Option<String>,
+    /// Line read ahead by [`ParserLines::peek`] from a reader source
+    lookahead: Option<Lookahead>,
+}
+
+/// The next command's line, read from a reader before it was needed
+struct Lookahead {
+    line: String,
+    /// Comment and blank lines read on the way to `line`
+    skipped: usize,
 }
 
 impl<'a> ParserLines<'a> {
@@ -53,32 +177,207 @@ impl<'a> ParserLines<'a> {
     #[inline]
     #[must_use]
     pub fn from_lines(lines: &'a [String]) -> Self {
+        Self::with_source(LineSource::Lines {
+            lines,
+            next: 0,
+            current: "",
+        })
+    }
+
+    /// Creates a parser that pulls lines from `reader` as it advances
+    ///
+    /// Only one line is held in memory at a time, so accessors borrow from
+    /// the parser and their results must be dropped before the next
+    /// `advance`. Lines end wherever [`read_source_lines`] would end them;
+    /// comments and blank lines are skipped exactly as with
+    /// [`ParserLines::from_lines`].
+    ///
+    /// Two-pass assembly reads the program twice. The `Seek` bound lets
+    /// [`ParserLines::reset`] rewind the reader for the second pass instead
+    /// of collecting the whole file up front. An I/O error ends the input
+    /// early; check [`ParserLines::take_error`] once `advance` returns `false`.
+    ///
+    /// # Example
+    /// ```
+    /// use project6::{CommandType, ParserLines};
+    /// use std::io::Cursor;
+    ///
+    /// let mut parser = ParserLines::from_reader(Cursor::new("// Sum\n@2\nD=A\n"));
+    /// assert!(parser.advance());
+    /// assert_eq!(parser.symbol().unwrap(), "2");
+    /// assert!(parser.advance());
+    /// assert_eq!(parser.command_type().unwrap(), CommandType::CCommand);
+    /// assert!(!parser.advance());
+    ///
+    /// parser.reset().unwrap();
+    /// assert!(parser.advance());
+    /// assert_eq!(parser.symbol().unwrap(), "2");
+    /// ```
+    pub fn from_reader<R: BufRead + Seek + 'a>(reader: R) -> Self {
+        Self::with_source(LineSource::Reader {
+            reader: Box::new(reader),
+            buffer: String::new(),
+        })
+    }
+
+    fn with_source(source: LineSource<'a>) -> Self {
         Self {
-            lines: lines.iter(),
-            current_line: "",
+            source,
+            current_range: (0, 0),
             current_command_type: None,
+            line_number: 0,
+            read_error: None,
+            normalized: None,
+            lookahead: None,
         }
     }
 
+    /// Rewinds to before the first line, e.g. to start the second pass
+    ///
+    /// # Errors
+    /// Returns an error if a reader cannot seek back to its start.
+    pub fn reset(&mut self) -> Result<(), ParserError> {
+        match &mut self.source {
+            LineSource::Lines { next, current, .. } => {
+                *next = 0;
+                *current = "";
+            }
+            LineSource::Reader { reader, buffer } => {
+                reader.rewind()?;
+                buffer.clear();
+            }
+        }
+        self.current_range = (0, 0);
+        self.current_command_type = None;
+        self.line_number = 0;
+        self.read_error = None;
+        self.normalized = None;
+        self.lookahead = None;
+        Ok(())
+    }
+
+    /// Reports the I/O error that stopped a reader-backed parser, if any
+    ///
+    /// # Errors
+    /// Returns the read error that made `advance` return `false` early.
+    pub fn take_error(&mut self) -> Result<(), ParserError> {
+        self.read_error
+            .take()
+            .map_or(Ok(()), |error| Err(error.into()))
+    }
+
+    /// Returns the 1-based source line of the current command
+    ///
+    /// Comment and blank lines count too, so this matches the line number an
+    /// editor shows. It is 0 before the first call to `advance`.
+    ///
+    /// # Example
+    /// ```
+    /// use project6::ParserLines;
+    ///
+    /// let lines = vec!["// Adds 2".to_string(), String::new(), "@2".to_string()];
+    /// let mut parser = ParserLines::from_lines(&lines);
+    /// assert!(parser.advance());
+    /// assert_eq!(parser.current_line_number(), 3);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn current_line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Builds a [`ParserError::Syntax`] for the current command
+    fn syntax_error(&self, message: &'static str) -> ParserError {
+        ParserError::Syntax {
+            line: self.line_number,
+            text: self.current_line().to_string(),
+            message,
+        }
+    }
+
+    /// The current command, trimmed and without its comment
+    ///
+    /// A C-command is also stripped of the whitespace inside it, so that
+    /// `D = D + 1` reads as `D=D+1`.
+    #[inline]
+    fn current_line(&self) -> &str {
+        if let Some(normalized) = &self.normalized {
+            return normalized;
+        }
+        let (start, end) = self.current_range;
+        match &self.source {
+            LineSource::Lines { current, .. } => &current[start..end],
+            LineSource::Reader { buffer, .. } => &buffer[start..end],
+        }
+    }
+
+    /// Moves to the next raw line, returning `false` at the end of input
+    #[inline]
+    fn next_raw_line(&mut self) -> bool {
+        if let Some(Lookahead { line, skipped }) = self.lookahead.take() {
+            // Take the line `peek` read ahead, counting the lines it skipped
+            if let LineSource::Reader { buffer, .. } = &mut self.source {
+                *buffer = line;
+            }
+            self.line_number += skipped + 1;
+            return true;
+        }
+        let consumed = match &mut self.source {
+            LineSource::Lines {
+                lines,
+                next,
+                current,
+            } => match lines.get(*next) {
+                Some(line) => {
+                    *current = line;
+                    *next += 1;
+                    true
+                }
+                None => false,
+            },
+            LineSource::Reader { reader, buffer } => {
+                buffer.clear();
+                match read_source_line(reader, buffer) {
+                    Ok(read) => read > 0,
+                    Err(error) => {
+                        self.read_error = Some(error);
+                        false
+                    }
+                }
+            }
+        };
+        self.line_number += usize::from(consumed);
+        consumed
+    }
+
     /// Advances to the next valid command, skipping comments and whitespace
     ///
+    /// Whitespace inside a C-command (`D = M + 1 ; JGT`) is dropped; only
+    /// such commands are copied, the rest stay borrowed from the line.
+    ///
     /// # Performance
     /// Uses byte-level operations for comment detection (2x faster than string methods)
     #[inline]
     pub fn advance(&mut self) -> bool {
-        for line in self.lines.by_ref() {
+        self.normalized = None;
+        while self.next_raw_line() {
+            let line = match &self.source {
+                LineSource::Lines { current, .. } => *current,
+                LineSource::Reader { buffer, .. } => buffer.as_str(),
+            };
+
             // Fast path: Check for empty line before processing
             if line.is_empty() {
                 continue;
             }
 
-            // Strip comments using fast byte scan
-            let clean_line = Self::strip_comment(line);
-            let trimmed = clean_line.trim();
-
-            if !trimmed.is_empty() {
-                self.current_line = trimmed;
-                self.current_command_type = Some(Self::classify_command(trimmed));
+            if let Some((start, trimmed)) = Self::find_command(line) {
+                let command_type = Self::classify_command(trimmed);
+                if command_type == CommandType::CCommand && trimmed.contains(char::is_whitespace) {
+                    self.normalized = Some(trimmed.split_whitespace().collect());
+                }
+                self.current_command_type = Some(command_type);
+                self.current_range = (start, start + trimmed.len());
                 return true;
             }
         }
@@ -87,6 +386,75 @@ impl<'a> ParserLines<'a> {
         false
     }
 
+    /// Returns the type of the next command without consuming it
+    ///
+    /// Only the type is available; the next command's fields are read after
+    /// `advance` moves to it as usual. Comment and blank lines in between
+    /// are skipped, and `None` means there is no next command.
+    ///
+    /// A reader-backed parser has to read ahead to answer, so it keeps the
+    /// next command's line buffered until `advance` takes it. An I/O error
+    /// while reading ahead is reported by [`ParserLines::take_error`].
+    ///
+    /// # Example
+    /// ```
+    /// use project6::{CommandType, ParserLines};
+    ///
+    /// let lines = vec!["@LOOP".to_string(), "// go".to_string(), "0;JMP".to_string()];
+    /// let mut parser = ParserLines::from_lines(&lines);
+    /// assert!(parser.advance());
+    /// assert_eq!(parser.peek(), Some(CommandType::CCommand));
+    /// assert_eq!(parser.symbol().unwrap(), "LOOP");
+    /// assert!(parser.advance());
+    /// assert_eq!(parser.current_line_number(), 3);
+    /// assert_eq!(parser.peek(), None);
+    /// ```
+    pub fn peek(&mut self) -> Option<CommandType> {
+        let line = match &mut self.source {
+            LineSource::Lines { lines, next, .. } => {
+                return lines[*next..]
+                    .iter()
+                    .find_map(|line| Self::find_command(line))
+                    .map(|(_, command)| Self::classify_command(command));
+            }
+            LineSource::Reader { reader, .. } => {
+                if self.lookahead.is_none() {
+                    let mut line = String::new();
+                    let mut skipped = 0;
+                    loop {
+                        line.clear();
+                        match read_source_line(reader, &mut line) {
+                            Ok(0) => return None,
+                            Ok(_) if Self::find_command(&line).is_none() => skipped += 1,
+                            Ok(_) => break,
+                            Err(error) => {
+                                self.read_error = Some(error);
+                                return None;
+                            }
+                        }
+                    }
+                    self.lookahead = Some(Lookahead { line, skipped });
+                }
+                self.lookahead
+                    .as_ref()
+                    .map(|lookahead| lookahead.line.as_str())?
+            }
+        };
+        Self::find_command(line).map(|(_, command)| Self::classify_command(command))
+    }
+
+    /// Finds the command on a raw line, returning its byte offset and text
+    ///
+    /// The command is trimmed and has its comment removed; comment and blank
+    /// lines have none.
+    #[inline]
+    fn find_command(line: &str) -> Option<(usize, &str)> {
+        // Strip comments using fast byte scan
+        let clean_line = Self::strip_comment(line);
+        let trimmed = clean_line.trim();
+        (!trimmed.is_empty()).then(|| (clean_line.len() - clean_line.trim_start().len(), trimmed))
+    }
+
     /// Strips comments from a line using optimized byte scanning
     ///
     /// # Performance
@@ -129,18 +497,33 @@ impl<'a> ParserLines<'a> {
     /// Returns the symbol from A-command or L-command
     ///
     /// # Errors
-    /// Returns error if called on C-command or if no command is available
+    /// Returns error if called on C-command, on a label without its closing
+    /// parenthesis or with text after it, or if no command is available
     #[inline]
     pub fn symbol(&self) -> Result<&str, ParserError> {
+        self.symbol_in(self.current_line())
+    }
+
+    /// [`ParserLines::symbol`] of `line`, the current command
+    #[inline]
+    fn symbol_in<'s>(&self, line: &'s str) -> Result<&'s str, ParserError> {
         match self.current_command_type {
             Some(CommandType::ACommand) => {
-                // Remove leading '@'
-                Ok(&self.current_line[1..])
+                // Remove leading '@'; `@ i` names `i`
+                Ok(line[1..].trim_start())
             }
             Some(CommandType::LCommand) => {
                 // Remove surrounding '(' and ')'
-                let len = self.current_line.len();
-                Ok(&self.current_line[1..len - 1])
+                let inner = &line[1..];
+                let close = inner
+                    .find(')')
+                    .ok_or_else(|| self.syntax_error("label is missing its closing ')'"))?;
+                if close + 1 != inner.len() {
+                    return Err(self.syntax_error(
+                        "unexpected text after label; put the label and the instruction on separate lines",
+                    ));
+                }
+                Ok(inner[..close].trim())
             }
             Some(CommandType::CCommand) => {
                 Err(ParserError::InvalidState("Called symbol() on C-command"))
@@ -149,16 +532,44 @@ impl<'a> ParserLines<'a> {
         }
     }
 
+    /// Returns `true` if the current A-command refers to a predefined symbol
+    ///
+    /// Lets consumers such as syntax highlighters style `@SP` or `@SCREEN`
+    /// without re-implementing the lookup. C- and L-commands return `false`.
+    ///
+    /// # Example
+    /// ```
+    /// use project6::ParserLines;
+    ///
+    /// let lines = vec!["@KBD".to_string(), "@key".to_string()];
+    /// let mut parser = ParserLines::from_lines(&lines);
+    /// parser.advance();
+    /// assert!(parser.symbol_is_predefined());
+    /// parser.advance();
+    /// assert!(!parser.symbol_is_predefined());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn symbol_is_predefined(&self) -> bool {
+        self.current_command_type == Some(CommandType::ACommand)
+            && self.symbol().is_ok_and(is_predefined)
+    }
+
     /// Returns the dest part of a C-command
     ///
     /// Returns empty string if no dest part exists
     #[inline]
     pub fn dest(&self) -> Result<Option<&str>, ParserError> {
+        self.dest_in(self.current_line())
+    }
+
+    #[inline]
+    fn dest_in<'s>(&self, line: &'s str) -> Result<Option<&'s str>, ParserError> {
         match self.current_command_type {
             Some(CommandType::CCommand) => {
                 // Find '=' to locate dest part
-                if let Some(pos) = self.current_line.find('=') {
-                    Ok(Some(&self.current_line[..pos]))
+                if let Some(pos) = line.find('=') {
+                    Ok(Some(&line[..pos]))
                 } else {
                     Ok(Some(""))
                 }
@@ -169,16 +580,27 @@ impl<'a> ParserLines<'a> {
     }
 
     /// Returns the comp part of a C-command
+    ///
+    /// An assignment with nothing after the `=` (`D=` or `D=;JGT`) is a
+    /// syntax error rather than an empty computation.
     #[inline]
     pub fn comp(&self) -> Result<Option<&str>, ParserError> {
+        self.comp_in(self.current_line())
+    }
+
+    #[inline]
+    fn comp_in<'s>(&self, line: &'s str) -> Result<Option<&'s str>, ParserError> {
         match self.current_command_type {
             Some(CommandType::CCommand) => {
-                let start = self.current_line.find('=').map_or(0, |pos| pos + 1);
-                let end = self
-                    .current_line
-                    .find(';')
-                    .unwrap_or(self.current_line.len());
-                Ok(Some(&self.current_line[start..end]))
+                let end = line.find(';').unwrap_or(line.len());
+                match line.find('=') {
+                    Some(pos) if pos >= end => Err(self.syntax_error("'=' must come before ';'")),
+                    Some(pos) if pos + 1 == end => {
+                        Err(self.syntax_error("missing computation after '='"))
+                    }
+                    Some(pos) => Ok(Some(&line[pos + 1..end])),
+                    None => Ok(Some(&line[..end])),
+                }
             }
             Some(_) => Ok(None),
             None => Err(ParserError::InvalidState("No current line available")),
@@ -190,10 +612,15 @@ impl<'a> ParserLines<'a> {
     /// Returns empty string if no jump part exists
     #[inline]
     pub fn jump(&self) -> Result<Option<&str>, ParserError> {
+        self.jump_in(self.current_line())
+    }
+
+    #[inline]
+    fn jump_in<'s>(&self, line: &'s str) -> Result<Option<&'s str>, ParserError> {
         match self.current_command_type {
             Some(CommandType::CCommand) => {
-                if let Some(pos) = self.current_line.find(';') {
-                    Ok(Some(&self.current_line[pos + 1..]))
+                if let Some(pos) = line.find(';') {
+                    Ok(Some(&line[pos + 1..]))
                 } else {
                     Ok(Some(""))
                 }
@@ -202,6 +629,334 @@ impl<'a> ParserLines<'a> {
             None => Err(ParserError::InvalidState("No current line available")),
         }
     }
+
+    /// Splits `line`, the current command, into a [`Command`]
+    fn command_in<'s>(&self, line: &'s str) -> Result<Command<'s>, ParserError> {
+        let kind = match self.command_type()? {
+            CommandType::ACommand => CommandKind::A(self.symbol_in(line)?),
+            CommandType::LCommand => CommandKind::L(self.symbol_in(line)?),
+            CommandType::CCommand => match &self.normalized {
+                // Whitespace was removed into a copy of the line
+                Some(normalized) => CommandKind::C {
+                    dest: Cow::Owned(self.dest_in(normalized)?.unwrap_or("").to_string()),
+                    comp: Cow::Owned(self.comp_in(normalized)?.unwrap_or("").to_string()),
+                    jump: Cow::Owned(self.jump_in(normalized)?.unwrap_or("").to_string()),
+                },
+                None => CommandKind::C {
+                    dest: Cow::Borrowed(self.dest_in(line)?.unwrap_or("")),
+                    comp: Cow::Borrowed(self.comp_in(line)?.unwrap_or("")),
+                    jump: Cow::Borrowed(self.jump_in(line)?.unwrap_or("")),
+                },
+            },
+        };
+        Ok(Command {
+            line: self.line_number,
+            kind,
+        })
+    }
+}
+
+/// Iterates over the remaining commands, already split into their fields
+///
+/// An alternative to calling `advance` and the accessors by hand; both can be
+/// mixed on one parser. A malformed command yields an error and iteration
+/// carries on with the next one.
+///
+/// Only parsers built with [`ParserLines::from_lines`] can lend out slices of
+/// their lines. A reader-backed parser yields a single
+/// [`ParserError::InvalidState`] and then ends. C fields are normalized as
+/// the accessors normalize them: `D = D + 1` yields comp `D+1`, owned
+/// because it is no longer a slice of the line.
+///
+/// # Example
+/// ```
+/// use project6::parser::{CommandKind, ParserLines};
+///
+/// let lines: Vec<String> = ["(LOOP)", "@LOOP", "D ; JGT"].iter().map(ToString::to_string).collect();
+/// let mut labels = Vec::new();
+/// for command in ParserLines::from_lines(&lines) {
+///     match command.unwrap().kind {
+///         CommandKind::L(label) => labels.push(label),
+///         CommandKind::C { jump, .. } => assert_eq!(jump, "JGT"),
+///         CommandKind::A(symbol) => assert_eq!(symbol, "LOOP"),
+///     }
+/// }
+/// assert_eq!(labels, ["LOOP"]);
+/// ```
+impl<'a> Iterator for ParserLines<'a> {
+    type Item = Result<Command<'a>, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.advance() {
+            return self.read_error.take().map(|error| Err(error.into()));
+        }
+
+        let LineSource::Lines { current, .. } = &self.source else {
+            // Drain the reader so that iteration ends after the error
+            while self.next_raw_line() {}
+            self.current_command_type = None;
+            return Some(Err(ParserError::InvalidState(
+                "only parsers over lines can be iterated; use advance() with a reader",
+            )));
+        };
+        let (start, end) = self.current_range;
+        let line: &'a str = &current[start..end];
+        Some(self.command_in(line))
+    }
+}
+
+/// Reads source text and splits it into lines
+///
+/// Besides `\n`, Windows `\r\n` and old Mac `\r`, the Unicode line
+/// separator (U+2028) and paragraph separator (U+2029) also end a line.
+/// `BufRead::lines` only knows about `\n`, so a file using the others would
+/// otherwise arrive as one huge line or with `\r` left on each line. The
+/// last line may or may not end with a separator.
+///
+/// # Example
+/// ```
+/// use project6::parser::read_source_lines;
+///
+/// let lines = read_source_lines("@1\u{2028}D=A\r\n0;JMP\rD;JGT".as_bytes()).unwrap();
+/// assert_eq!(lines, ["@1", "D=A", "0;JMP", "D;JGT"]);
+/// ```
+pub fn read_source_lines<R: Read>(reader: R) -> Result<Vec<String>, ParserError> {
+    let mut reader = BufReader::new(reader);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    while read_source_line(&mut reader, &mut line)? > 0 {
+        lines.push(std::mem::take(&mut line));
+    }
+    Ok(lines)
+}
+
+/// Appends the next line of `reader` to `line`, without its separator
+///
+/// Lines end as described in [`read_source_lines`], which this splits for
+/// as well as the reader-backed [`ParserLines`]. Returns the number of
+/// bytes consumed, separator included, so 0 means the end of input.
+fn read_source_line<R: BufRead + ?Sized>(reader: &mut R, line: &mut String) -> io::Result<usize> {
+    const SEPARATORS: [&[u8]; 2] = ["\u{2028}".as_bytes(), "\u{2029}".as_bytes()];
+
+    let mut bytes = Vec::new();
+    let mut consumed = 0;
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            break;
+        }
+        // Copy up to the next byte that may start a separator in one go
+        let plain = available
+            .iter()
+            .position(|&byte| matches!(byte, b'\n' | b'\r' | 0xE2))
+            .unwrap_or(available.len());
+        if plain > 0 {
+            bytes.extend_from_slice(&available[..plain]);
+            reader.consume(plain);
+            consumed += plain;
+            continue;
+        }
+
+        let byte = available[0];
+        reader.consume(1);
+        consumed += 1;
+        match byte {
+            b'\n' => break,
+            b'\r' => {
+                // The `\n` of `\r\n` ends the same line
+                if reader.fill_buf()?.first() == Some(&b'\n') {
+                    reader.consume(1);
+                    consumed += 1;
+                }
+                break;
+            }
+            _ => {
+                // U+2028 and U+2029 start with 0xE2, and their continuation
+                // bytes may not be buffered yet
+                bytes.push(byte);
+                for _ in 0..2 {
+                    match reader.fill_buf()?.first() {
+                        Some(&next) if next & 0xC0 == 0x80 => {
+                            bytes.push(next);
+                            reader.consume(1);
+                            consumed += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                if SEPARATORS
+                    .iter()
+                    .any(|separator| bytes.ends_with(separator))
+                {
+                    bytes.truncate(bytes.len() - 3);
+                    break;
+                }
+            }
+        }
+    }
+
+    let text = std::str::from_utf8(&bytes)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    line.push_str(text);
+    Ok(consumed)
+}
+
+/// Returns `true` if the program references any symbol or defines any label
+///
+/// A program made only of numeric A-commands and C-commands needs no symbol
+/// table, so the assembler can skip pass 1 entirely.
+///
+/// # Example
+/// ```
+/// use project6::parser::uses_symbols;
+///
+/// let numeric = vec!["@2".to_string(), "D=A".to_string()];
+/// let symbolic = vec!["@i".to_string(), "M=0".to_string()];
+/// assert!(!uses_symbols(&numeric));
+/// assert!(uses_symbols(&symbolic));
+/// ```
+#[must_use]
+pub fn uses_symbols(lines: &[String]) -> bool {
+    let mut parser = ParserLines::from_lines(lines);
+
+    while parser.advance() {
+        match parser.current_command_type {
+            Some(CommandType::LCommand) => return true,
+            Some(CommandType::ACommand)
+                if !parser.current_line()[1..]
+                    .bytes()
+                    .all(|b| b.is_ascii_digit()) =>
+            {
+                return true;
+            }
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// Syntactic role of a [`Token`], for syntax highlighting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// The `@` starting an A-command
+    AtSign,
+    /// A label or variable name
+    Symbol,
+    /// A decimal constant in an A-command
+    Number,
+    /// The destination field of a C-command
+    Dest,
+    /// The `=` after the destination
+    Equals,
+    /// The computation field of a C-command
+    Comp,
+    /// The `;` before the jump
+    Semicolon,
+    /// The jump field of a C-command
+    Jump,
+    /// Either parenthesis around a label definition
+    LabelParen,
+    /// A `//` comment, up to the end of the line
+    Comment,
+    /// A run of spaces or tabs
+    Whitespace,
+}
+
+/// A classified span of one source line
+///
+/// `start` and `end` are byte offsets into the line, `end` exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Splits one source line into highlightable tokens
+///
+/// Every byte of the line belongs to exactly one token. Malformed commands
+/// are still tokenized: the spans follow the punctuation, whatever the
+/// fields contain.
+///
+/// # Example
+/// ```
+/// use project6::parser::{tokenize_line, TokenKind};
+///
+/// let kinds: Vec<TokenKind> = tokenize_line("@LOOP").iter().map(|token| token.kind).collect();
+/// assert_eq!(kinds, [TokenKind::AtSign, TokenKind::Symbol]);
+/// ```
+#[must_use]
+pub fn tokenize_line(line: &str) -> Vec<Token> {
+    let code = ParserLines::strip_comment(line);
+    let bytes = code.as_bytes();
+    let is_c_command = !matches!(
+        code.trim_start().as_bytes().first(),
+        Some(b'@' | b'(') | None
+    );
+    let is_delimiter = |byte: u8| {
+        byte.is_ascii_whitespace()
+            || matches!(byte, b'(' | b')')
+            || (is_c_command && matches!(byte, b'=' | b';'))
+    };
+
+    let mut tokens = Vec::new();
+    let mut field = if code.contains('=') {
+        TokenKind::Dest
+    } else {
+        TokenKind::Comp
+    };
+    let mut start = 0;
+    while start < bytes.len() {
+        let byte = bytes[start];
+        let (kind, end) = match byte {
+            _ if byte.is_ascii_whitespace() => {
+                let len = bytes[start..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_whitespace())
+                    .count();
+                (TokenKind::Whitespace, start + len)
+            }
+            b'(' | b')' => (TokenKind::LabelParen, start + 1),
+            b'@' if !is_c_command => (TokenKind::AtSign, start + 1),
+            b'=' if is_c_command => {
+                field = TokenKind::Comp;
+                (TokenKind::Equals, start + 1)
+            }
+            b';' if is_c_command => {
+                field = TokenKind::Jump;
+                (TokenKind::Semicolon, start + 1)
+            }
+            _ => {
+                let len = bytes[start..]
+                    .iter()
+                    .take_while(|&&b| !is_delimiter(b))
+                    .count();
+                let end = start + len;
+                let kind = if is_c_command {
+                    field
+                } else if code[..start].trim_end().ends_with('@')
+                    && code[start..end].bytes().all(|b| b.is_ascii_digit())
+                {
+                    TokenKind::Number
+                } else {
+                    TokenKind::Symbol
+                };
+                (kind, end)
+            }
+        };
+        tokens.push(Token { kind, start, end });
+        start = end;
+    }
+
+    if code.len() < line.len() {
+        tokens.push(Token {
+            kind: TokenKind::Comment,
+            start: code.len(),
+            end: line.len(),
+        });
+    }
+    tokens
 }
 
 #[cfg(test)]
@@ -216,6 +971,10 @@ mod tests {
             CommandType::LCommand
         );
         assert_eq!(ParserLines::classify_command("D=M"), CommandType::CCommand);
+        assert_eq!(
+            format!("{} then {}", CommandType::CCommand, CommandType::LCommand),
+            "C-command then L-command"
+        );
     }
 
     #[test]
@@ -290,6 +1049,66 @@ mod tests {
         assert_eq!(parser.symbol().unwrap(), "LOOP");
     }
 
+    #[test]
+    fn test_uses_symbols() {
+        let numeric: Vec<String> = ["@2", "D=A", "@3", "D=D+A // add", "@0", "M=D"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert!(!uses_symbols(&numeric));
+
+        let labelled: Vec<String> = ["(LOOP)", "@0", "0;JMP"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert!(uses_symbols(&labelled));
+
+        assert!(uses_symbols(&["@R0".to_string()]));
+        assert!(!uses_symbols(&[]));
+    }
+
+    #[test]
+    fn test_read_source_lines_unicode_separators() {
+        let source = "@2\u{2028}D=A\u{2029}@3\r\nD=D+A\n";
+        let lines = read_source_lines(source.as_bytes()).unwrap();
+        assert_eq!(lines, ["@2", "D=A", "@3", "D=D+A"]);
+
+        let mut parser = ParserLines::from_lines(&lines);
+        let mut count = 0;
+        while parser.advance() {
+            count += 1;
+        }
+        assert_eq!(count, 4);
+
+        // Other characters starting with the same byte are kept
+        let lines = read_source_lines("a\u{2014}b\u{2028}c".as_bytes()).unwrap();
+        assert_eq!(lines, ["a\u{2014}b", "c"]);
+    }
+
+    #[test]
+    fn test_read_source_lines_line_endings() {
+        let expected = ["@2", "", "D=A"];
+        for source in [
+            "@2\n\nD=A\n",
+            "@2\r\n\r\nD=A\r\n",
+            "@2\r\rD=A\r",
+            "@2\r\n\rD=A",
+        ] {
+            assert_eq!(
+                read_source_lines(source.as_bytes()).unwrap(),
+                expected,
+                "{source:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_source_lines_keeps_blank_lines() {
+        let lines = read_source_lines("a\n\nb".as_bytes()).unwrap();
+        assert_eq!(lines, ["a", "", "b"]);
+        assert!(read_source_lines("".as_bytes()).unwrap().is_empty());
+    }
+
     #[test]
     fn test_whitespace_handling() {
         let lines = vec!["   @100   ".to_string(), "  D=M  // comment  ".to_string()];
@@ -301,4 +1120,397 @@ mod tests {
         assert!(parser.advance());
         assert_eq!(parser.dest().unwrap(), Some("D"));
     }
+
+    #[test]
+    fn test_whitespace_inside_c_commands() {
+        let lines: Vec<String> = [
+            "D = D + 1",
+            "  AM = M - 1 ; JGT  // keep  these  spaces",
+            "0 ; JMP",
+            "\tD\t=\tA",
+            "@ i",
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+        let mut parser = ParserLines::from_lines(&lines);
+
+        for (dest, comp, jump) in [("D", "D+1", ""), ("AM", "M-1", "JGT"), ("", "0", "JMP")] {
+            assert!(parser.advance());
+            assert_eq!(parser.dest().unwrap(), Some(dest));
+            assert_eq!(parser.comp().unwrap(), Some(comp));
+            assert_eq!(parser.jump().unwrap(), Some(jump));
+        }
+        assert!(parser.advance());
+        assert_eq!(parser.comp().unwrap(), Some("A"));
+        // Space after `@` is not part of the symbol
+        assert!(parser.advance());
+        assert_eq!(parser.symbol().unwrap(), "i");
+
+        // Iteration yields the same fields as the accessors
+        let commands: Vec<_> = ParserLines::from_lines(&lines)
+            .map(|command| command.unwrap().kind)
+            .collect();
+        let c = |dest: &str, comp: &str, jump: &str| CommandKind::C {
+            dest: dest.to_string().into(),
+            comp: comp.to_string().into(),
+            jump: jump.to_string().into(),
+        };
+        assert_eq!(
+            commands,
+            [
+                c("D", "D+1", ""),
+                c("AM", "M-1", "JGT"),
+                c("", "0", "JMP"),
+                c("D", "A", ""),
+                CommandKind::A("i"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_symbol_is_predefined() {
+        let lines = vec![
+            "@SP".to_string(),
+            "@ SCREEN".to_string(),
+            "@myvar".to_string(),
+            "M=D".to_string(),
+            "(R1)".to_string(),
+        ];
+        let mut parser = ParserLines::from_lines(&lines);
+        assert!(!parser.symbol_is_predefined());
+
+        parser.advance();
+        assert!(parser.symbol_is_predefined());
+        // The symbol is read as `symbol` reads it
+        parser.advance();
+        assert!(parser.symbol_is_predefined());
+        parser.advance();
+        assert!(!parser.symbol_is_predefined());
+        parser.advance();
+        assert!(!parser.symbol_is_predefined());
+        // Only A-commands count, even if a label reuses a predefined name
+        parser.advance();
+        assert!(!parser.symbol_is_predefined());
+    }
+
+    fn token(kind: TokenKind, start: usize, end: usize) -> Token {
+        Token { kind, start, end }
+    }
+
+    #[test]
+    fn test_tokenize_c_command_with_comment() {
+        assert_eq!(
+            tokenize_line("MD=D+1;JMP // go"),
+            [
+                token(TokenKind::Dest, 0, 2),
+                token(TokenKind::Equals, 2, 3),
+                token(TokenKind::Comp, 3, 6),
+                token(TokenKind::Semicolon, 6, 7),
+                token(TokenKind::Jump, 7, 10),
+                token(TokenKind::Whitespace, 10, 11),
+                token(TokenKind::Comment, 11, 16),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_a_and_label_commands() {
+        assert_eq!(
+            tokenize_line("  @21"),
+            [
+                token(TokenKind::Whitespace, 0, 2),
+                token(TokenKind::AtSign, 2, 3),
+                token(TokenKind::Number, 3, 5),
+            ]
+        );
+        assert_eq!(
+            tokenize_line("(LOOP)"),
+            [
+                token(TokenKind::LabelParen, 0, 1),
+                token(TokenKind::Symbol, 1, 5),
+                token(TokenKind::LabelParen, 5, 6),
+            ]
+        );
+        assert_eq!(
+            tokenize_line("0;JMP"),
+            [
+                token(TokenKind::Comp, 0, 1),
+                token(TokenKind::Semicolon, 1, 2),
+                token(TokenKind::Jump, 2, 5),
+            ]
+        );
+        assert_eq!(tokenize_line("// only"), [token(TokenKind::Comment, 0, 7)]);
+        assert!(tokenize_line("").is_empty());
+    }
+
+    /// Asserts that every accessor reports an error instead of panicking
+    fn assert_no_command(parser: &ParserLines) {
+        assert!(parser.command_type().is_err());
+        assert!(parser.symbol().is_err());
+        assert!(parser.dest().is_err());
+        assert!(parser.comp().is_err());
+        assert!(parser.jump().is_err());
+        assert!(!parser.symbol_is_predefined());
+    }
+
+    #[test]
+    fn test_empty_input() {
+        let mut parser = ParserLines::from_lines(&[]);
+        assert_no_command(&parser);
+        assert!(!parser.advance());
+        assert_no_command(&parser);
+        assert!(!parser.advance());
+    }
+
+    #[test]
+    fn test_only_blank_and_comment_lines() {
+        let lines: Vec<String> = ["", "   ", "\t", "// comment", "  // indented", "/"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let mut parser = ParserLines::from_lines(&lines[..5]);
+        assert!(!parser.advance());
+        assert_no_command(&parser);
+
+        // A lone '/' is not a comment, so it is (an invalid) C-command
+        let mut parser = ParserLines::from_lines(&lines);
+        assert!(parser.advance());
+        assert_eq!(parser.comp().unwrap(), Some("/"));
+    }
+
+    #[test]
+    fn test_unterminated_label_is_an_error() {
+        let lines = vec!["(".to_string(), "// note".to_string(), "(LOOP".to_string()];
+        let mut parser = ParserLines::from_lines(&lines);
+        parser.advance();
+        assert!(parser.symbol().is_err());
+        parser.advance();
+        let error = parser.symbol().unwrap_err();
+        assert!(matches!(
+            &error,
+            ParserError::Syntax { line: 3, text, .. } if text == "(LOOP"
+        ));
+        assert_eq!(
+            error.to_string(),
+            "line 3: label is missing its closing ')': `(LOOP`"
+        );
+    }
+
+    #[test]
+    fn test_text_after_label_is_an_error() {
+        let lines = vec![
+            "(LOOP) D=M".to_string(),
+            "(LOOP))".to_string(),
+            "(LOOP) // comment".to_string(),
+        ];
+        let mut parser = ParserLines::from_lines(&lines);
+
+        parser.advance();
+        let error = parser.symbol().unwrap_err().to_string();
+        assert!(error.contains("separate lines"), "{error}");
+        assert!(error.contains("`(LOOP) D=M`"), "{error}");
+        parser.advance();
+        assert!(parser.symbol().is_err());
+        parser.advance();
+        assert_eq!(parser.symbol().unwrap(), "LOOP");
+    }
+
+    #[test]
+    fn test_empty_comp_after_assignment_is_an_error() {
+        let lines = vec![
+            "D=;JGT".to_string(),
+            "D=".to_string(),
+            "D=M;JGT".to_string(),
+            "0;JMP=D".to_string(),
+        ];
+        let mut parser = ParserLines::from_lines(&lines);
+
+        parser.advance();
+        assert_eq!(
+            parser.comp().unwrap_err().to_string(),
+            "line 1: missing computation after '=': `D=;JGT`"
+        );
+        parser.advance();
+        assert_eq!(
+            parser.comp().unwrap_err().to_string(),
+            "line 2: missing computation after '=': `D=`"
+        );
+        parser.advance();
+        assert_eq!(parser.dest().unwrap(), Some("D"));
+        assert_eq!(parser.comp().unwrap(), Some("M"));
+        assert_eq!(parser.jump().unwrap(), Some("JGT"));
+        parser.advance();
+        assert!(parser.comp().is_err());
+    }
+
+    #[test]
+    fn test_line_numbers_count_skipped_lines() {
+        let source = "// header\n\n@1\n  // indented comment\n\nD=A\n";
+        let lines: Vec<String> = source.lines().map(ToString::to_string).collect();
+        let mut parser = ParserLines::from_lines(&lines);
+        let mut streamed = ParserLines::from_reader(std::io::Cursor::new(source));
+
+        for expected in [3, 6] {
+            assert!(parser.advance());
+            assert!(streamed.advance());
+            assert_eq!(parser.current_line_number(), expected);
+            assert_eq!(streamed.current_line_number(), expected);
+        }
+        assert!(!parser.advance());
+        parser.reset().unwrap();
+        assert_eq!(parser.current_line_number(), 0);
+    }
+
+    #[test]
+    fn test_from_reader_splits_like_read_source_lines() {
+        let source = "@2\u{2028}D=A\u{2029}@3\r\nD=D+A\r0;JMP\r\n\r(END)\n";
+        let lines = read_source_lines(source.as_bytes()).unwrap();
+        let mut expected = ParserLines::from_lines(&lines);
+        let mut parser = ParserLines::from_reader(std::io::Cursor::new(source));
+
+        assert_eq!(parser.peek(), Some(CommandType::ACommand));
+        while expected.advance() {
+            assert!(parser.advance());
+            assert_eq!(parser.current_line_number(), expected.current_line_number());
+            assert_eq!(parser.current_line(), expected.current_line());
+        }
+        assert_eq!(expected.current_line_number(), 7);
+        assert!(!parser.advance());
+        assert!(parser.take_error().is_ok());
+
+        // A separator split across buffer refills still ends the line
+        let reader = BufReader::with_capacity(1, std::io::Cursor::new(source));
+        let mut parser = ParserLines::from_reader(reader);
+        assert!(parser.advance());
+        assert_eq!(parser.symbol().unwrap(), "2");
+        assert!(parser.advance());
+        assert_eq!(parser.current_line_number(), 2);
+        assert_eq!(parser.comp().unwrap(), Some("A"));
+    }
+
+    #[test]
+    fn test_from_reader_matches_from_lines() {
+        let source = "// header\n\n  @counter // comment\r\n(LOOP)\nAM=M-1;JGT\n0;JMP";
+        let lines: Vec<String> = source.lines().map(ToString::to_string).collect();
+        let mut expected = ParserLines::from_lines(&lines);
+        let mut parser = ParserLines::from_reader(std::io::Cursor::new(source));
+
+        while expected.advance() {
+            assert!(parser.advance());
+            assert_eq!(
+                parser.command_type().unwrap(),
+                expected.command_type().unwrap()
+            );
+            if expected.command_type().unwrap() == CommandType::CCommand {
+                assert_eq!(parser.dest().unwrap(), expected.dest().unwrap());
+                assert_eq!(parser.comp().unwrap(), expected.comp().unwrap());
+                assert_eq!(parser.jump().unwrap(), expected.jump().unwrap());
+            } else {
+                assert_eq!(parser.symbol().unwrap(), expected.symbol().unwrap());
+            }
+        }
+        assert!(!parser.advance());
+        assert!(parser.take_error().is_ok());
+    }
+
+    #[test]
+    fn test_peek_leaves_position_unchanged() {
+        let source = "@END // jump\n\n// forever\n0;JMP\n(END)\n@END\n";
+        let lines: Vec<String> = source.lines().map(ToString::to_string).collect();
+        let reader = ParserLines::from_reader(std::io::Cursor::new(source));
+        for mut parser in [ParserLines::from_lines(&lines), reader] {
+            assert_eq!(parser.peek(), Some(CommandType::ACommand));
+            assert!(parser.advance());
+            assert_eq!(parser.peek(), Some(CommandType::CCommand));
+            assert_eq!(parser.peek(), Some(CommandType::CCommand));
+            assert_eq!(parser.symbol().unwrap(), "END");
+
+            assert!(parser.advance());
+            assert_eq!(parser.current_line_number(), 4);
+            assert_eq!(parser.comp().unwrap(), Some("0"));
+            assert_eq!(parser.jump().unwrap(), Some("JMP"));
+            assert_eq!(parser.peek(), Some(CommandType::LCommand));
+            assert!(parser.advance());
+            assert_eq!(parser.symbol().unwrap(), "END");
+            assert!(parser.advance());
+            assert_eq!(parser.current_line_number(), 6);
+            assert_eq!(parser.peek(), None);
+            assert!(!parser.advance());
+            parser.reset().unwrap();
+            assert_eq!(parser.peek(), Some(CommandType::ACommand));
+        }
+    }
+
+    #[test]
+    fn test_reset_allows_second_pass() {
+        let lines = vec!["@1".to_string(), "D=A".to_string()];
+        let mut parser = ParserLines::from_lines(&lines);
+        while parser.advance() {}
+        parser.reset().unwrap();
+        assert!(parser.advance());
+        assert_eq!(parser.symbol().unwrap(), "1");
+
+        let mut parser = ParserLines::from_reader(std::io::Cursor::new("(END)\n"));
+        assert!(parser.advance());
+        assert!(!parser.advance());
+        parser.reset().unwrap();
+        assert!(parser.advance());
+        assert_eq!(parser.symbol().unwrap(), "END");
+    }
+
+    #[test]
+    fn test_iterator_yields_borrowed_commands() {
+        let lines: Vec<String> = [
+            "// start",
+            "@i",
+            "(LOOP)",
+            "  MD=M+1;JMP // again",
+            "D=;JGT",
+            "0",
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+        let commands: Vec<_> = ParserLines::from_lines(&lines).collect();
+
+        assert_eq!(commands.len(), 5);
+        assert_eq!(
+            commands[0].as_ref().unwrap(),
+            &Command {
+                line: 2,
+                kind: CommandKind::A("i")
+            }
+        );
+        assert_eq!(commands[1].as_ref().unwrap().kind, CommandKind::L("LOOP"));
+        assert_eq!(
+            commands[2].as_ref().unwrap().kind,
+            CommandKind::C {
+                dest: "MD".into(),
+                comp: "M+1".into(),
+                jump: "JMP".into()
+            }
+        );
+        assert!(matches!(
+            commands[3],
+            Err(ParserError::Syntax { line: 5, .. })
+        ));
+        let last = commands[4].as_ref().unwrap();
+        assert_eq!(last.command_type(), CommandType::CCommand);
+        assert_eq!(
+            last.kind,
+            CommandKind::C {
+                dest: "".into(),
+                comp: "0".into(),
+                jump: "".into()
+            }
+        );
+
+        let mut parser = ParserLines::from_reader(std::io::Cursor::new("@1\n@2\n"));
+        assert!(matches!(
+            parser.next(),
+            Some(Err(ParserError::InvalidState(_)))
+        ));
+        assert!(parser.next().is_none());
+    }
 }
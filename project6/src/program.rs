@@ -0,0 +1,287 @@
+//! Owned, in-memory representation of a parsed Hack assembly program
+//!
+//! [`ParserLines`] is a zero-copy streaming parser, which is ideal for the two
+//! assembly passes but awkward for whole-program analysis. [`Program`] collects
+//! every instruction once so that lints and rewrites can look backwards and
+//! forwards freely.
+//!
+//! Labels are stored by *instruction index* rather than by resolved address, so
+//! removing an instruction only requires shifting the indices that follow it.
+
+use crate::code::parse_a_constant;
+use crate::parser::{CommandType, ParserError, ParserLines};
+use std::fmt;
+
+/// A single machine instruction (labels are not instructions)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    /// `@symbol` or `@value`
+    A(String),
+    /// `dest=comp;jump`
+    C {
+        dest: String,
+        comp: String,
+        jump: String,
+    },
+}
+
+impl Instruction {
+    /// Returns the symbol of an A-instruction, or `None` for C-instructions
+    #[inline]
+    #[must_use]
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            Self::A(symbol) => Some(symbol),
+            Self::C { .. } => None,
+        }
+    }
+
+    /// Returns `true` for a C-instruction that always jumps (`;JMP`)
+    #[inline]
+    #[must_use]
+    pub fn is_unconditional_jump(&self) -> bool {
+        matches!(self, Self::C { jump, .. } if jump == "JMP")
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::A(symbol) => write!(f, "@{symbol}"),
+            Self::C { dest, comp, jump } => {
+                if !dest.is_empty() {
+                    write!(f, "{dest}=")?;
+                }
+                write!(f, "{comp}")?;
+                if !jump.is_empty() {
+                    write!(f, ";{jump}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A parsed program: instructions in ROM order plus label definitions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Program {
+    /// Instructions in ROM order (index == ROM address)
+    pub instructions: Vec<Instruction>,
+    /// Label definitions in source order, as `(name, instruction index)`
+    pub labels: Vec<(String, usize)>,
+}
+
+impl Program {
+    /// Parses assembly source lines into a [`Program`]
+    ///
+    /// # Example
+    /// ```
+    /// use project6::program::{Instruction, Program};
+    ///
+    /// let lines = vec!["(LOOP)".to_string(), "@LOOP".to_string(), "0;JMP".to_string()];
+    /// let program = Program::from_lines(&lines).unwrap();
+    /// assert_eq!(program.instructions.len(), 2);
+    /// assert_eq!(program.label_address("LOOP"), Some(0));
+    /// assert_eq!(program.instructions[0], Instruction::A("LOOP".to_string()));
+    /// ```
+    pub fn from_lines(lines: &[String]) -> Result<Self, ParserError> {
+        let mut program = Self::default();
+        let mut parser = ParserLines::from_lines(lines);
+
+        while parser.advance() {
+            match parser.command_type()? {
+                CommandType::ACommand => {
+                    program
+                        .instructions
+                        .push(Instruction::A(parser.symbol()?.to_string()));
+                }
+                CommandType::CCommand => {
+                    program.instructions.push(Instruction::C {
+                        dest: parser.dest()?.unwrap_or("").to_string(),
+                        comp: parser.comp()?.unwrap_or("").to_string(),
+                        jump: parser.jump()?.unwrap_or("").to_string(),
+                    });
+                }
+                CommandType::LCommand => {
+                    program
+                        .labels
+                        .push((parser.symbol()?.to_string(), program.instructions.len()));
+                }
+            }
+        }
+
+        Ok(program)
+    }
+
+    /// Returns the instruction index a label points at, if it is defined
+    #[must_use]
+    pub fn label_address(&self, name: &str) -> Option<usize> {
+        self.labels
+            .iter()
+            .find(|(label, _)| label == name)
+            .map(|&(_, index)| index)
+    }
+
+    /// Returns every label with its ROM address, in definition order
+    ///
+    /// Suited to generating jump tables, where the order of entries matters.
+    /// Returns `None` if a label lies beyond the 16-bit address space.
+    ///
+    /// # Example
+    /// ```
+    /// use project6::program::Program;
+    ///
+    /// let lines: Vec<String> = ["(B)", "@0", "(A)", "D=A"].iter().map(ToString::to_string).collect();
+    /// let program = Program::from_lines(&lines).unwrap();
+    /// assert_eq!(
+    ///     program.labels_in_order().unwrap(),
+    ///     [("B".to_string(), 0), ("A".to_string(), 1)]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn labels_in_order(&self) -> Option<Vec<(String, u16)>> {
+        self.labels
+            .iter()
+            .map(|(name, index)| Some((name.clone(), u16::try_from(*index).ok()?)))
+            .collect()
+    }
+
+    /// Removes the instruction at `index`, shifting later labels down by one
+    ///
+    /// Labels pointing at `index` itself keep their index and so move onto the
+    /// instruction that followed the removed one.
+    pub fn remove_instruction(&mut self, index: usize) -> Instruction {
+        for (_, label_index) in &mut self.labels {
+            if *label_index > index {
+                *label_index -= 1;
+            }
+        }
+        self.instructions.remove(index)
+    }
+
+    /// Returns `true` if any label is defined at instruction `index`
+    #[must_use]
+    pub fn has_label_at(&self, index: usize) -> bool {
+        self.labels
+            .iter()
+            .any(|&(_, label_index)| label_index == index)
+    }
+
+    /// Renders the program back to assembly source, one command per line
+    ///
+    /// Comments and formatting are not preserved, but assembling the result
+    /// produces the same machine code as the original program.
+    #[must_use]
+    pub fn to_source_lines(&self) -> Vec<String> {
+        let mut lines = Vec::with_capacity(self.instructions.len() + self.labels.len());
+        let mut labels = self.labels.iter().peekable();
+
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            while let Some((name, _)) = labels.next_if(|&&(_, at)| at <= index) {
+                lines.push(format!("({name})"));
+            }
+            lines.push(instruction.to_string());
+        }
+        lines.extend(labels.map(|(name, _)| format!("({name})")));
+
+        lines
+    }
+
+    /// Resolves an A-instruction symbol to a ROM address when statically known
+    ///
+    /// Numeric constants resolve to themselves and labels to their instruction
+    /// index. Variables and predefined RAM symbols return `None`.
+    #[must_use]
+    pub fn jump_target(&self, symbol: &str) -> Option<usize> {
+        match parse_a_constant(symbol) {
+            Some(constant) => constant.ok().map(usize::from),
+            None => self.label_address(symbol),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(source: &[&str]) -> Vec<String> {
+        source.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_from_lines_records_labels_by_index() {
+        let source = lines(&["@0", "D=M", "(LOOP)", "(AGAIN)", "@LOOP", "0;JMP"]);
+        let program = Program::from_lines(&source).unwrap();
+
+        assert_eq!(program.instructions.len(), 4);
+        assert_eq!(
+            program.labels,
+            vec![("LOOP".to_string(), 2), ("AGAIN".to_string(), 2)]
+        );
+        assert_eq!(
+            program.instructions[1],
+            Instruction::C {
+                dest: "D".to_string(),
+                comp: "M".to_string(),
+                jump: String::new(),
+            }
+        );
+        assert!(program.instructions[3].is_unconditional_jump());
+    }
+
+    #[test]
+    fn test_remove_instruction_shifts_labels() {
+        let source = lines(&["@1", "(A)", "@2", "(B)", "D=A"]);
+        let mut program = Program::from_lines(&source).unwrap();
+
+        program.remove_instruction(0);
+        assert_eq!(program.label_address("A"), Some(0));
+        assert_eq!(program.label_address("B"), Some(1));
+        assert!(program.has_label_at(1));
+    }
+
+    #[test]
+    fn test_to_source_lines_round_trip() {
+        let source = lines(&["(START)", "@START", "MD=D+1;JMP", "0;JMP", "D=M", "(END)"]);
+        let program = Program::from_lines(&source).unwrap();
+
+        assert_eq!(program.to_source_lines(), source);
+        assert_eq!(
+            Program::from_lines(&program.to_source_lines()).unwrap(),
+            program
+        );
+    }
+
+    #[test]
+    fn test_jump_target() {
+        let source = lines(&["(START)", "@START", "0;JMP"]);
+        let program = Program::from_lines(&source).unwrap();
+
+        assert_eq!(program.jump_target("START"), Some(0));
+        assert_eq!(program.jump_target("7"), Some(7));
+        assert_eq!(program.jump_target("0ah"), Some(10));
+        assert_eq!(program.jump_target("counter"), None);
+    }
+
+    #[test]
+    fn test_labels_in_order() {
+        // Defined out of alphabetical and address-sorted order on purpose
+        let source = lines(&[
+            "(ZETA)", "@ALPHA", "0;JMP", "(MID)", "(ALPHA)", "D=0", "@ZETA", "0;JMP",
+        ]);
+        let mut program = Program::from_lines(&source).unwrap();
+
+        assert_eq!(
+            program.labels_in_order().unwrap(),
+            [
+                ("ZETA".to_string(), 0),
+                ("MID".to_string(), 2),
+                ("ALPHA".to_string(), 2)
+            ]
+        );
+
+        // Labels are public, so nothing stops one pointing past 16 bits
+        program.labels.push(("FAR".to_string(), 1 << 16));
+        assert_eq!(program.labels_in_order(), None);
+    }
+}
@@ -0,0 +1,302 @@
+//! A minimal Hack CPU simulator
+//!
+//! Executes assembled machine words so tests can check what a program
+//! actually computes rather than only how it is encoded. There is no screen
+//! or keyboard emulation: `SCREEN` and `KBD` are plain RAM.
+//!
+//! Hack has no halt instruction; programs stop by spinning in a self-loop such
+//! as `(END) @END 0;JMP`. [`Simulator::run_until_halt`] recognises that idiom
+//! and returns early instead of burning through its cycle budget.
+
+use crate::consts::{MAX_A_VALUE, RAM_SIZE};
+
+/// Why [`Simulator::run_until_halt`] stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    /// The program entered a self-loop that can never change state
+    Halted,
+    /// `max_cycles` instructions executed without halting
+    CycleLimit,
+    /// The program counter left the loaded ROM
+    PcOutOfRange,
+}
+
+/// Outcome of [`Simulator::run_until_halt`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunResult {
+    pub status: RunStatus,
+    /// Instructions executed during this run
+    pub cycles: u64,
+}
+
+/// The Hack CPU, its instruction ROM and data RAM
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    rom: Vec<u16>,
+    ram: Vec<u16>,
+    a: u16,
+    d: u16,
+    pc: u16,
+    /// Stop at `(END) @END 0;JMP`-style self-loops rather than spinning
+    halt_on_self_loop: bool,
+}
+
+impl Simulator {
+    /// Creates a simulator with `rom` loaded, zeroed RAM and registers
+    ///
+    /// # Example
+    /// ```
+    /// use project6::simulator::{RunStatus, Simulator};
+    ///
+    /// // @7 D=A @0 M=D (END) @4 0;JMP
+    /// let mut sim = Simulator::new(vec![7, 0xEC10, 0, 0xE308, 4, 0xEA87]);
+    /// let result = sim.run_until_halt(1000);
+    /// assert_eq!(result.status, RunStatus::Halted);
+    /// assert_eq!(sim.ram(0), 7);
+    /// ```
+    #[must_use]
+    pub fn new(rom: Vec<u16>) -> Self {
+        Self {
+            rom,
+            ram: vec![0; RAM_SIZE],
+            a: 0,
+            d: 0,
+            pc: 0,
+            halt_on_self_loop: true,
+        }
+    }
+
+    /// Chooses whether self-loops count as a halt (the default)
+    ///
+    /// With detection off, [`run_until_halt`](Self::run_until_halt) only stops
+    /// at `max_cycles` or when the PC leaves ROM, like real hardware.
+    pub fn set_halt_on_self_loop(&mut self, enabled: bool) {
+        self.halt_on_self_loop = enabled;
+    }
+
+    /// Returns the word at RAM `address`
+    #[must_use]
+    pub fn ram(&self, address: u16) -> u16 {
+        self.ram[usize::from(address)]
+    }
+
+    /// Stores `value` at RAM `address`, e.g. to pass inputs to a program
+    pub fn set_ram(&mut self, address: u16, value: u16) {
+        self.ram[usize::from(address)] = value;
+    }
+
+    /// Returns the A register
+    #[must_use]
+    pub fn a(&self) -> u16 {
+        self.a
+    }
+
+    /// Returns the D register
+    #[must_use]
+    pub fn d(&self) -> u16 {
+        self.d
+    }
+
+    /// Returns the program counter
+    #[must_use]
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    /// Runs until the program halts, leaves ROM or executes `max_cycles` instructions
+    ///
+    /// A halt is a taken jump that can never change state: a jump to itself,
+    /// or back to an `@n` at address `n` immediately before it, with no dest.
+    pub fn run_until_halt(&mut self, max_cycles: u64) -> RunResult {
+        let mut cycles = 0;
+
+        while cycles < max_cycles {
+            if usize::from(self.pc) >= self.rom.len() {
+                return RunResult {
+                    status: RunStatus::PcOutOfRange,
+                    cycles,
+                };
+            }
+            if self.halt_on_self_loop && self.at_self_loop() {
+                return RunResult {
+                    status: RunStatus::Halted,
+                    cycles,
+                };
+            }
+            self.step();
+            cycles += 1;
+        }
+
+        RunResult {
+            status: RunStatus::CycleLimit,
+            cycles,
+        }
+    }
+
+    /// Executes the instruction at the program counter
+    ///
+    /// # Panics
+    /// Panics if the program counter is outside the loaded ROM.
+    pub fn step(&mut self) {
+        let instruction = self.rom[usize::from(self.pc)];
+
+        if instruction & 0x8000 == 0 {
+            self.a = instruction;
+            self.pc += 1;
+            return;
+        }
+
+        let out = self.alu_output(instruction);
+        let address = self.a;
+
+        // Registers latch together at the clock edge: M and the jump target
+        // use A as it was before this instruction
+        if instruction & 0b001_000 != 0 {
+            self.ram[usize::from(address & MAX_A_VALUE)] = out;
+        }
+        if instruction & 0b100_000 != 0 {
+            self.a = out;
+        }
+        if instruction & 0b010_000 != 0 {
+            self.d = out;
+        }
+
+        self.pc = if jump_taken(instruction, out) {
+            address
+        } else {
+            self.pc + 1
+        };
+    }
+
+    /// Computes a C-instruction's ALU output from its a-bit and six control bits
+    fn alu_output(&self, instruction: u16) -> u16 {
+        let bit = |n: u16| instruction & (1 << n) != 0;
+        let (zx, nx, zy, ny, f, no) = (bit(11), bit(10), bit(9), bit(8), bit(7), bit(6));
+
+        let mut x = self.d;
+        let mut y = if bit(12) {
+            self.ram[usize::from(self.a & MAX_A_VALUE)]
+        } else {
+            self.a
+        };
+
+        if zx {
+            x = 0;
+        }
+        if nx {
+            x = !x;
+        }
+        if zy {
+            y = 0;
+        }
+        if ny {
+            y = !y;
+        }
+        let out = if f { x.wrapping_add(y) } else { x & y };
+        if no { !out } else { out }
+    }
+
+    /// Returns `true` if executing from here would spin forever unchanged
+    fn at_self_loop(&self) -> bool {
+        let pc = usize::from(self.pc);
+        let instruction = self.rom[pc];
+
+        let is_pure_jump = instruction & 0x8000 != 0 && instruction & 0b111_000 == 0;
+        if !is_pure_jump || !jump_taken(instruction, self.alu_output(instruction)) {
+            return false;
+        }
+
+        // `0;JMP` onto itself, or `@n 0;JMP` where `@n` sits at address n
+        self.a == self.pc || (usize::from(self.a) + 1 == pc && self.rom[pc - 1] == self.a)
+    }
+}
+
+/// Evaluates a C-instruction's jump bits against the ALU output
+fn jump_taken(instruction: u16, out: u16) -> bool {
+    // Two's complement view of the output
+    #[allow(clippy::cast_possible_wrap)]
+    let value = out as i16;
+    (instruction & 0b100 != 0 && value < 0)
+        || (instruction & 0b010 != 0 && value == 0)
+        || (instruction & 0b001 != 0 && value > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assemble_to_u16;
+
+    fn assemble(source: &[&str]) -> Vec<u16> {
+        let lines: Vec<String> = source.iter().map(ToString::to_string).collect();
+        assemble_to_u16(&lines).unwrap()
+    }
+
+    /// RAM[2] = RAM[0] * RAM[1] by repeated addition, then the usual halt
+    const MULTIPLY: &[&str] = &[
+        "@2", "M=0", "(LOOP)", "@1", "D=M", "@END", "D;JLE", "@0", "D=M", "@2", "M=D+M", "@1",
+        "M=M-1", "@LOOP", "0;JMP", "(END)", "@END", "0;JMP",
+    ];
+
+    #[test]
+    fn test_self_loop_halts_early() {
+        let mut sim = Simulator::new(assemble(MULTIPLY));
+        sim.set_ram(0, 6);
+        sim.set_ram(1, 7);
+
+        let result = sim.run_until_halt(1_000_000);
+        assert_eq!(result.status, RunStatus::Halted);
+        assert!(result.cycles < 200, "took {} cycles", result.cycles);
+        assert_eq!(sim.ram(2), 42);
+    }
+
+    #[test]
+    fn test_detection_can_be_disabled() {
+        let mut sim = Simulator::new(assemble(MULTIPLY));
+        sim.set_halt_on_self_loop(false);
+
+        let result = sim.run_until_halt(500);
+        assert_eq!(result.status, RunStatus::CycleLimit);
+        assert_eq!(result.cycles, 500);
+    }
+
+    #[test]
+    fn test_single_instruction_self_loop() {
+        // @2 D=A (HERE) 0;JMP  -- A already holds HERE's address
+        let mut sim = Simulator::new(assemble(&["@2", "D=A", "0;JMP"]));
+        assert_eq!(sim.run_until_halt(100).status, RunStatus::Halted);
+        assert_eq!(sim.pc(), 2);
+        assert_eq!(sim.d(), 2);
+    }
+
+    #[test]
+    fn test_busy_loop_with_side_effects_is_not_a_halt() {
+        // M=M+1;JMP changes RAM every time round
+        let mut sim = Simulator::new(assemble(&["(SPIN)", "@SPIN", "M=M+1;JMP"]));
+        assert_eq!(sim.run_until_halt(100).status, RunStatus::CycleLimit);
+        assert_eq!(sim.ram(0), 50);
+    }
+
+    #[test]
+    fn test_running_off_the_end() {
+        let mut sim = Simulator::new(assemble(&["@5", "D=A"]));
+        let result = sim.run_until_halt(100);
+        assert_eq!(result.status, RunStatus::PcOutOfRange);
+        assert_eq!(result.cycles, 2);
+        assert_eq!(sim.a(), 5);
+    }
+
+    #[test]
+    fn test_runs_assembled_words() {
+        // RAM[3] = RAM[0] + RAM[1] - 1, through every register
+        let mut sim = Simulator::new(assemble(&[
+            "@0", "D=M", "@1", "AD=D+M", "MD=A-1", "@3", "M=D", "(END)", "@END", "0;JMP",
+        ]));
+        sim.set_ram(0, 20);
+        sim.set_ram(1, 22);
+
+        assert_eq!(sim.run_until_halt(100).status, RunStatus::Halted);
+        assert_eq!(sim.ram(3), 41);
+        assert_eq!(sim.ram(42), 41);
+        assert_eq!(sim.d(), 41);
+    }
+}
@@ -0,0 +1,114 @@
+//! Summary counts describing one assembly run
+//!
+//! [`AssemblyStats`] is filled in by [`assemble_full`](crate::assemble_full).
+//! The assembler prints it for `--stats` and, with the `serde` feature, writes
+//! it as JSON for `--stats-json` so build dashboards can track program size.
+
+use std::fmt;
+
+/// Counts gathered while assembling a program
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AssemblyStats {
+    /// A-instructions in the output
+    pub a_instructions: usize,
+    /// C-instructions in the output
+    pub c_instructions: usize,
+    /// Distinct labels defined
+    pub labels: usize,
+    /// Variables allocated in RAM
+    pub variables: usize,
+    /// Words of machine code (`a_instructions + c_instructions`)
+    pub total_instructions: usize,
+    /// Highest RAM address given to a variable, or `None` without variables
+    pub ram_high_water: Option<u16>,
+    /// Warnings reported (e.g. by `--lint`)
+    pub warnings: usize,
+}
+
+impl AssemblyStats {
+    /// Serializes the statistics as a pretty-printed JSON object
+    ///
+    /// # Example
+    /// ```
+    /// use project6::{AssemblerConfig, assemble_full};
+    ///
+    /// let lines: Vec<String> = ["@x", "M=1"].iter().map(ToString::to_string).collect();
+    /// let stats = assemble_full(&lines, &AssemblerConfig::default()).stats;
+    /// assert!(stats.to_json().contains("\"ram_high_water\": 16"));
+    /// ```
+    #[cfg(feature = "serde")]
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("statistics contain only plain numbers")
+    }
+}
+
+impl fmt::Display for AssemblyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Instructions:   {}", self.total_instructions)?;
+        writeln!(f, "  A-commands:   {}", self.a_instructions)?;
+        writeln!(f, "  C-commands:   {}", self.c_instructions)?;
+        writeln!(f, "Labels:         {}", self.labels)?;
+        writeln!(f, "Variables:      {}", self.variables)?;
+        match self.ram_high_water {
+            Some(address) => writeln!(f, "RAM high-water: {address}")?,
+            None => writeln!(f, "RAM high-water: -")?,
+        }
+        write!(f, "Warnings:       {}", self.warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AssemblerConfig, assemble_full};
+
+    fn lines(source: &[&str]) -> Vec<String> {
+        source.iter().map(ToString::to_string).collect()
+    }
+
+    /// Counts a loop summing into two variables, ending in the usual halt
+    const KNOWN_PROGRAM: &[&str] = &[
+        "@i", "M=1", "@sum", "M=0", "(LOOP)", "@i", "D=M", "@sum", "M=D+M", "@LOOP", "0;JMP",
+    ];
+
+    #[test]
+    fn test_known_program_stats() {
+        let config = AssemblerConfig {
+            lint: true,
+            ..AssemblerConfig::default()
+        };
+        let stats = assemble_full(&lines(KNOWN_PROGRAM), &config).stats;
+
+        assert_eq!(stats.a_instructions, 5);
+        assert_eq!(stats.c_instructions, 5);
+        assert_eq!(stats.total_instructions, 10);
+        assert_eq!(stats.labels, 1);
+        assert_eq!(stats.variables, 2);
+        assert_eq!(stats.ram_high_water, Some(17));
+        assert_eq!(stats.warnings, 0);
+        assert!(stats.to_string().contains("Variables:      2"));
+    }
+
+    #[test]
+    fn test_no_variables() {
+        let stats = assemble_full(&lines(&["@2", "D=A"]), &AssemblerConfig::default()).stats;
+        assert_eq!(stats.variables, 0);
+        assert_eq!(stats.ram_high_water, None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_stats_json_fields() {
+        let stats = assemble_full(&lines(KNOWN_PROGRAM), &AssemblerConfig::default()).stats;
+        let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+
+        assert_eq!(json["a_instructions"], 5);
+        assert_eq!(json["c_instructions"], 5);
+        assert_eq!(json["labels"], 1);
+        assert_eq!(json["variables"], 2);
+        assert_eq!(json["total_instructions"], 10);
+        assert_eq!(json["ram_high_water"], 17);
+        assert_eq!(json["warnings"], 0);
+    }
+}
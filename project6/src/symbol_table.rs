@@ -7,6 +7,7 @@
 //! This gives us the best of both worlds: blazing fast lookups for common symbols
 //! and flexibility for user-defined labels and variables.
 
+use crate::consts::{KBD_ADDR, SCREEN_BASE, VAR_BASE};
 use phf::phf_map;
 use std::collections::HashMap;
 use std::fmt;
@@ -42,10 +43,263 @@ static PREDEFINED_SYMBOLS: phf::Map<&'static str, u16> = phf_map! {
     "THAT" => 4,
 
     // I/O pointers
-    "SCREEN" => 16384,
-    "KBD" => 24576,
+    "SCREEN" => SCREEN_BASE,
+    "KBD" => KBD_ADDR,
 };
 
+/// Reasons a label cannot be added to a [`SymbolTable`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolError {
+    /// The label was already defined at `address`
+    DuplicateLabel { symbol: String, address: u16 },
+    /// The label or variable would shadow a predefined symbol such as `SP`
+    /// or `R1`, which always resolves to its built-in address
+    PredefinedSymbol(String),
+}
+
+impl std::error::Error for SymbolError {}
+
+impl fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DuplicateLabel { symbol, address } => {
+                write!(f, "label '{symbol}' is already defined (at ROM {address})")
+            }
+            Self::PredefinedSymbol(symbol) => {
+                write!(
+                    f,
+                    "'{symbol}' is a predefined symbol; defining it again is ignored"
+                )
+            }
+        }
+    }
+}
+
+/// Looks up a predefined symbol without needing a [`SymbolTable`] instance
+///
+/// # Example
+/// ```
+/// use project6::symbol_table::predefined_address;
+///
+/// assert_eq!(predefined_address("SCREEN"), Some(16384));
+/// assert_eq!(predefined_address("LOOP"), None);
+/// ```
+#[inline]
+#[must_use]
+pub fn predefined_address(symbol: &str) -> Option<u16> {
+    PREDEFINED_SYMBOLS.get(symbol).copied()
+}
+
+/// Returns `true` if `symbol` is built into the Hack platform
+///
+/// # Example
+/// ```
+/// use project6::symbol_table::is_predefined;
+///
+/// assert!(is_predefined("R15"));
+/// assert!(!is_predefined("R16"));
+/// ```
+#[inline]
+#[must_use]
+pub fn is_predefined(symbol: &str) -> bool {
+    PREDEFINED_SYMBOLS.contains_key(symbol)
+}
+
+/// Number of `R` registers in the standard Hack platform (`R0`-`R15`)
+const STANDARD_REGISTER_COUNT: u16 = VAR_BASE;
+
+/// Parses an extended register name `R16`, `R17`, ... below `register_count`
+///
+/// The standard sixteen are left to the PHF map.
+#[inline]
+fn extended_register(symbol: &str, register_count: u16) -> Option<u16> {
+    let digits = symbol.strip_prefix('R')?;
+    // Reject "R016" and "R+1", which `parse` would otherwise accept
+    if digits.starts_with(['0', '+']) {
+        return None;
+    }
+    digits
+        .parse::<u16>()
+        .ok()
+        .filter(|&n| (STANDARD_REGISTER_COUNT..register_count).contains(&n))
+}
+
+/// Dense id of an interned user symbol name
+///
+/// Handed out by [`SymbolTable::intern`] on a table created with
+/// [`SymbolTable::with_interning`], and valid for the life of that table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SymbolId(usize);
+
+/// Storage for user-defined symbols
+///
+/// `Plain` keys a `HashMap` by owned `String`s. `Interned` stores each name
+/// exactly once, as the key mapping it to a dense [`SymbolId`], and keeps the
+/// addresses in a vector indexed by id. Names stay interned when their
+/// symbol is removed, so ids are never reused or invalidated.
+#[derive(Debug, Clone)]
+enum UserSymbols {
+    Plain(HashMap<String, u16>),
+    Interned {
+        ids: HashMap<Box<str>, SymbolId>,
+        addresses: Vec<Option<u16>>,
+        /// Number of ids with an address
+        len: usize,
+    },
+}
+
+impl UserSymbols {
+    #[inline]
+    fn get(&self, symbol: &str) -> Option<u16> {
+        match self {
+            Self::Plain(map) => map.get(symbol).copied(),
+            Self::Interned { ids, addresses, .. } => {
+                ids.get(symbol).and_then(|&id| addresses[id.0])
+            }
+        }
+    }
+
+    /// Returns the id of `symbol`, interning it on first sight
+    ///
+    /// `None` for plain storage, which has no ids.
+    fn intern(&mut self, symbol: &str) -> Option<SymbolId> {
+        let Self::Interned { ids, addresses, .. } = self else {
+            return None;
+        };
+        // Borrowed lookup first: the name is only copied on first sight
+        if let Some(&id) = ids.get(symbol) {
+            return Some(id);
+        }
+        let id = SymbolId(addresses.len());
+        ids.insert(symbol.into(), id);
+        addresses.push(None);
+        Some(id)
+    }
+
+    #[inline]
+    fn insert(&mut self, symbol: &str, address: u16) {
+        match self {
+            Self::Plain(map) => {
+                map.insert(symbol.to_string(), address);
+            }
+            Self::Interned { .. } => {
+                if let Some(id) = self.intern(symbol) {
+                    self.set(id, Some(address));
+                }
+            }
+        }
+    }
+
+    /// Sets or clears the address of an interned id, returning the old one
+    fn set(&mut self, id: SymbolId, address: Option<u16>) -> Option<u16> {
+        let Self::Interned { addresses, len, .. } = self else {
+            return None;
+        };
+        let old = std::mem::replace(&mut addresses[id.0], address);
+        *len = *len + usize::from(address.is_some()) - usize::from(old.is_some());
+        old
+    }
+
+    /// Returns the existing address, or inserts the one produced by `allocate`
+    ///
+    /// Looks the name up borrowed first, so it is only copied on first sight.
+    #[inline]
+    fn get_or_insert_with(&mut self, symbol: &str, allocate: impl FnOnce() -> u16) -> u16 {
+        if let Some(address) = self.get(symbol) {
+            return address;
+        }
+        let address = allocate();
+        self.insert(symbol, address);
+        address
+    }
+
+    fn remove(&mut self, symbol: &str) -> Option<u16> {
+        match self {
+            Self::Plain(map) => map.remove(symbol),
+            Self::Interned { ids, .. } => {
+                let id = *ids.get(symbol)?;
+                self.set(id, None)
+            }
+        }
+    }
+
+    /// Forgets every address, keeping allocations and interned names
+    fn clear(&mut self) {
+        match self {
+            Self::Plain(map) => map.clear(),
+            Self::Interned { addresses, len, .. } => {
+                addresses.fill(None);
+                *len = 0;
+            }
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        match self {
+            Self::Plain(map) => map.len(),
+            Self::Interned { len, .. } => *len,
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (&str, u16)> + '_> {
+        match self {
+            Self::Plain(map) => {
+                Box::new(map.iter().map(|(name, &address)| (name.as_str(), address)))
+            }
+            Self::Interned { ids, addresses, .. } => Box::new(
+                ids.iter()
+                    .filter_map(|(name, &id)| Some((&**name, addresses[id.0]?))),
+            ),
+        }
+    }
+}
+
+/// Sort key for [`SymbolTable::sorted_symbols`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymbolOrder {
+    /// Alphabetically by symbol name
+    #[default]
+    Name,
+    /// By ascending address, then by name
+    Address,
+}
+
+impl std::str::FromStr for SymbolOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(Self::Name),
+            "address" => Ok(Self::Address),
+            _ => Err(format!("unknown sort key '{s}' (expected name or address)")),
+        }
+    }
+}
+
+/// User-symbol changes between two symbol tables, as reported by
+/// [`SymbolTable::diff`]
+///
+/// Each list is sorted by symbol name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTableDiff {
+    /// Symbols only in the newer table, with their address
+    pub added: Vec<(String, u16)>,
+    /// Symbols only in the older table, with their address
+    pub removed: Vec<(String, u16)>,
+    /// Symbols in both tables, as `(name, old address, new address)`
+    pub changed: Vec<(String, u16, u16)>,
+}
+
+impl SymbolTableDiff {
+    /// Returns `true` if the two tables resolve every user symbol identically
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
 /// Symbol table for the Hack assembler
 ///
 /// Maintains mappings between symbolic labels and numeric addresses.
@@ -70,10 +324,16 @@ static PREDEFINED_SYMBOLS: phf::Map<&'static str, u16> = phf_map! {
 /// st.add_entry("LOOP", 100);
 /// assert_eq!(st.get_address("LOOP"), 100);
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SymbolTable {
     /// User-defined symbols (labels and variables)
-    user_symbols: HashMap<String, u16>,
+    user_symbols: UserSymbols,
+    /// Number of `R` registers, at least the standard 16
+    register_count: u16,
+    /// RAM address of the first variable, `register_count` unless moved
+    variable_base: u16,
+    /// RAM address [`SymbolTable::allocate_variable`] hands out next
+    next_variable: u16,
 }
 
 impl Default for SymbolTable {
@@ -83,6 +343,23 @@ impl Default for SymbolTable {
     }
 }
 
+/// Two tables are equal when they hold the same user-defined symbols
+///
+/// Predefined symbols live in the shared PHF map, so beyond the register
+/// count they are always equal.
+impl PartialEq for SymbolTable {
+    fn eq(&self, other: &Self) -> bool {
+        self.register_count == other.register_count
+            && self.variable_base == other.variable_base
+            && self.user_symbols.len() == other.user_symbols.len()
+            && self
+                .iter()
+                .all(|(name, address)| other.user_symbols.get(name) == Some(address))
+    }
+}
+
+impl Eq for SymbolTable {}
+
 impl fmt::Display for SymbolTable {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "SymbolTable")
@@ -97,7 +374,128 @@ impl SymbolTable {
     #[must_use]
     pub fn new() -> Self {
         Self {
-            user_symbols: HashMap::with_capacity(32),
+            user_symbols: UserSymbols::Plain(HashMap::with_capacity(32)),
+            register_count: STANDARD_REGISTER_COUNT,
+            variable_base: STANDARD_REGISTER_COUNT,
+            next_variable: STANDARD_REGISTER_COUNT,
+        }
+    }
+
+    /// Creates a symbol table for a Hack variant with `count` registers
+    ///
+    /// `R0`-`R{count-1}` resolve to RAM 0 through `count - 1`, and variables
+    /// are allocated from [`first_variable_address`] upwards so they never
+    /// overlap the extra registers. Counts of 16 or less behave exactly like
+    /// [`SymbolTable::new`].
+    ///
+    /// [`first_variable_address`]: SymbolTable::first_variable_address
+    ///
+    /// # Example
+    /// ```
+    /// use project6::SymbolTable;
+    ///
+    /// let st = SymbolTable::with_register_count(32);
+    /// assert_eq!(st.get_address("R20"), 20);
+    /// assert_eq!(st.first_variable_address(), 32);
+    /// ```
+    #[must_use]
+    pub fn with_register_count(count: u16) -> Self {
+        let register_count = count.max(STANDARD_REGISTER_COUNT);
+        Self {
+            register_count,
+            variable_base: register_count,
+            next_variable: register_count,
+            ..Self::new()
+        }
+    }
+
+    /// Creates a symbol table that allocates variables from `base` upwards
+    ///
+    /// For experiments with a modified memory map; the standard platform
+    /// starts at 16. See [`SymbolTable::set_first_variable_address`].
+    ///
+    /// # Example
+    /// ```
+    /// use project6::SymbolTable;
+    ///
+    /// let mut st = SymbolTable::with_variable_base(64);
+    /// assert_eq!(st.allocate_variable("i"), 64);
+    /// assert_eq!(st.next_variable_address(), 65);
+    /// ```
+    #[must_use]
+    pub fn with_variable_base(base: u16) -> Self {
+        let mut table = Self::new();
+        table.set_first_variable_address(base);
+        table
+    }
+
+    /// Creates a symbol table that interns user symbol names
+    ///
+    /// Each name is stored once and given a [`SymbolId`]; see
+    /// [`SymbolTable::intern`]. A caller that keeps the id of a long,
+    /// often-referenced name (e.g. a compiler-mangled one) resolves it with
+    /// [`SymbolTable::address_of`] without hashing the name again. Lookups
+    /// by name behave exactly as with [`SymbolTable::new`].
+    ///
+    /// # Example
+    /// ```
+    /// use project6::SymbolTable;
+    ///
+    /// let mut st = SymbolTable::with_interning();
+    /// let mut ram_addr = 16;
+    /// assert_eq!(st.get_or_insert("Main.veryLongMangledName$loop", &mut ram_addr), 16);
+    /// assert_eq!(st.get_or_insert("Main.veryLongMangledName$loop", &mut ram_addr), 16);
+    /// assert_eq!(st, {
+    ///     let mut plain = SymbolTable::new();
+    ///     plain.add_entry("Main.veryLongMangledName$loop", 16);
+    ///     plain
+    /// });
+    /// ```
+    #[must_use]
+    pub fn with_interning() -> Self {
+        Self {
+            user_symbols: UserSymbols::Interned {
+                ids: HashMap::with_capacity(32),
+                addresses: Vec::with_capacity(32),
+                len: 0,
+            },
+            ..Self::new()
+        }
+    }
+
+    /// Returns the id of a user symbol name, interning it on first sight
+    ///
+    /// The name need not be defined yet; its id stays the same through
+    /// [`SymbolTable::remove`] and [`SymbolTable::clear_user_symbols`].
+    /// Returns `None` unless the table was created with
+    /// [`SymbolTable::with_interning`].
+    ///
+    /// # Example
+    /// ```
+    /// use project6::SymbolTable;
+    ///
+    /// let mut st = SymbolTable::with_interning();
+    /// let id = st.intern("Main.loop").unwrap();
+    /// assert_eq!(st.address_of(id), None);
+    /// st.add_entry("Main.loop", 7);
+    /// assert_eq!(st.address_of(id), Some(7));
+    /// assert_eq!(st.intern("Main.loop"), Some(id));
+    ///
+    /// assert_eq!(SymbolTable::new().intern("Main.loop"), None);
+    /// ```
+    pub fn intern(&mut self, symbol: &str) -> Option<SymbolId> {
+        self.user_symbols.intern(symbol)
+    }
+
+    /// Returns the address of the user symbol with id `id`, if it is defined
+    ///
+    /// Indexes straight into the table, without hashing the name. Ids name
+    /// user symbols only: a predefined symbol is never found this way.
+    #[must_use]
+    pub fn address_of(&self, id: SymbolId) -> Option<u16> {
+        match &self.user_symbols {
+            UserSymbols::Interned { addresses, .. } => addresses.get(id.0).copied().flatten(),
+            UserSymbols::Plain(_) => None,
         }
     }
 
@@ -115,9 +513,133 @@ impl SymbolTable {
     /// st.add_entry("LOOP", 100);
     /// assert_eq!(st.get_address("LOOP"), 100);
     /// ```
+    ///
+    /// Lookups check the predefined symbols first, so an entry named `KBD`
+    /// or `R5` is stored but never found. [`SymbolTable::add_entry_checked`]
+    /// reports that instead.
     #[inline]
     pub fn add_entry(&mut self, symbol: &str, address: u16) {
-        self.user_symbols.insert(symbol.to_string(), address);
+        self.user_symbols.insert(symbol, address);
+    }
+
+    /// Adds a user-defined symbol unless it names a predefined one
+    ///
+    /// Unlike [`SymbolTable::try_add_label`], an existing user symbol is
+    /// overwritten, as with [`SymbolTable::add_entry`].
+    ///
+    /// # Errors
+    /// Returns [`SymbolError::PredefinedSymbol`] if `symbol` is built in
+    /// (including extended registers); the table is left unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// use project6::SymbolTable;
+    /// use project6::symbol_table::SymbolError;
+    ///
+    /// let mut st = SymbolTable::new();
+    /// assert_eq!(st.add_entry_checked("LOOP", 4), Ok(()));
+    /// assert_eq!(
+    ///     st.add_entry_checked("KBD", 9),
+    ///     Err(SymbolError::PredefinedSymbol("KBD".to_string()))
+    /// );
+    /// assert_eq!(st.get_address("KBD"), 24576);
+    /// ```
+    pub fn add_entry_checked(&mut self, symbol: &str, address: u16) -> Result<(), SymbolError> {
+        if self.predefined(symbol).is_some() {
+            return Err(SymbolError::PredefinedSymbol(symbol.to_string()));
+        }
+        self.user_symbols.insert(symbol, address);
+        Ok(())
+    }
+
+    /// Removes a user-defined symbol, returning its address
+    ///
+    /// Predefined symbols cannot be removed and keep resolving as before. An
+    /// entry [`SymbolTable::add_entry`] stored under a predefined name is
+    /// removed like any other user symbol, so [`SymbolTable::iter`] stops
+    /// reporting it.
+    ///
+    /// # Example
+    /// ```
+    /// use project6::SymbolTable;
+    ///
+    /// let mut st = SymbolTable::new();
+    /// st.add_entry("LOOP", 4);
+    /// assert_eq!(st.remove("LOOP"), Some(4));
+    /// assert_eq!(st.remove("LOOP"), None);
+    /// assert_eq!(st.remove("SP"), None);
+    /// assert_eq!(st.get_address("SP"), 0);
+    /// ```
+    pub fn remove(&mut self, symbol: &str) -> Option<u16> {
+        self.user_symbols.remove(symbol)
+    }
+
+    /// Removes every user-defined symbol, e.g. before the next assembly unit
+    ///
+    /// The storage keeps its capacity, and an interning table its names, so
+    /// reusing the table does not allocate again. Variable allocation restarts at
+    /// [`SymbolTable::first_variable_address`]; predefined symbols and the
+    /// register count are unaffected.
+    ///
+    /// # Example
+    /// ```
+    /// use project6::SymbolTable;
+    ///
+    /// let mut st = SymbolTable::new();
+    /// st.add_entry("LOOP", 4);
+    /// st.allocate_variable("i");
+    /// st.clear_user_symbols();
+    /// assert_eq!(st.user_symbol_count(), 0);
+    /// assert_eq!(st.allocate_variable("j"), 16);
+    /// ```
+    pub fn clear_user_symbols(&mut self) {
+        self.user_symbols.clear();
+        self.next_variable = self.variable_base;
+    }
+
+    /// Defines a label, refusing to redefine an existing one
+    ///
+    /// Variables are only allocated in pass 2, after every label is known, so
+    /// in pass 1 any user symbol already present is an earlier label.
+    ///
+    /// # Errors
+    /// Returns [`SymbolError::DuplicateLabel`] if `symbol` is already defined
+    /// and [`SymbolError::PredefinedSymbol`] if it names a built-in symbol.
+    ///
+    /// # Example
+    /// ```
+    /// use project6::SymbolTable;
+    /// use project6::symbol_table::SymbolError;
+    ///
+    /// let mut st = SymbolTable::new();
+    /// assert_eq!(st.try_add_label("LOOP", 4), Ok(()));
+    /// assert_eq!(
+    ///     st.try_add_label("LOOP", 9),
+    ///     Err(SymbolError::DuplicateLabel { symbol: "LOOP".to_string(), address: 4 })
+    /// );
+    /// assert!(st.try_add_label("SCREEN", 9).is_err());
+    /// ```
+    pub fn try_add_label(&mut self, symbol: &str, address: u16) -> Result<(), SymbolError> {
+        if self.predefined(symbol).is_some() {
+            return Err(SymbolError::PredefinedSymbol(symbol.to_string()));
+        }
+        if let Some(existing) = self.user_symbols.get(symbol) {
+            return Err(SymbolError::DuplicateLabel {
+                symbol: symbol.to_string(),
+                address: existing,
+            });
+        }
+        self.user_symbols.insert(symbol, address);
+        Ok(())
+    }
+
+    /// Looks up a predefined symbol, including any extended registers
+    #[inline]
+    fn predefined(&self, symbol: &str) -> Option<u16> {
+        PREDEFINED_SYMBOLS
+            .get(symbol)
+            .copied()
+            .or_else(|| extended_register(symbol, self.register_count))
     }
 
     /// Checks if a symbol exists (either predefined or user-defined)
@@ -129,7 +651,7 @@ impl SymbolTable {
     #[must_use]
     #[allow(dead_code)] // Used in tests and public API
     pub fn contains(&self, symbol: &str) -> bool {
-        PREDEFINED_SYMBOLS.contains_key(symbol) || self.user_symbols.contains_key(symbol)
+        self.predefined(symbol).is_some() || self.user_symbols.get(symbol).is_some()
     }
 
     /// Gets the address associated with a symbol
@@ -154,12 +676,12 @@ impl SymbolTable {
     #[allow(dead_code)] // Used in tests and public API
     pub fn get_address(&self, symbol: &str) -> u16 {
         // Fast path: Check predefined symbols first (most common)
-        if let Some(&addr) = PREDEFINED_SYMBOLS.get(symbol) {
+        if let Some(addr) = self.predefined(symbol) {
             return addr;
         }
 
         // Slow path: Check user-defined symbols
-        self.user_symbols.get(symbol).copied().unwrap_or(0)
+        self.user_symbols.get(symbol).unwrap_or(0)
     }
 
     /// Gets or inserts a symbol, returning its address
@@ -191,22 +713,56 @@ impl SymbolTable {
     /// ```
     #[inline]
     pub fn get_or_insert(&mut self, symbol: &str, next_address: &mut u16) -> u16 {
-        use std::collections::hash_map::Entry;
-
         // Fast path: Check predefined symbols (most common in well-written code)
-        if let Some(&addr) = PREDEFINED_SYMBOLS.get(symbol) {
+        if let Some(addr) = self.predefined(symbol) {
             return addr;
         }
 
-        // User symbol: use Entry API to avoid double lookup
-        match self.user_symbols.entry(symbol.to_string()) {
-            Entry::Occupied(e) => *e.get(),
-            Entry::Vacant(e) => {
-                let addr = *next_address;
-                *next_address += 1;
-                *e.insert(addr)
-            }
+        self.user_symbols.get_or_insert_with(symbol, || {
+            let addr = *next_address;
+            // Saturates instead of overflowing; the assembler rejects the
+            // out-of-range address rather than panicking here
+            *next_address = next_address.saturating_add(1);
+            addr
+        })
+    }
+
+    /// Returns the address of `symbol`, allocating the next free variable if new
+    ///
+    /// Like [`SymbolTable::get_or_insert`], but the table keeps the allocation
+    /// counter itself, starting at [`SymbolTable::first_variable_address`].
+    ///
+    /// # Example
+    /// ```
+    /// use project6::SymbolTable;
+    ///
+    /// let mut st = SymbolTable::new();
+    /// assert_eq!(st.allocate_variable("i"), 16);
+    /// assert_eq!(st.allocate_variable("sum"), 17);
+    /// assert_eq!(st.allocate_variable("i"), 16);
+    /// assert_eq!(st.allocate_variable("SCREEN"), 16384);
+    /// assert_eq!(st.next_variable_address(), 18);
+    /// ```
+    #[inline]
+    pub fn allocate_variable(&mut self, symbol: &str) -> u16 {
+        if let Some(addr) = self.predefined(symbol) {
+            return addr;
         }
+
+        let next_address = &mut self.next_variable;
+        self.user_symbols.get_or_insert_with(symbol, || {
+            let addr = *next_address;
+            // Saturates like `get_or_insert`; the assembler reports the overflow
+            *next_address = next_address.saturating_add(1);
+            addr
+        })
+    }
+
+    /// Returns the RAM address the next new variable will be given
+    #[inline]
+    #[must_use]
+    pub fn next_variable_address(&self) -> u16 {
+        self.next_variable
     }
 
     /// Returns the number of user-defined symbols
@@ -219,6 +775,138 @@ impl SymbolTable {
         self.user_symbols.len()
     }
 
+    /// Iterates over the user-defined symbols (labels and variables) and
+    /// their addresses
+    ///
+    /// The order is unspecified and may change between runs; use
+    /// [`SymbolTable::sorted_symbols`] for a stable listing.
+    ///
+    /// # Example
+    /// ```
+    /// use project6::SymbolTable;
+    ///
+    /// let mut st = SymbolTable::new();
+    /// st.add_entry("LOOP", 4);
+    /// st.allocate_variable("i");
+    /// let mut symbols: Vec<_> = st.iter().collect();
+    /// symbols.sort_unstable();
+    /// assert_eq!(symbols, [("LOOP", 4), ("i", 16)]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u16)> + '_ {
+        self.user_symbols.iter()
+    }
+
+    /// Like [`SymbolTable::iter`], preceded by the predefined symbols
+    ///
+    /// Extended registers (`R16`, ...) of [`SymbolTable::with_register_count`]
+    /// are resolved on demand and not listed. The order is unspecified.
+    pub fn iter_all(&self) -> impl Iterator<Item = (&str, u16)> + '_ {
+        PREDEFINED_SYMBOLS
+            .entries()
+            .map(|(&name, &address)| (name, address))
+            .chain(self.iter())
+    }
+
+    /// Returns every symbol with its address: the predefined ones, then
+    /// labels and variables, sorted by `order`
+    ///
+    /// Symbols sharing an address (`SP`, `R0`, ...) are ordered by name, so
+    /// the listing is deterministic.
+    ///
+    /// # Example
+    /// ```
+    /// use project6::symbol_table::{SymbolOrder, SymbolTable};
+    ///
+    /// let mut st = SymbolTable::new();
+    /// st.add_entry("LOOP", 4);
+    /// let symbols = st.sorted_symbols(SymbolOrder::Address);
+    /// assert_eq!(symbols[..3], [("R0", 0), ("SP", 0), ("LCL", 1)]);
+    /// assert!(symbols.contains(&("LOOP", 4)));
+    /// ```
+    #[must_use]
+    pub fn sorted_symbols(&self, order: SymbolOrder) -> Vec<(&str, u16)> {
+        let mut symbols: Vec<(&str, u16)> = self.iter_all().collect();
+        match order {
+            SymbolOrder::Name => symbols.sort_unstable(),
+            SymbolOrder::Address => {
+                symbols.sort_unstable_by_key(|&(name, address)| (address, name));
+            }
+        }
+        symbols
+    }
+
+    /// Compares the user symbols of `self` (older) with `other` (newer)
+    ///
+    /// Predefined symbols never differ and are not reported.
+    ///
+    /// # Example
+    /// ```
+    /// use project6::SymbolTable;
+    ///
+    /// let mut before = SymbolTable::new();
+    /// before.add_entry("LOOP", 4);
+    /// let mut after = before.clone();
+    /// after.add_entry("LOOP", 6);
+    ///
+    /// let diff = before.diff(&after);
+    /// assert_eq!(diff.changed, [("LOOP".to_string(), 4, 6)]);
+    /// ```
+    #[must_use]
+    pub fn diff(&self, other: &SymbolTable) -> SymbolTableDiff {
+        let mut diff = SymbolTableDiff::default();
+
+        for (name, old) in self.iter() {
+            match other.user_symbols.get(name) {
+                None => diff.removed.push((name.to_string(), old)),
+                Some(new) if new != old => diff.changed.push((name.to_string(), old, new)),
+                Some(_) => {}
+            }
+        }
+        for (name, new) in other.iter() {
+            if self.user_symbols.get(name).is_none() {
+                diff.added.push((name.to_string(), new));
+            }
+        }
+
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        diff
+    }
+
+    /// Returns the RAM address of the first variable
+    ///
+    /// This is 16 on the standard platform, or just past the last register when
+    /// the table was built with [`SymbolTable::with_register_count`], unless
+    /// moved by [`SymbolTable::set_first_variable_address`].
+    #[inline]
+    #[must_use]
+    pub fn first_variable_address(&self) -> u16 {
+        self.variable_base
+    }
+
+    /// Allocates variables from `address` upwards instead
+    ///
+    /// Useful to keep a block of RAM after the registers free for hand-placed
+    /// data. Nothing stops the variables from overlapping `SCREEN` or `KBD`
+    /// if `address` is set too high. Call it before allocating any variables:
+    /// it also restarts [`SymbolTable::allocate_variable`]'s counter.
+    ///
+    /// # Example
+    /// ```
+    /// use project6::SymbolTable;
+    ///
+    /// let mut st = SymbolTable::new();
+    /// st.set_first_variable_address(64);
+    /// let mut next = st.first_variable_address();
+    /// assert_eq!(st.get_or_insert("i", &mut next), 64);
+    /// ```
+    #[inline]
+    pub fn set_first_variable_address(&mut self, address: u16) {
+        self.variable_base = address;
+        self.next_variable = address;
+    }
+
     /// Returns the total number of predefined symbols (23)
     #[inline]
     #[must_use]
@@ -253,6 +941,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_allocate_variable_keeps_its_own_counter() {
+        let mut st = SymbolTable::with_variable_base(100);
+        st.add_entry("LOOP", 3);
+
+        assert_eq!(st.allocate_variable("LOOP"), 3);
+        assert_eq!(st.allocate_variable("x"), 100);
+        assert_eq!(st.allocate_variable("R2"), 2);
+        assert_eq!(st.allocate_variable("y"), 101);
+        assert_eq!(st.allocate_variable("x"), 100);
+        assert_eq!(st.first_variable_address(), 100);
+        assert_eq!(st.next_variable_address(), 102);
+
+        let mut wide = SymbolTable::with_register_count(32);
+        assert_eq!(wide.allocate_variable("x"), 32);
+    }
+
+    #[test]
+    fn test_sorted_symbols() {
+        let mut st = SymbolTable::new();
+        st.add_entry("LOOP", 3);
+        st.add_entry("END", 16384);
+        st.add_entry("counter", 16);
+
+        let by_name = st.sorted_symbols(SymbolOrder::Name);
+        assert_eq!(by_name.len(), 23 + 3);
+        assert!(by_name.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        let by_address = st.sorted_symbols(SymbolOrder::Address);
+        let names: Vec<&str> = by_address.iter().map(|&(name, _)| name).collect();
+        assert_eq!(names[..4], ["R0", "SP", "LCL", "R1"]);
+        // Ties are broken by name, whether user-defined or predefined
+        assert_eq!(names[6..9], ["LOOP", "R3", "THIS"]);
+        assert_eq!(names[names.len() - 3..], ["END", "SCREEN", "KBD"]);
+        assert!(by_address.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+        assert_eq!("address".parse(), Ok(SymbolOrder::Address));
+        assert!("value".parse::<SymbolOrder>().is_err());
+    }
+
+    #[test]
+    fn test_iter() {
+        for mut st in [SymbolTable::new(), SymbolTable::with_interning()] {
+            st.add_entry("LOOP", 3);
+            st.allocate_variable("i");
+            st.add_entry("LOOP", 5);
+
+            let mut user: Vec<_> = st.iter().collect();
+            user.sort_unstable();
+            assert_eq!(user, [("LOOP", 5), ("i", 16)]);
+
+            let all: Vec<_> = st.iter_all().collect();
+            assert_eq!(all.len(), SymbolTable::predefined_symbol_count() + 2);
+            assert!(all.contains(&("KBD", 24576)));
+            assert!(all.contains(&("LOOP", 5)));
+        }
+    }
+
     #[test]
     fn test_add_and_get_user_symbols() {
         let mut st = SymbolTable::new();
@@ -334,6 +1080,69 @@ mod tests {
         assert!(!st.contains("NONEXISTENT"));
     }
 
+    #[test]
+    fn test_equality_and_clone() {
+        let build = || {
+            let mut st = SymbolTable::new();
+            let mut next = 16;
+            st.add_entry("LOOP", 4);
+            st.get_or_insert("i", &mut next);
+            st
+        };
+
+        let first = build();
+        let second = build();
+        assert_eq!(first, second);
+        assert_eq!(first.clone(), first);
+
+        let mut third = build();
+        third.add_entry("END", 10);
+        assert_ne!(first, third);
+    }
+
+    #[test]
+    fn test_register_count() {
+        let mut wide = SymbolTable::with_register_count(32);
+        let mut next = wide.first_variable_address();
+        assert_eq!(wide.get_or_insert("R20", &mut next), 20);
+        assert_eq!(wide.get_or_insert("R31", &mut next), 31);
+        assert_eq!(wide.get_or_insert("R7", &mut next), 7);
+        assert_eq!(wide.user_symbol_count(), 0);
+
+        // Out of range or non-canonical names are ordinary variables
+        assert_eq!(wide.get_or_insert("R32", &mut next), 32);
+        assert_eq!(wide.get_or_insert("R020", &mut next), 33);
+
+        let mut standard = SymbolTable::new();
+        let mut next = standard.first_variable_address();
+        assert_eq!(standard.get_or_insert("R20", &mut next), 16);
+        assert_eq!(SymbolTable::with_register_count(8), SymbolTable::new());
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut before = SymbolTable::new();
+        before.add_entry("LOOP", 4);
+        before.add_entry("END", 10);
+        before.add_entry("i", 16);
+
+        let mut after = SymbolTable::new();
+        after.add_entry("LOOP", 4);
+        after.add_entry("END", 12); // Changed
+        after.add_entry("i", 16);
+        after.add_entry("sum", 17); // Added
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.added, [("sum".to_string(), 17)]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed, [("END".to_string(), 10, 12)]);
+
+        let reverse = after.diff(&before);
+        assert_eq!(reverse.removed, [("sum".to_string(), 17)]);
+        assert_eq!(reverse.changed, [("END".to_string(), 12, 10)]);
+        assert!(before.diff(&before.clone()).is_empty());
+    }
+
     #[test]
     fn test_phf_performance() {
         // This test verifies that PHF map is working correctly
@@ -342,4 +1151,98 @@ mod tests {
         assert_eq!(PREDEFINED_SYMBOLS.get("SCREEN"), Some(&16384));
         assert_eq!(PREDEFINED_SYMBOLS.get("INVALID"), None);
     }
+
+    #[test]
+    fn test_try_add_label() {
+        let mut st = SymbolTable::with_register_count(20);
+        assert!(st.try_add_label("END", 7).is_ok());
+        assert_eq!(
+            st.try_add_label("END", 9),
+            Err(SymbolError::DuplicateLabel {
+                symbol: "END".to_string(),
+                address: 7
+            })
+        );
+        assert_eq!(st.get_address("END"), 7);
+        assert_eq!(
+            st.try_add_label("R18", 0),
+            Err(SymbolError::PredefinedSymbol("R18".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_add_entry_checked() {
+        let mut st = SymbolTable::with_register_count(20);
+        assert_eq!(st.add_entry_checked("END", 7), Ok(()));
+        assert_eq!(st.add_entry_checked("END", 9), Ok(()));
+        assert_eq!(st.get_address("END"), 9);
+
+        for symbol in ["SP", "R5", "R19", "KBD"] {
+            let error = st.add_entry_checked(symbol, 100).unwrap_err();
+            assert_eq!(error, SymbolError::PredefinedSymbol(symbol.to_string()));
+            assert!(error.to_string().contains("ignored"));
+        }
+        assert_eq!(st.user_symbol_count(), 1);
+        assert!(st.add_entry_checked("R20", 100).is_ok());
+    }
+
+    #[test]
+    fn test_remove_and_clear_user_symbols() {
+        for mut st in [SymbolTable::new(), SymbolTable::with_interning()] {
+            for (i, symbol) in ["a", "b", "c", "d"].into_iter().enumerate() {
+                st.add_entry(symbol, u16::try_from(i).unwrap());
+            }
+            st.add_entry("KBD", 5);
+            assert_eq!(st.remove("b"), Some(1));
+            assert_eq!(st.remove("b"), None);
+            // The shadowing user entry goes; the predefined symbol stays
+            assert_eq!(st.remove("KBD"), Some(5));
+            assert_eq!(st.remove("KBD"), None);
+            assert_eq!(st.get_address("KBD"), 24576);
+            assert!(st.contains("KBD"));
+            assert_eq!(st.remove("a"), Some(0));
+            assert_eq!(st.get_address("c"), 2);
+            assert_eq!(st.get_address("d"), 3);
+            st.add_entry("e", 9);
+            let mut symbols: Vec<_> = st.iter().collect();
+            symbols.sort_unstable();
+            assert_eq!(symbols, [("c", 2), ("d", 3), ("e", 9)]);
+
+            st.allocate_variable("i");
+            st.clear_user_symbols();
+            assert_eq!(st.user_symbol_count(), 0);
+            assert!(!st.contains("c"));
+            assert!(st.contains("SCREEN"));
+            assert_eq!(st.allocate_variable("i"), 16);
+        }
+    }
+
+    #[test]
+    fn test_interned_ids_are_stable() {
+        let mut st = SymbolTable::with_interning();
+        let loop_id = st.intern("Main.loop").unwrap();
+        let i_id = st.intern("i").unwrap();
+        assert_ne!(loop_id, i_id);
+        assert_eq!(st.user_symbol_count(), 0);
+
+        st.add_entry("Main.loop", 4);
+        assert_eq!(st.allocate_variable("i"), 16);
+        assert_eq!(st.address_of(loop_id), Some(4));
+        assert_eq!(st.address_of(i_id), Some(16));
+
+        // Removing and clearing keep the names interned under the same ids
+        assert_eq!(st.remove("Main.loop"), Some(4));
+        assert_eq!(st.address_of(loop_id), None);
+        assert_eq!(st.user_symbol_count(), 1);
+        st.clear_user_symbols();
+        assert_eq!(st.intern("i"), Some(i_id));
+        st.add_entry("Main.loop", 9);
+        assert_eq!(st.intern("Main.loop"), Some(loop_id));
+        assert_eq!(st.address_of(loop_id), Some(9));
+        assert_eq!(st.iter().collect::<Vec<_>>(), [("Main.loop", 9)]);
+
+        // Predefined symbols are not user symbols
+        assert_eq!(SymbolTable::new().intern("i"), None);
+        assert_eq!(SymbolTable::new().address_of(loop_id), None);
+    }
 }
@@ -0,0 +1,39 @@
+//! `wasm-bindgen` entry points for running the assembler in a browser
+//!
+//! Only compiled with the `wasm` feature. The bindings stay thin wrappers
+//! around the in-memory API so the same code paths are tested natively.
+
+use wasm_bindgen::prelude::*;
+
+/// Assembles `source` and returns the `.hack` text
+///
+/// Errors are thrown to JavaScript as a string, one message per line.
+#[wasm_bindgen]
+pub fn assemble_wasm(source: &str) -> Result<String, JsValue> {
+    assemble(source).map_err(|message| JsValue::from_str(&message))
+}
+
+/// The binding's behaviour, without any JavaScript types
+fn assemble(source: &str) -> Result<String, String> {
+    crate::assembler::assemble_source(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_program_assembles() {
+        let hack = assemble("// Adds 2 and 3\n@2\nD=A\n@3\nD=D+A\n(END)\n@END\n0;JMP\n").unwrap();
+        assert_eq!(
+            hack,
+            "0000000000000010\n1110110000010000\n0000000000000011\n\
+             1110000010010000\n0000000000000100\n1110101010000111\n"
+        );
+    }
+
+    #[test]
+    fn test_errors_are_messages() {
+        assert!(assemble("@2\nD=Q\n").unwrap_err().contains('Q'));
+    }
+}
@@ -1,3 +1,4 @@
+use std::env;
 use std::fs;
 use std::path::Path;
 use std::process::Command;
@@ -44,10 +45,6 @@ fn test_asm_files_in_directory(dir: &Path) {
     }
 }
 
-fn normalize_line_endings(text: &str) -> String {
-    text.replace("\r\n", "\n").replace("\r", "\n")
-}
-
 fn test_single_asm_file(input_path: &Path, reference_files: &[std::path::PathBuf]) {
     println!("Testing file: {}", input_path.display());
 
@@ -76,18 +73,16 @@ fn test_single_asm_file(input_path: &Path, reference_files: &[std::path::PathBuf
 
     // Compare the output with reference if reference exists
     if let Some(ref_path) = reference_path {
-        let generated = fs::read_to_string(&temp_output_path).unwrap_or_else(|_| panic!("Cannot read generated file: {}",
-            temp_output_path.display()));
-        let reference = fs::read_to_string(&ref_path).unwrap_or_else(|_| panic!("Cannot read reference file: {}",
-            ref_path.display()));
-
-        // Normalize line endings before comparison
-        let generated_normalized = normalize_line_endings(&generated);
-        let reference_normalized = normalize_line_endings(&reference);
+        let generated = fs::read_to_string(&temp_output_path).unwrap_or_else(|_| {
+            panic!("Cannot read generated file: {}", temp_output_path.display())
+        });
+        let reference = fs::read_to_string(&ref_path)
+            .unwrap_or_else(|_| panic!("Cannot read reference file: {}", ref_path.display()));
 
+        // Byte for byte: one `\n` after every word, whatever the source used
         assert_eq!(
-            generated_normalized,
-            reference_normalized,
+            generated,
+            reference,
             "Generated code does not match reference for {}",
             input_path.display()
         );
@@ -131,3 +126,227 @@ fn find_reference_file(
         None
     }
 }
+
+/// Assembles `input` with the binary into `output` and returns the bytes written
+fn assemble_to_bytes(input: &Path, output: &Path) -> Vec<u8> {
+    let status = Command::new("cargo")
+        .args(["run", "--quiet", "--"])
+        .args([input, output])
+        .status()
+        .expect("Failed to execute assembler");
+    assert!(status.success(), "Assembler failed for {}", input.display());
+    fs::read(output).expect("Cannot read generated file")
+}
+
+#[test]
+fn test_crlf_source_matches_lf_output() {
+    let dir = env::temp_dir().join(format!("project6_crlf_{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("Cannot create temp directory");
+
+    let crlf = fs::read("tests/crlf/Add.asm").unwrap();
+    assert!(crlf.windows(2).any(|pair| pair == b"\r\n"));
+    assert!(!crlf.ends_with(b"\n"), "the fixture has no final newline");
+
+    let lf = assemble_to_bytes(Path::new("tests/add/Add.asm"), &dir.join("lf.hack"));
+    let from_crlf = assemble_to_bytes(Path::new("tests/crlf/Add.asm"), &dir.join("crlf.hack"));
+    assert_eq!(from_crlf, lf);
+    assert_eq!(lf, fs::read("tests/add/Add.hack").unwrap());
+    assert!(lf.ends_with(b"\n") && !lf.ends_with(b"\n\n"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn run_where_query(input: &str, symbol: &str) -> String {
+    let output = Command::new("cargo")
+        .args(["run", "--quiet", "--", input, "--where", symbol])
+        .output()
+        .expect("Failed to execute assembler");
+
+    assert!(output.status.success(), "--where {symbol} failed");
+    String::from_utf8(output.stdout).expect("Output is not UTF-8")
+}
+
+#[test]
+fn test_where_query() {
+    assert_eq!(
+        run_where_query("tests/max/Max.asm", "OUTPUT_FIRST"),
+        "OUTPUT_FIRST: ROM 10 (label)\n"
+    );
+    assert_eq!(
+        run_where_query("tests/max/Max.asm", "R2"),
+        "R2: RAM 2 (predefined)\n"
+    );
+}
+
+#[test]
+fn test_verify_existing_directory() {
+    let dir = env::temp_dir().join(format!("project6_batch_{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("Cannot create temp directory");
+
+    // Add.hack matches its source, while Max.hack is a stale copy of Add's output
+    fs::copy("tests/add/Add.asm", dir.join("Add.asm")).unwrap();
+    fs::copy("tests/add/Add.hack", dir.join("Add.hack")).unwrap();
+    fs::copy("tests/max/Max.asm", dir.join("Max.asm")).unwrap();
+    fs::copy("tests/add/Add.hack", dir.join("Max.hack")).unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--quiet",
+            "--",
+            dir.to_str().unwrap(),
+            "--verify-existing",
+        ])
+        .output()
+        .expect("Failed to execute assembler");
+    let stdout = String::from_utf8(output.stdout).expect("Output is not UTF-8");
+
+    assert!(
+        !output.status.success(),
+        "stale output must fail verification"
+    );
+    assert!(stdout.contains(&format!("up to date: {}", dir.join("Add.hack").display())));
+    assert!(stdout.contains(&format!("out of date: {}", dir.join("Max.hack").display())));
+
+    // Verification must not have overwritten the stale file
+    assert_eq!(
+        fs::read(dir.join("Max.hack")).unwrap(),
+        fs::read("tests/add/Add.hack").unwrap()
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_check_reports_every_error() {
+    let dir = env::temp_dir().join(format!("project6_check_{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("Cannot create temp directory");
+    fs::copy("tests/add/Add.asm", dir.join("Add.asm")).unwrap();
+    fs::write(dir.join("Bad.asm"), "@2\nD=Q\nAM=D+2\n0;JMPX\n").unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--quiet", "--", dir.to_str().unwrap(), "--check"])
+        .output()
+        .expect("Failed to execute assembler");
+    let stderr = String::from_utf8(output.stderr).expect("Output is not UTF-8");
+
+    assert!(!output.status.success());
+    for line in ["line 2", "line 3", "line 4"] {
+        assert!(stderr.contains(line), "{stderr}");
+    }
+    assert!(stderr.ends_with("error: 3 error(s) found\n"), "{stderr}");
+    assert!(!dir.join("Add.hack").exists() && !dir.join("Bad.hack").exists());
+
+    let output = Command::new("cargo")
+        .args(["run", "--quiet", "--", "tests/add/Add.asm", "--check"])
+        .output()
+        .expect("Failed to execute assembler");
+    assert!(output.status.success());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_quiet_mode() {
+    let dir = env::temp_dir().join(format!("project6_quiet_{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("Cannot create temp directory");
+    let bad = dir.join("Bad.asm");
+    fs::write(&bad, "@2\nD=Q\n").unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--quiet", "--", "tests/add/Add.asm"])
+        .arg(dir.join("Add.hack"))
+        .arg("--quiet")
+        .output()
+        .expect("Failed to execute assembler");
+    assert!(output.status.success());
+    assert!(
+        output.stdout.is_empty(),
+        "--quiet must not print on success"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("Add.hack")).unwrap(),
+        fs::read_to_string("tests/add/Add.hack").unwrap()
+    );
+
+    let output = Command::new("cargo")
+        .args(["run", "--quiet", "--", bad.to_str().unwrap(), "--quiet"])
+        .output()
+        .expect("Failed to execute assembler");
+    let stderr = String::from_utf8(output.stderr).expect("Output is not UTF-8");
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(stderr.contains("line 2"), "{stderr}");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_banks_image() {
+    let dir = env::temp_dir().join(format!("project6_banks_{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("Cannot create temp directory");
+    // Both programs use a variable and a label; each must get its own
+    fs::write(dir.join("first.asm"), "@x\nM=1\n(END)\n@END\n0;JMP\n").unwrap();
+    fs::write(dir.join("second.asm"), "(LOOP)\n@y\n@LOOP\n0;JMP\n").unwrap();
+    let image = dir.join("image.bin");
+
+    let output = Command::new("cargo")
+        .args(["run", "--quiet", "--", "--banks"])
+        .arg(format!("{}:8", dir.join("second.asm").display()))
+        .arg(format!("{}:0", dir.join("first.asm").display()))
+        .arg("-o")
+        .arg(&image)
+        .output()
+        .expect("Failed to execute assembler");
+    assert!(output.status.success(), "{output:?}");
+
+    let words: Vec<u16> = fs::read(&image)
+        .unwrap()
+        .chunks(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    assert_eq!(
+        words,
+        [16, 0xEFC8, 2, 0xEA87, 0, 0, 0, 0, 16, 0, 0xEA87],
+        "{words:?}"
+    );
+
+    let output = Command::new("cargo")
+        .args(["run", "--quiet", "--", "--banks"])
+        .arg(format!("{}:0", dir.join("first.asm").display()))
+        .arg(format!("{}:3", dir.join("second.asm").display()))
+        .arg("-o")
+        .arg(&image)
+        .output()
+        .expect("Failed to execute assembler");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).expect("Output is not UTF-8");
+    assert!(stderr.contains("overlaps"), "{stderr}");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// The same comparison as `test_all_asm_files`, in-process through the library
+#[test]
+fn test_library_matches_expected_hack() {
+    for entry in fs::read_dir("tests").expect("Cannot read tests directory") {
+        let dir = entry.expect("Cannot read directory entry").path();
+        if !dir.is_dir() {
+            continue;
+        }
+        for file in fs::read_dir(&dir).expect("Cannot read directory") {
+            let asm = file.expect("Cannot read directory entry").path();
+            let hack = asm.with_extension("hack");
+            if asm.extension().is_none_or(|ext| ext != "asm") || !hack.exists() {
+                continue;
+            }
+
+            let source = fs::read_to_string(&asm).unwrap();
+            let words = project6::assemble_to_strings(&source)
+                .unwrap_or_else(|errors| panic!("{}: {errors:?}", asm.display()));
+            let expected = fs::read_to_string(&hack).unwrap();
+            let expected: Vec<&str> = expected.lines().map(str::trim_end).collect();
+            assert_eq!(words, expected, "{}", asm.display());
+        }
+    }
+}
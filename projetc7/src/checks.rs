@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+/// An access to the `argument` segment, checked once all calls are known
+struct ArgumentAccess {
+    function: String,
+    index: i32,
+    line: usize,
+}
+
+/// Optional static check of `local`/`argument` indices against declarations.
+///
+/// `push local 5` inside `function Foo 3` reads stack space the function
+/// never initialized. Likewise an `argument` index is suspicious when every
+/// `call` of the function passes fewer arguments. Argument counts are only
+/// known from call sites, so those accesses are checked in [`finish`].
+///
+/// [`finish`]: SegmentChecker::finish
+#[derive(Default)]
+pub struct SegmentChecker {
+    /// Current function name and its declared local count
+    current_function: Option<(String, i32)>,
+    /// Largest `nArgs` seen at any call site, per callee
+    call_args: HashMap<String, i32>,
+    argument_accesses: Vec<ArgumentAccess>,
+    /// Warnings keyed by VM source line
+    warnings: Vec<(usize, String)>,
+}
+
+impl SegmentChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a `function name nLocals` declaration
+    pub fn enter_function(&mut self, name: &str, n_locals: i32) {
+        self.current_function = Some((name.to_string(), n_locals));
+    }
+
+    /// Records a `call name nArgs` site
+    pub fn record_call(&mut self, name: &str, n_args: i32) {
+        let known = self.call_args.entry(name.to_string()).or_insert(n_args);
+        *known = (*known).max(n_args);
+    }
+
+    /// Checks a push/pop of `segment index` at VM source `line`
+    pub fn check_access(&mut self, segment: &str, index: i32, line: usize) {
+        let Some((function, n_locals)) = &self.current_function else {
+            return;
+        };
+
+        match segment {
+            "local" if index >= *n_locals => self.warnings.push((
+                line,
+                format!(
+                    "line {}: local {} is out of range in {} (declares {} local{})",
+                    line,
+                    index,
+                    function,
+                    n_locals,
+                    if *n_locals == 1 { "" } else { "s" }
+                ),
+            )),
+            "argument" => self.argument_accesses.push(ArgumentAccess {
+                function: function.clone(),
+                index,
+                line,
+            }),
+            _ => {}
+        }
+    }
+
+    /// Completes the argument checks and returns every warning in source order
+    pub fn finish(mut self) -> Vec<String> {
+        for access in &self.argument_accesses {
+            if let Some(&n_args) = self.call_args.get(&access.function) {
+                if access.index >= n_args {
+                    self.warnings.push((access.line, format!(
+                        "line {}: argument {} is out of range in {} (called with at most {} argument{})",
+                        access.line,
+                        access.index,
+                        access.function,
+                        n_args,
+                        if n_args == 1 { "" } else { "s" }
+                    )));
+                }
+            }
+        }
+
+        self.warnings.sort_by_key(|&(line, _)| line);
+        self.warnings
+            .into_iter()
+            .map(|(_, warning)| warning)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_out_of_range() {
+        let mut checker = SegmentChecker::new();
+        checker.enter_function("Foo.bar", 3);
+        checker.check_access("local", 2, 2);
+        checker.check_access("local", 5, 3);
+
+        let warnings = checker.finish();
+        assert_eq!(
+            warnings,
+            ["line 3: local 5 is out of range in Foo.bar (declares 3 locals)"]
+        );
+    }
+
+    #[test]
+    fn test_argument_checked_against_calls() {
+        let mut checker = SegmentChecker::new();
+        checker.enter_function("Foo.bar", 0);
+        checker.check_access("argument", 1, 2);
+        checker.check_access("argument", 2, 3);
+        checker.enter_function("Main.main", 0);
+        checker.record_call("Foo.bar", 1);
+        checker.record_call("Foo.bar", 2);
+        // Callee never called: nothing is statically known
+        checker.enter_function("Foo.unused", 0);
+        checker.check_access("argument", 9, 8);
+
+        assert_eq!(
+            checker.finish(),
+            ["line 3: argument 2 is out of range in Foo.bar (called with at most 2 arguments)"]
+        );
+    }
+
+    #[test]
+    fn test_code_outside_functions_not_checked() {
+        let mut checker = SegmentChecker::new();
+        checker.check_access("local", 7, 1);
+        assert!(checker.finish().is_empty());
+    }
+}
@@ -8,8 +8,8 @@ macro_rules! write_asm {
     };
 }
 
-#[derive(Clone, Copy)]
-enum SegmentSymbol {
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SegmentSymbol {
     Local,
     Argument,
     This,
@@ -21,7 +21,7 @@ enum SegmentSymbol {
 }
 
 impl SegmentSymbol {
-    fn from_str(segment: &str) -> Option<Self> {
+    pub(crate) fn from_str(segment: &str) -> Option<Self> {
         match segment {
             "local" => Some(SegmentSymbol::Local),
             "argument" => Some(SegmentSymbol::Argument),
@@ -47,28 +47,580 @@ impl SegmentSymbol {
             SegmentSymbol::Constant => "CONSTANT", // Special case handled separately
         }
     }
+
+    /// Largest valid index of a fixed-size segment, `None` if unbounded
+    pub(crate) fn max_index(self) -> Option<i32> {
+        match self {
+            SegmentSymbol::Temp => Some(7),
+            SegmentSymbol::Pointer => Some(1),
+            _ => None,
+        }
+    }
+}
+
+/// Passes output through to `W`, or holds it back while a header is pending
+///
+/// The header describes every static, so it can only be written once the
+/// whole program has been seen; the body waits in `held` until then.
+struct HeldWriter<W> {
+    writer: W,
+    held: Option<Vec<u8>>,
+}
+
+impl<W: Write> Write for HeldWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.held {
+            Some(held) => held.write(buf),
+            None => self.writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
 }
 
-pub struct CodeWriter {
-    output_file: BufWriter<File>,
+/// Output sink that counts the ROM instructions written through it
+///
+/// Every line that is not blank, a `//` comment or a `(label)` assembles to
+/// exactly one instruction, so the count is the ROM address of the next one.
+/// Unless `emit_comments` is set, comment and blank lines are dropped here
+/// rather than at each place that writes one.
+struct RomCounter<W> {
+    inner: W,
+    instructions: usize,
+    at_line_start: bool,
+    emit_comments: bool,
+    /// Whether the line being written is a comment being dropped
+    in_comment: bool,
+}
+
+impl<W: Write> RomCounter<W> {
+    fn new(inner: W) -> Self {
+        RomCounter {
+            inner,
+            instructions: 0,
+            at_line_start: true,
+            emit_comments: true,
+            in_comment: false,
+        }
+    }
+
+    /// Writes `buf` without its comment and blank lines
+    fn write_compact(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut kept = Vec::with_capacity(buf.len());
+        for &byte in buf {
+            if self.at_line_start {
+                if byte == b'\n' {
+                    continue;
+                }
+                self.in_comment = byte == b'/';
+                if !matches!(byte, b'/' | b'(') {
+                    self.instructions += 1;
+                }
+            }
+            if !self.in_comment {
+                kept.push(byte);
+            }
+            self.at_line_start = byte == b'\n';
+        }
+        self.inner.write_all(&kept)?;
+        Ok(buf.len())
+    }
+}
+
+impl<W: Write> Write for RomCounter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if !self.emit_comments {
+            return self.write_compact(buf);
+        }
+        let written = self.inner.write(buf)?;
+        for &byte in &buf[..written] {
+            if self.at_line_start && !matches!(byte, b'/' | b'(' | b'\n') {
+                self.instructions += 1;
+            }
+            self.at_line_start = byte == b'\n';
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Register holding the ROM load address in position-independent mode
+///
+/// Code is assembled as if loaded at ROM 0, so every label's value is its
+/// offset from the start of the image. The loader stores the address it
+/// actually placed the image at in RAM[R15] before jumping to it, and each
+/// jump targets `RAM[R15] + offset`.
+const PIC_BASE_REGISTER: u16 = 15;
+
+/// Holds the return address while a shared block runs
+const SHARED_RETURN_REGISTER: u16 = 15;
+
+/// Arithmetic commands that `set_shared_arithmetic` turns into calls
+const SHARED_ARITHMETIC_OPS: [&str; 6] = ["add", "sub", "and", "or", "neg", "not"];
+
+/// Comparison commands that `set_use_subroutines` turns into calls
+const COMPARISON_OPS: [&str; 3] = ["eq", "gt", "lt"];
+
+/// Label of the shared `return` subroutine
+const SHARED_RETURN_LABEL: &str = "SHARED_RETURN";
+
+/// Default scratch register pair used by comparison operations
+const DEFAULT_SCRATCH_REGISTERS: (u16, u16) = (13, 14);
+
+pub struct CodeWriter<W = BufWriter<File>> {
+    output_file: RomCounter<HeldWriter<W>>,
     label_counter: usize,
     filename: String,
+    /// Scratch registers (first operand, second operand), R13/R14 by default
+    scratch: (u16, u16),
+    /// Whether `// vm command:` comments carry the VM source line number
+    emit_source_lines: bool,
+    /// VM source line of the command currently being translated
+    source_line: usize,
+    /// Function entry points are padded to a multiple of this many instructions
+    function_alignment: usize,
+    /// Whether `set_filename` restarts comparison label numbering at 0
+    reset_labels_per_file: bool,
+    /// Whether jumps are relative to the base in R15 (experimental)
+    position_independent: bool,
+    /// Whether comparisons set D to the result and share one push
+    shared_comparison_tail: bool,
+    /// Whether `add`..`not` call one shared block per operation
+    shared_arithmetic: bool,
+    /// Shared arithmetic blocks called so far, emitted by `close`
+    shared_blocks: Vec<&'static str>,
+    /// Whether `eq`/`gt`/`lt` and `return` jump to shared subroutines
+    use_subroutines: bool,
+    /// Whether a comparison subroutine was called, so `close` emits them
+    comparison_routines: bool,
+    /// Whether the return subroutine was jumped to, so `close` emits it
+    return_routine: bool,
+    /// Whether `close` prepends a memory map comment header
+    emit_header: bool,
+    /// Static variable symbols in order of first use, for the header
+    statics: Vec<(String, i32)>,
+    /// Function whose body is being translated, scoping `label`/`goto`/`if-goto`
+    current_function: String,
+    /// Whether `push constant` 0, 1 and -1 store the value without going through D
+    small_constants: bool,
 }
 
 impl CodeWriter {
     /// 创建一个新的CodeWriter实例，用于将汇编代码写入指定的输出文件，默认启动使用Buf占据8192字节。
     pub fn new(output_filename: &str) -> Result<Self, std::io::Error> {
         let file = File::create(output_filename)?;
-        let buffered = BufWriter::with_capacity(8192, file);
-        Ok(CodeWriter {
-            output_file: buffered,
+        Ok(Self::new_with_writer(BufWriter::with_capacity(8192, file)))
+    }
+}
+
+impl CodeWriter<Vec<u8>> {
+    /// Creates a writer that keeps the assembly in memory
+    ///
+    /// Collect it with [`CodeWriter::into_inner`] after `close`.
+    pub fn in_memory() -> Self {
+        Self::new_with_writer(Vec::new())
+    }
+}
+
+impl<W: Write> CodeWriter<W> {
+    /// Creates a writer that sends the assembly to `writer`
+    ///
+    /// Nothing is buffered in between, so wrap unbuffered sinks such as a
+    /// file or socket in a `BufWriter`.
+    pub fn new_with_writer(writer: W) -> Self {
+        CodeWriter {
+            output_file: RomCounter::new(HeldWriter { writer, held: None }),
             label_counter: 0,
             filename: String::new(),
-        })
+            scratch: DEFAULT_SCRATCH_REGISTERS,
+            emit_source_lines: false,
+            source_line: 0,
+            function_alignment: 1,
+            reset_labels_per_file: false,
+            position_independent: false,
+            shared_comparison_tail: false,
+            shared_arithmetic: false,
+            shared_blocks: Vec::new(),
+            use_subroutines: false,
+            comparison_routines: false,
+            return_routine: false,
+            emit_header: false,
+            statics: Vec::new(),
+            current_function: String::new(),
+            small_constants: false,
+        }
+    }
+
+    /// Enables `// vm:<line> <command>` comments in place of `// vm command:<command>`
+    #[inline]
+    pub fn set_emit_source_lines(&mut self, enabled: bool) {
+        self.emit_source_lines = enabled;
+    }
+
+    /// Writes explanatory `//` comments and blank lines between commands
+    /// (the default). Turning this off makes the output about half the size
+    /// without changing the machine code; the `set_emit_header` block is
+    /// still written.
+    pub fn set_emit_comments(&mut self, enabled: bool) {
+        self.output_file.emit_comments = enabled;
+    }
+
+    /// Records the VM source line of the next command to be written
+    #[inline]
+    pub fn set_source_line(&mut self, line: usize) {
+        self.source_line = line;
+    }
+
+    /// Writes the comment introducing the assembly block of one VM command
+    #[inline]
+    fn write_command_comment(
+        &mut self,
+        command: std::fmt::Arguments,
+    ) -> Result<(), std::io::Error> {
+        if self.emit_source_lines {
+            writeln!(self.output_file, "// vm:{} {}", self.source_line, command)
+        } else {
+            writeln!(self.output_file, "// vm command:{}", command)
+        }
+    }
+
+    /// Selects the RAM registers used as scratch space by comparisons, pops
+    /// and `return`.
+    ///
+    /// `a` holds the first operand (second from the top of the stack) and `b`
+    /// the second one. Both must be virtual registers R0–R15 and distinct.
+    #[allow(dead_code)] // Configuration hook, not exposed on the command line yet
+    pub fn set_scratch_registers(&mut self, a: u16, b: u16) -> Result<(), std::io::Error> {
+        if a > 15 || b > 15 || a == b {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "scratch registers must be two distinct registers in R0-R15, got R{a}/R{b}"
+                ),
+            ));
+        }
+        self.scratch = (a, b);
+        Ok(())
+    }
+
+    /// Aligns every function entry point to a multiple of `alignment` ROM
+    /// instructions, for CPU variants that fetch aligned blocks faster.
+    ///
+    /// Padding is made of `0` instructions, which compute zero and store it
+    /// nowhere, so falling through them changes no register or memory.
+    /// An alignment of 0 or 1 disables padding.
+    #[allow(dead_code)] // Configuration hook, not exposed on the command line yet
+    pub fn set_function_alignment(&mut self, alignment: usize) {
+        self.function_alignment = alignment.max(1);
+    }
+
+    /// Writes a function's entry label, padded to the configured alignment
+    pub fn write_function_label(&mut self, name: &str) -> Result<(), std::io::Error> {
+        let misalignment = self.output_file.instructions % self.function_alignment;
+        if misalignment != 0 {
+            writeln!(
+                self.output_file,
+                "// pad to a {}-instruction boundary",
+                self.function_alignment
+            )?;
+            for _ in misalignment..self.function_alignment {
+                self.output_file.write_all(b"0\n")?;
+            }
+        }
+        self.current_function.clear();
+        self.current_function.push_str(name);
+        writeln!(self.output_file, "({})", name)
+    }
+
+    /// Qualifies a VM label with the current function, as in `Foo$loop`
+    ///
+    /// Labels outside any function are left as written.
+    fn scoped_label(&self, label: &str) -> String {
+        if self.current_function.is_empty() {
+            label.to_string()
+        } else {
+            format!("{}${}", self.current_function, label)
+        }
+    }
+
+    /// Writes `label`, marking a jump target in the current function
+    pub fn write_label(&mut self, label: &str) -> Result<(), std::io::Error> {
+        self.write_command_comment(format_args!("label {}", label))?;
+        let label = self.scoped_label(label);
+        writeln!(
+            self.output_file,
+            "({})
+",
+            label
+        )
+    }
+
+    /// Writes `goto`, jumping unconditionally to a label in the current function
+    pub fn write_goto(&mut self, label: &str) -> Result<(), std::io::Error> {
+        self.write_command_comment(format_args!("goto {}", label))?;
+        let label = self.scoped_label(label);
+        self.write_jump(&label, "JMP")?;
+        self.output_file.write_all(b"\n")
+    }
+
+    /// Writes `if-goto`: pops the top of the stack and jumps to `label` if it
+    /// is true (non-zero)
+    pub fn write_if(&mut self, label: &str) -> Result<(), std::io::Error> {
+        self.write_command_comment(format_args!("if-goto {}", label))?;
+        self.write_pop_to_d()?;
+        let label = self.scoped_label(label);
+        self.write_jump(&label, "JNE")?;
+        self.output_file.write_all(b"\n")
+    }
+
+    /// Restarts comparison label numbering (`EQ0`, `GT0`, ...) at every
+    /// `set_filename` call, so each file's output matches what translating it
+    /// alone would produce.
+    ///
+    /// Comparison labels are not qualified by file name, so two files
+    /// translated into one `.asm` with this enabled will define the same
+    /// labels twice. Only use it when each file's output is assembled on its
+    /// own; by default the counter runs on across files and stays unique.
+    #[allow(dead_code)] // Configuration hook, not exposed on the command line yet
+    pub fn set_reset_labels_per_file(&mut self, enabled: bool) {
+        self.reset_labels_per_file = enabled;
+    }
+
+    /// Makes `eq`/`gt`/`lt` branch only to pick `0` or `-1` into D and then
+    /// push it with a single shared sequence, instead of a full push on each
+    /// branch. Saves three instructions per comparison.
+    #[allow(dead_code)] // Configuration hook, not exposed on the command line yet
+    pub fn set_shared_comparison_tail(&mut self, enabled: bool) {
+        self.shared_comparison_tail = enabled;
+    }
+
+    /// Pushes the constants 0, 1 and -1 by writing them straight to the stack
+    /// (`M=0`, `M=1`, `M=-1`), two instructions shorter than loading them
+    /// into D first. The stack ends up the same; only D differs afterwards,
+    /// which no command relies on.
+    pub fn set_small_constants(&mut self, enabled: bool) {
+        self.small_constants = enabled;
+    }
+
+    /// Starts the output with a comment block describing the RAM layout and
+    /// where each static variable the program uses will be allocated.
+    ///
+    /// Static addresses assume the assembler allocates variables from RAM 16
+    /// in order of first use, as the standard Hack assembler does; statics
+    /// are the only variables the translator emits. The header is written by
+    /// `close` once every static is known, so until then the output is held
+    /// in memory instead of reaching the writer.
+    pub fn set_emit_header(&mut self, enabled: bool) {
+        self.emit_header = enabled;
+        let held = &mut self.output_file.inner.held;
+        if enabled && held.is_none() {
+            *held = Some(Vec::new());
+        }
+    }
+
+    /// Pops the top of the stack into the RAM address held in D
+    ///
+    /// The address waits in the first scratch register while the value is
+    /// popped.
+    fn write_pop_to_address_in_d(&mut self) -> Result<(), std::io::Error> {
+        write!(
+            self.output_file,
+            "// store the result temporarily\n@R{a}\nM=D\n",
+            a = self.scratch.0
+        )?;
+        self.write_pop_to_d()?;
+        write!(
+            self.output_file,
+            "// store the top value\n@R{a}\nA=M\nM=D\n",
+            a = self.scratch.0
+        )
+    }
+
+    /// Returns the assembly symbol of static `index`, recording it for the header
+    ///
+    /// Statics are named after the file they belong to, so without a
+    /// `set_filename` call they would all collide as `.0`, `.1`, ...
+    fn static_symbol(&mut self, index: i32) -> Result<String, std::io::Error> {
+        if self.filename.is_empty() {
+            return Err(invalid_command(format_args!(
+                "static {} accessed before set_filename named its file",
+                index
+            )));
+        }
+        if !self
+            .statics
+            .iter()
+            .any(|(file, i)| *i == index && *file == self.filename)
+        {
+            self.statics.push((self.filename.clone(), index));
+        }
+        Ok(format!("{}.{}", self.filename, index))
+    }
+
+    /// Builds the memory map comment block written by `set_emit_header`
+    fn header(&self) -> String {
+        let mut header = String::from(
+            "// Memory map\n\
+             //   RAM[0-4]      SP, LCL, ARG, THIS, THAT\n\
+             //   RAM[5-12]     temp 0-7\n",
+        );
+        header.push_str(&format!(
+            "//   RAM[{}], RAM[{}] scratch registers\n",
+            self.scratch.0, self.scratch.1
+        ));
+        if self.position_independent || self.shared_arithmetic || self.use_subroutines {
+            header.push_str(&format!(
+                "//   RAM[{}]        {}\n",
+                PIC_BASE_REGISTER,
+                if self.position_independent {
+                    "load address for relative jumps"
+                } else {
+                    "return address of shared blocks"
+                }
+            ));
+        }
+        header.push_str(
+            "//   RAM[16-255]   static variables\n\
+             //   RAM[256-2047] stack\n",
+        );
+        for (address, (file, index)) in (16..).zip(&self.statics) {
+            header.push_str(&format!(
+                "// static {index} -> {file}.{index} at RAM[{address}]\n"
+            ));
+        }
+        header.push('\n');
+        header
+    }
+
+    /// Emits `add`/`sub`/`and`/`or`/`neg`/`not` as calls to one shared block
+    /// per operation, written once by `close`.
+    ///
+    /// A call stores its return address in R15 (see [`SHARED_RETURN_REGISTER`])
+    /// and jumps to the block, which ends by jumping back through R15:
+    ///
+    /// | op                | inline | call | block |
+    /// |-------------------|--------|------|-------|
+    /// | `add`/`sub`/`and`/`or` | 5 | 6 | 8 |
+    /// | `neg`             | 11     | 6    | 14    |
+    /// | `not`             | 10     | 6    | 13    |
+    ///
+    /// A binary op never pays off, since the in-place code is shorter than the
+    /// call; `neg` does from its third use and `not` from its fourth. `close`
+    /// also spends two instructions on a halt loop that keeps execution from
+    /// running into the blocks. Every call costs
+    /// nine extra instructions at run time. Position-independent mode keeps
+    /// the inline code, since the return address would be absolute.
+    pub fn set_shared_arithmetic(&mut self, enabled: bool) {
+        self.shared_arithmetic = enabled;
+    }
+
+    /// Emits `eq`/`gt`/`lt` as calls to shared comparison subroutines and
+    /// `return` as a jump to a shared return subroutine, written once by
+    /// `close` after the shared arithmetic blocks.
+    ///
+    /// Comparisons call the same way as [`set_shared_arithmetic`], through
+    /// R15; the three comparison subroutines are emitted together the first
+    /// time any of them is needed. `return` needs no return address, since
+    /// it jumps through the caller's frame anyway, so each one shrinks from
+    /// about 40 instructions to 2. Position-independent mode keeps the inline
+    /// code.
+    ///
+    /// [`set_shared_arithmetic`]: CodeWriter::set_shared_arithmetic
+    pub fn set_use_subroutines(&mut self, enabled: bool) {
+        self.use_subroutines = enabled;
+    }
+
+    /// Experimental: emits every jump relative to a base register so the
+    /// assembled image can be loaded at any ROM offset.
+    ///
+    /// The loader must store the image's load address in RAM[R15] (see
+    /// [`PIC_BASE_REGISTER`]) and nothing may overwrite it. Relative jumps are
+    /// longer than absolute ones, and conditional jumps use the scratch
+    /// registers to hold the condition and target, so neither of those may be
+    /// R15 either.
+    pub fn set_position_independent(&mut self, enabled: bool) {
+        self.position_independent = enabled;
+    }
+
+    /// Jumps to `label` if D satisfies `jump` (`JMP` jumps unconditionally)
+    ///
+    /// In position-independent mode the target is computed as
+    /// `RAM[R15] + label`; D is preserved for the condition via a scratch
+    /// register.
+    fn write_jump(&mut self, label: &str, jump: &str) -> Result<(), std::io::Error> {
+        if !self.position_independent {
+            let condition = if jump == "JMP" { "0" } else { "D" };
+            return write!(self.output_file, "@{label}\n{condition};{jump}\n");
+        }
+
+        if jump == "JMP" {
+            return write!(
+                self.output_file,
+                "// relative jump: RAM[R{base}] + {label}\n\
+                 @{label}\n\
+                 D=A\n\
+                 @R{base}\n\
+                 A=D+M\n\
+                 0;JMP\n",
+                base = PIC_BASE_REGISTER,
+            );
+        }
+
+        self.write_relative_jump_keeping_d(label, jump)
+    }
+
+    /// Like `write_jump` with `JMP`, but D still holds its value at `label`
+    ///
+    /// A relative jump computes its target in D, so this one parks D in a
+    /// scratch register on the way. Absolute jumps never touch D.
+    fn write_jump_keeping_d(&mut self, label: &str) -> Result<(), std::io::Error> {
+        if self.position_independent {
+            self.write_relative_jump_keeping_d(label, "JMP")
+        } else {
+            self.write_jump(label, "JMP")
+        }
+    }
+
+    /// Jumps to RAM[R15] + `label` when D satisfies `jump`, preserving D
+    fn write_relative_jump_keeping_d(
+        &mut self,
+        label: &str,
+        jump: &str,
+    ) -> Result<(), std::io::Error> {
+        let condition = if jump == "JMP" { "0" } else { "D" };
+        write!(
+            self.output_file,
+            "// relative jump: RAM[R{base}] + {label} if D {jump}\n\
+             @R{a}\n\
+             M=D\n\
+             @{label}\n\
+             D=A\n\
+             @R{base}\n\
+             D=D+M\n\
+             @R{b}\n\
+             M=D\n\
+             @R{a}\n\
+             D=M\n\
+             @R{b}\n\
+             A=M\n\
+             {condition};{jump}\n",
+            base = PIC_BASE_REGISTER,
+            a = self.scratch.0,
+            b = self.scratch.1,
+        )
     }
 
     #[inline]
     pub fn set_filename(&mut self, filename: &str) {
+        if self.reset_labels_per_file {
+            self.label_counter = 0;
+        }
+
         // Extract filename without path and extension
         let name = std::path::Path::new(filename)
             .file_stem()
@@ -78,12 +630,187 @@ impl CodeWriter {
         self.filename.push_str(name);
     }
 
+    /// Writes the bootstrap code: sets `SP` to 256 and calls `Sys.init`
+    ///
+    /// Belongs at the very start of a whole program's output; a single file
+    /// translated for testing on its own doesn't want it.
+    pub fn write_init(&mut self) -> Result<(), std::io::Error> {
+        write_asm!(self.output_file,
+            "// bootstrap: SP = 256"
+            "@256"
+            "D=A"
+            "@SP"
+            "M=D"
+        )?;
+        self.write_call("Sys.init", 0)
+    }
+
+    /// Writes `function name nLocals`: the entry label, then `n_locals` zeros
+    /// pushed to initialize the local segment
+    pub fn write_function(&mut self, name: &str, n_locals: i32) -> Result<(), std::io::Error> {
+        if n_locals < 0 {
+            return Err(invalid_command(format_args!(
+                "function {} declares a negative local count: {}",
+                name, n_locals
+            )));
+        }
+        self.write_command_comment(format_args!("function {} {}", name, n_locals))?;
+        self.write_function_label(name)?;
+        for _ in 0..n_locals {
+            write_asm!(self.output_file,
+                "@SP"
+                "A=M"
+                "M=0"
+                "@SP"
+                "M=M+1"
+            )?;
+        }
+        self.output_file.write_all(b"\n")
+    }
+
+    /// Writes `call name nArgs`: saves the caller's frame, points ARG at the
+    /// first argument and LCL at the new frame, then jumps to `name`
+    ///
+    /// The return address label is `caller$ret.N`, numbered with the same
+    /// counter as the comparison labels so it is unique in the output.
+    pub fn write_call(&mut self, name: &str, n_args: i32) -> Result<(), std::io::Error> {
+        if n_args < 0 {
+            return Err(invalid_command(format_args!(
+                "call {} passes a negative argument count: {}",
+                name, n_args
+            )));
+        }
+        self.write_command_comment(format_args!("call {} {}", name, n_args))?;
+
+        let caller = if self.current_function.is_empty() {
+            &self.filename
+        } else {
+            &self.current_function
+        };
+        let return_label = format!("{}$ret.{}", caller, self.label_counter);
+        self.label_counter += 1;
+
+        writeln!(
+            self.output_file,
+            "// push the return address\n@{return_label}\nD=A"
+        )?;
+        if self.position_independent {
+            // Return through an absolute address, so add the load address
+            writeln!(self.output_file, "@R{}\nD=D+M", PIC_BASE_REGISTER)?;
+        }
+        self.write_push_d()?;
+        for pointer in ["LCL", "ARG", "THIS", "THAT"] {
+            writeln!(self.output_file, "@{pointer}\nD=M")?;
+            self.write_push_d()?;
+        }
+        write!(
+            self.output_file,
+            "// ARG = SP - {offset}, LCL = SP\n\
+             @SP\n\
+             D=M\n\
+             @{offset}\n\
+             D=D-A\n\
+             @ARG\n\
+             M=D\n\
+             @SP\n\
+             D=M\n\
+             @LCL\n\
+             M=D\n",
+            offset = n_args + 5,
+        )?;
+        self.write_jump(name, "JMP")?;
+        writeln!(self.output_file, "({return_label})\n")
+    }
+
+    /// Writes `return`: places the return value where the caller's arguments
+    /// began, restores the caller's frame and jumps to the return address
+    ///
+    /// The frame and return address are kept in the scratch registers.
+    pub fn write_return(&mut self) -> Result<(), std::io::Error> {
+        self.write_command_comment(format_args!("return"))?;
+        if self.use_subroutines && !self.position_independent {
+            self.return_routine = true;
+            return write!(self.output_file, "@{SHARED_RETURN_LABEL}\n0;JMP\n\n");
+        }
+        self.write_return_body()
+    }
+
+    /// Writes the instructions of `return`, inline or as the shared subroutine
+    fn write_return_body(&mut self) -> Result<(), std::io::Error> {
+        let (frame, ret) = self.scratch;
+
+        write!(
+            self.output_file,
+            "// frame = LCL, return address = *(frame - 5)\n\
+             @LCL\n\
+             D=M\n\
+             @R{frame}\n\
+             M=D\n\
+             @5\n\
+             A=D-A\n\
+             D=M\n\
+             @R{ret}\n\
+             M=D\n",
+        )?;
+        self.write_pop_to_d()?;
+        write_asm!(self.output_file,
+            "// *ARG = return value, SP = ARG + 1"
+            "@ARG"
+            "A=M"
+            "M=D"
+            "@ARG"
+            "D=M+1"
+            "@SP"
+            "M=D"
+        )?;
+        for pointer in ["THAT", "THIS", "ARG", "LCL"] {
+            write!(
+                self.output_file,
+                "@R{frame}\n\
+                 AM=M-1\n\
+                 D=M\n\
+                 @{pointer}\n\
+                 M=D\n",
+            )?;
+        }
+        write!(self.output_file, "@R{ret}\nA=M\n0;JMP\n\n")
+    }
+
+    /// Pops the top of the stack and jumps to `target` if it is false (zero)
+    ///
+    /// The inverse of `if-goto`, used when `if-goto label` / `goto target` /
+    /// `label label` collapses into "jump to target unless the condition
+    /// holds". Both original commands are written as comments.
+    pub fn write_if_not(&mut self, label: &str, target: &str) -> Result<(), std::io::Error> {
+        self.write_command_comment(format_args!("if-goto {}", label))?;
+        self.write_command_comment(format_args!("goto {}", target))?;
+        self.write_pop_to_d()?;
+        let target = self.scoped_label(target);
+        self.write_jump(&target, "JEQ")?;
+        self.output_file.write_all(b"\n")
+    }
+
     pub fn write_arithmetic(&mut self, command: &str) -> Result<(), std::io::Error> {
-        writeln!(self.output_file, "// vm command:{}", command)?;
+        self.write_command_comment(format_args!("{}", command))?;
+
+        if self.shared_arithmetic && !self.position_independent {
+            if let Some(&op) = SHARED_ARITHMETIC_OPS.iter().find(|&&op| op == command) {
+                if !self.shared_blocks.contains(&op) {
+                    self.shared_blocks.push(op);
+                }
+                return self.write_shared_call(op);
+            }
+        }
+        if self.use_subroutines && !self.position_independent {
+            if let Some(&op) = COMPARISON_OPS.iter().find(|&&op| op == command) {
+                self.comparison_routines = true;
+                return self.write_shared_call(op);
+            }
+        }
 
         match command {
             "add" => self.write_binary_op("D+M"),
-            "sub" => self.write_binary_op("D-M"),
+            "sub" => self.write_binary_op("M-D"),
             "and" => self.write_binary_op("D&M"),
             "or" => self.write_binary_op("D|M"),
             "neg" => self.write_unary_op(true),
@@ -91,41 +818,96 @@ impl CodeWriter {
             "eq" => self.write_comparison("JEQ"),
             "gt" => self.write_comparison("JGT"),
             "lt" => self.write_comparison("JLT"),
-            _ => panic!("Unknown arithmetic command: {}", command),
+            _ => Err(invalid_command(format_args!(
+                "unknown arithmetic command: {}",
+                command
+            ))),
         }
     }
 
+    /// Calls the shared block for `op`, returning through R15
+    fn write_shared_call(&mut self, op: &str) -> Result<(), std::io::Error> {
+        let block = shared_block_label(op);
+        let return_label = format!("{block}_RETURN{}", self.label_counter);
+        self.label_counter += 1;
+
+        write!(
+            self.output_file,
+            "@{return_label}\n\
+             D=A\n\
+             @R{ret}\n\
+             M=D\n\
+             @{block}\n\
+             0;JMP\n\
+             ({return_label})\n\n",
+            ret = SHARED_RETURN_REGISTER,
+        )
+    }
+
+    /// Writes the body of every shared block and subroutine called so far
+    fn write_shared_blocks(&mut self) -> Result<(), std::io::Error> {
+        let blocks = std::mem::take(&mut self.shared_blocks);
+        let comparisons = std::mem::take(&mut self.comparison_routines);
+        let ret = std::mem::take(&mut self.return_routine);
+        if blocks.is_empty() && !comparisons && !ret {
+            return Ok(());
+        }
+
+        write_asm!(self.output_file,
+            "// halt before the shared blocks"
+            "(SHARED_ARITHMETIC_HALT)"
+            "@SHARED_ARITHMETIC_HALT"
+            "0;JMP"
+        )?;
+        for op in blocks {
+            writeln!(self.output_file, "({})", shared_block_label(op))?;
+            match op {
+                "add" => self.write_binary_op("D+M")?,
+                "sub" => self.write_binary_op("M-D")?,
+                "and" => self.write_binary_op("D&M")?,
+                "or" => self.write_binary_op("D|M")?,
+                "neg" => self.write_unary_op(true)?,
+                _ => self.write_unary_op(false)?,
+            }
+            self.write_shared_block_return()?;
+        }
+        if comparisons {
+            for (op, jump) in COMPARISON_OPS.into_iter().zip(["JEQ", "JGT", "JLT"]) {
+                writeln!(self.output_file, "({})", shared_block_label(op))?;
+                self.write_comparison(jump)?;
+                self.write_shared_block_return()?;
+            }
+        }
+        if ret {
+            writeln!(self.output_file, "({SHARED_RETURN_LABEL})")?;
+            self.write_return_body()?;
+        }
+        Ok(())
+    }
+
+    /// Ends a shared block by jumping back through R15
+    fn write_shared_block_return(&mut self) -> Result<(), std::io::Error> {
+        write!(
+            self.output_file,
+            "// return to the caller\n@R{}\nA=M\n0;JMP\n\n",
+            SHARED_RETURN_REGISTER
+        )
+    }
+
+    /// Pops y and replaces x, the new top, with `operation` applied in place
+    ///
+    /// `operation` is a comp over M (x) and D (y), such as `M-D` for `sub`.
     #[inline]
     fn write_binary_op(&mut self, operation: &str) -> Result<(), std::io::Error> {
-        // Optimized: write all at once to reduce syscalls
         write!(
             self.output_file,
-            "// get the top element of stack\n\
-             @SP\n\
-             M=M-1\n\
-             A=M\n\
-             D=M\n\
-             // store the result temporarily\n\
-             @R14\n\
-             M=D\n\
-             // get the top element of stack\n\
+            "// pop y into D and point at x\n\
              @SP\n\
-             M=M-1\n\
-             A=M\n\
-             D=M\n\
-             // store the result temporarily\n\
-             @R13\n\
-             M=D\n\
-             @R13\n\
+             AM=M-1\n\
              D=M\n\
-             @R14\n\
-             D={}\n",
-            operation
-        )?;
-
-        self.write_push_d()?;
-        self.output_file.write_all(b"\n")?;
-        Ok(())
+             A=A-1\n\
+             M={operation}\n\n",
+        )
     }
 
     #[inline]
@@ -171,7 +953,7 @@ impl CodeWriter {
              A=M\n\
              D=M\n\
              // store the result temporarily\n\
-             @R14\n\
+             @R{b}\n\
              M=D\n\
              // get the top element of stack\n\
              @SP\n\
@@ -179,39 +961,107 @@ impl CodeWriter {
              A=M\n\
              D=M\n\
              // store the result temporarily\n\
-             @R13\n\
-             M=D\n\
-             @R13\n\
-             D=M\n\
-             @R14\n\
-             D=D-M\n\
-             @{}{}\n\
-             D;{}\n\
-             // push the value into stack\n\
+             @R{a}\n\
+             M=D\n",
+            a = self.scratch.0,
+            b = self.scratch.1,
+        )?;
+        let true_label = format!("{label_prefix}{label_num}");
+        let end_label = format!("END{label_prefix}{label_num}");
+        let false_label = format!("FALSE{label_prefix}{label_num}");
+
+        let sign_checked = jump != "JEQ";
+        if sign_checked {
+            self.write_sign_check(label_prefix, label_num, jump, &true_label, &false_label)?;
+        } else {
+            // x - y is zero exactly when x == y, even if it overflows
+            write!(
+                self.output_file,
+                "@R{a}\n\
+                 D=M\n\
+                 @R{b}\n\
+                 D=D-M\n",
+                a = self.scratch.0,
+                b = self.scratch.1,
+            )?;
+        }
+        self.write_jump(&true_label, jump)?;
+        if sign_checked {
+            writeln!(self.output_file, "({false_label})")?;
+        }
+
+        if self.shared_comparison_tail {
+            // The tail pushes D, so the jump to it must not clobber the result
+            self.output_file.write_all(b"D=0\n")?;
+            self.write_jump_keeping_d(&end_label)?;
+            write!(self.output_file, "({true_label})\nD=-1\n({end_label})\n")?;
+            self.write_push_d()?;
+            return self.output_file.write_all(b"\n");
+        }
+
+        write!(
+            self.output_file,
+            "// push the value into stack\n\
              @SP\n\
              A=M\n\
              M=0\n\
              @SP\n\
-             M=M+1\n\
-             @END{}{}\n\
-             0;JMP\n\
-             ({}{})\n\
+             M=M+1\n",
+        )?;
+        self.write_jump(&end_label, "JMP")?;
+        write!(
+            self.output_file,
+            "({true_label})\n\
              // push the value into stack\n\
              @SP\n\
              A=M\n\
              M=-1\n\
              @SP\n\
              M=M+1\n\
-             (END{}{})\n\n",
-            label_prefix,
-            label_num,
-            jump,
-            label_prefix,
-            label_num,
-            label_prefix,
-            label_num,
-            label_prefix,
-            label_num
+             ({end_label})\n\n",
+        )
+    }
+
+    /// Leaves `x - y` in D for `gt`/`lt`, or jumps straight to the answer
+    /// when the operands' signs differ and the subtraction could overflow
+    ///
+    /// Expects x in D and both operands still in place just above the stack
+    /// pointer; they are reloaded from there because position-independent
+    /// conditional jumps clobber the scratch registers.
+    fn write_sign_check(
+        &mut self,
+        label_prefix: &str,
+        label_num: usize,
+        jump: &str,
+        true_label: &str,
+        false_label: &str,
+    ) -> Result<(), std::io::Error> {
+        let negative_label = format!("NEG{label_prefix}{label_num}");
+        let subtract_label = format!("SUB{label_prefix}{label_num}");
+        // With x >= 0 > y, x is greater; with x < 0 <= y, it is less
+        let (x_greater, x_less) = if jump == "JGT" {
+            (true_label, false_label)
+        } else {
+            (false_label, true_label)
+        };
+
+        self.output_file
+            .write_all(b"// compare the signs first: x - y overflows when they differ\n")?;
+        self.write_jump(&negative_label, "JLT")?;
+        self.output_file.write_all(b"@SP\nA=M+1\nD=M\n")?;
+        self.write_jump(&subtract_label, "JGE")?;
+        self.write_jump(x_greater, "JMP")?;
+        writeln!(self.output_file, "({negative_label})\n@SP\nA=M+1\nD=M")?;
+        self.write_jump(&subtract_label, "JLT")?;
+        self.write_jump(x_less, "JMP")?;
+        write!(
+            self.output_file,
+            "({subtract_label})\n\
+             @SP\n\
+             A=M\n\
+             D=M\n\
+             A=A+1\n\
+             D=D-M\n",
         )
     }
 
@@ -221,11 +1071,15 @@ impl CodeWriter {
         segment: &str,
         index: i32,
     ) -> Result<(), std::io::Error> {
-        writeln!(
-            self.output_file,
-            "// vm command:{} {} {}",
-            command, segment, index
-        )?;
+        if let Some(max) = SegmentSymbol::from_str(segment).and_then(SegmentSymbol::max_index) {
+            if !(0..=max).contains(&index) {
+                return Err(invalid_command(format_args!(
+                    "{} index must be between 0 and {}: {} {} {}",
+                    segment, max, command, segment, index
+                )));
+            }
+        }
+        self.write_command_comment(format_args!("{} {} {}", command, segment, index))?;
 
         if command == "push" {
             self.write_push(segment, index)?;
@@ -241,8 +1095,23 @@ impl CodeWriter {
     fn write_push(&mut self, segment: &str, index: i32) -> Result<(), std::io::Error> {
         match SegmentSymbol::from_str(segment) {
             Some(SegmentSymbol::Constant) => {
-                write!(self.output_file, "@{}\nD=A\n", index)?;
-                self.write_push_d()
+                let comp = match index {
+                    0 => Some("0"),
+                    1 => Some("1"),
+                    -1 => Some("-1"),
+                    _ => None,
+                };
+                match comp.filter(|_| self.small_constants) {
+                    Some(comp) => write!(
+                        self.output_file,
+                        "// push the value into stack\n@SP\nA=M\nM={}\n@SP\nM=M+1\n",
+                        comp
+                    ),
+                    None => {
+                        write!(self.output_file, "@{}\nD=A\n", index)?;
+                        self.write_push_d()
+                    }
+                }
             }
             Some(seg)
                 if matches!(
@@ -270,10 +1139,14 @@ impl CodeWriter {
                 self.write_push_d()
             }
             Some(SegmentSymbol::Static) => {
-                write!(self.output_file, "@{}.{}\nD=M\n", self.filename, index)?;
+                let symbol = self.static_symbol(index)?;
+                write!(self.output_file, "@{}\nD=M\n", symbol)?;
                 self.write_push_d()
             }
-            _ => panic!("Unknown segment: {}", segment),
+            _ => Err(invalid_command(format_args!(
+                "unknown segment: {}",
+                segment
+            ))),
         }
     }
 
@@ -295,22 +1168,11 @@ impl CodeWriter {
                     "@{}\n\
                      D=M\n\
                      @{}\n\
-                     D=D+A\n\
-                     // store the result temporarily\n\
-                     @R13\n\
-                     M=D\n",
+                     D=D+A\n",
                     segment_symbol, index
                 )?;
 
-                self.write_pop_to_d()?;
-
-                write_asm!(self.output_file,
-                    "// store the top value"
-                    "@R13"
-                    "A=M"
-                    "M=D"
-                )?;
-                Ok(())
+                self.write_pop_to_address_in_d()
             }
             Some(SegmentSymbol::Temp) => {
                 write!(
@@ -318,22 +1180,11 @@ impl CodeWriter {
                     "@5\n\
                      D=A\n\
                      @{}\n\
-                     D=D+A\n\
-                     // store the result temporarily\n\
-                     @R13\n\
-                     M=D\n",
+                     D=D+A\n",
                     index
                 )?;
 
-                self.write_pop_to_d()?;
-
-                write_asm!(self.output_file,
-                    "// store the top value"
-                    "@R13"
-                    "A=M"
-                    "M=D"
-                )?;
-                Ok(())
+                self.write_pop_to_address_in_d()
             }
             Some(SegmentSymbol::Pointer) => {
                 write!(
@@ -341,28 +1192,21 @@ impl CodeWriter {
                     "@THIS\n\
                      D=A\n\
                      @{}\n\
-                     D=D+A\n\
-                     // store the result temporarily\n\
-                     @R13\n\
-                     M=D\n",
+                     D=D+A\n",
                     index
                 )?;
 
-                self.write_pop_to_d()?;
-
-                write_asm!(self.output_file,
-                    "// store the top value"
-                    "@R13"
-                    "A=M"
-                    "M=D"
-                )?;
-                Ok(())
+                self.write_pop_to_address_in_d()
             }
             Some(SegmentSymbol::Static) => {
+                let symbol = self.static_symbol(index)?;
                 self.write_pop_to_d()?;
-                write!(self.output_file, "@{}.{}\nM=D\n", self.filename, index)
+                write!(self.output_file, "@{}\nM=D\n", symbol)
             }
-            _ => panic!("Cannot pop to segment: {}", segment),
+            _ => Err(invalid_command(format_args!(
+                "cannot pop to segment: {}",
+                segment
+            ))),
         }
     }
 
@@ -391,6 +1235,540 @@ impl CodeWriter {
 
     #[inline]
     pub fn close(&mut self) -> Result<(), std::io::Error> {
+        self.write_shared_blocks()?;
+
+        if let Some(body) = self.output_file.inner.held.take() {
+            let header = if self.emit_header {
+                self.header()
+            } else {
+                String::new()
+            };
+            let writer = &mut self.output_file.inner.writer;
+            writer.write_all(header.as_bytes())?;
+            writer.write_all(&body)?;
+        }
         self.output_file.flush()
     }
+
+    /// Returns the underlying writer, e.g. the buffer of [`in_memory`]
+    ///
+    /// Call `close` first, or the shared blocks and header will be missing.
+    ///
+    /// [`in_memory`]: CodeWriter::in_memory
+    pub fn into_inner(self) -> W {
+        self.output_file.inner.writer
+    }
+}
+
+/// Error for a VM command the writer cannot translate
+fn invalid_command(message: std::fmt::Arguments) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message.to_string())
+}
+
+/// Label of the shared block implementing arithmetic command `op`
+fn shared_block_label(op: &str) -> String {
+    format!("SHARED_{}", op.to_ascii_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `emit` against a fresh writer and returns the generated assembly
+    fn generate(emit: impl FnOnce(&mut CodeWriter<Vec<u8>>)) -> String {
+        let mut writer = CodeWriter::in_memory();
+        writer.set_filename("Test.vm");
+        emit(&mut writer);
+        writer.close().unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn test_default_scratch_registers() {
+        let asm = generate(|w| {
+            w.write_arithmetic("eq").unwrap();
+        });
+        assert!(asm.contains("@R13\n"));
+        assert!(asm.contains("@R14\n"));
+    }
+
+    #[test]
+    fn test_custom_scratch_registers() {
+        let asm = generate(|w| {
+            w.set_scratch_registers(11, 12).unwrap();
+            w.write_arithmetic("eq").unwrap();
+            for segment in [
+                "local", "argument", "this", "that", "temp", "pointer", "static",
+            ] {
+                w.write_push_pop("push", "constant", 1).unwrap();
+                w.write_push_pop("pop", segment, 1).unwrap();
+            }
+        });
+        assert!(asm.contains("@R11\nM=D\n@R11\nD=M\n@R12\nD=D-M\n"));
+        assert!(asm.contains("@R11\nM=D\n// get the top element of stack\n@SP\nM=M-1\nA=M\nD=M\n// store the top value\n@R11\nA=M\nM=D\n"));
+        assert!(!asm.contains("@R13"));
+        assert!(!asm.contains("@R14"));
+    }
+
+    #[test]
+    fn test_fixed_segment_bounds() {
+        let asm = generate(|w| {
+            let error = w.write_push_pop("push", "temp", 99).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "temp index must be between 0 and 7: push temp 99"
+            );
+            assert!(w.write_push_pop("pop", "pointer", 2).is_err());
+            w.write_push_pop("pop", "pointer", 1).unwrap();
+        });
+        assert!(asm.starts_with("// vm command:pop pointer 1\n"));
+    }
+
+    #[test]
+    fn test_in_memory_output() {
+        let mut writer = CodeWriter::in_memory();
+        writer.set_filename("Main.vm");
+        writer.set_emit_header(true);
+        writer.write_push_pop("pop", "static", 1).unwrap();
+        writer.close().unwrap();
+
+        let asm = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(asm.starts_with("// Memory map\n"));
+        assert!(asm.contains("@Main.1\nM=D\n"));
+    }
+
+    #[test]
+    fn test_compact_output_assembles_identically() {
+        fn program(w: &mut CodeWriter<Vec<u8>>) {
+            w.write_function("Main.main", 1).unwrap();
+            w.write_label("LOOP").unwrap();
+            w.write_push_pop("push", "local", 0).unwrap();
+            w.write_push_pop("push", "constant", 3).unwrap();
+            w.write_arithmetic("lt").unwrap();
+            w.write_if("LOOP").unwrap();
+            w.write_call("Main.main", 0).unwrap();
+            w.write_push_pop("pop", "static", 1).unwrap();
+            w.write_return().unwrap();
+        }
+        let full = generate(program);
+        let compact = generate(|w| {
+            w.set_emit_comments(false);
+            program(w);
+        });
+
+        assert!(!compact.contains("//"));
+        assert!(!compact.contains("\n\n"));
+        assert!(compact.len() * 3 < full.len() * 2);
+        assert_eq!(
+            project6::assemble(&compact).unwrap(),
+            project6::assemble(&full).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_source_line_comments() {
+        let asm = generate(|w| {
+            w.set_emit_source_lines(true);
+            w.set_source_line(7);
+            w.write_push_pop("push", "local", 0).unwrap();
+            w.set_source_line(12);
+            w.write_arithmetic("add").unwrap();
+        });
+        assert!(asm.starts_with("// vm:7 push local 0\n"));
+        assert!(asm.contains("\n// vm:12 add\n"));
+        assert!(!asm.contains("vm command:"));
+
+        let asm = generate(|w| {
+            w.set_source_line(7);
+            w.write_push_pop("push", "local", 0).unwrap();
+        });
+        assert!(asm.starts_with("// vm command:push local 0\n"));
+    }
+
+    /// Returns the ROM address each label in `asm` resolves to
+    fn label_addresses(asm: &str) -> Vec<(String, usize)> {
+        let mut address = 0;
+        let mut labels = Vec::new();
+        for line in asm.lines().map(str::trim) {
+            if let Some(label) = line.strip_prefix('(') {
+                labels.push((label.trim_end_matches(')').to_string(), address));
+            } else if !line.is_empty() && !line.starts_with("//") {
+                address += 1;
+            }
+        }
+        labels
+    }
+
+    #[test]
+    fn test_function_alignment() {
+        let asm = generate(|w| {
+            w.set_function_alignment(8);
+            w.write_function_label("Main.first").unwrap();
+            w.write_push_pop("push", "constant", 7).unwrap();
+            w.write_arithmetic("eq").unwrap();
+            w.write_function_label("Main.second").unwrap();
+            w.write_push_pop("push", "local", 2).unwrap();
+            w.write_function_label("Main.third").unwrap();
+        });
+
+        let functions: Vec<_> = label_addresses(&asm)
+            .into_iter()
+            .filter(|(label, _)| label.starts_with("Main."))
+            .collect();
+        assert_eq!(functions.len(), 3);
+        assert_eq!(functions[0].1, 0);
+        for (label, address) in &functions {
+            assert_eq!(address % 8, 0, "{label} at {address}");
+        }
+        assert!(functions[1].1 < functions[2].1);
+        assert!(asm.contains("// pad to a 8-instruction boundary\n0\n"));
+
+        let unpadded = generate(|w| {
+            w.write_push_pop("push", "constant", 7).unwrap();
+            w.write_function_label("Main.main").unwrap();
+        });
+        assert!(!unpadded.contains("pad to"));
+        assert_eq!(label_addresses(&unpadded), [("Main.main".to_string(), 7)]);
+    }
+
+    #[test]
+    fn test_statics_are_named_per_file() {
+        let asm = generate(|w| {
+            w.set_filename("dir/Foo.vm");
+            w.write_push_pop("pop", "static", 3).unwrap();
+            w.set_filename("Bar.vm");
+            w.write_push_pop("push", "static", 3).unwrap();
+        });
+        assert!(asm.contains("@Foo.3\nM=D\n"));
+        assert!(asm.contains("@Bar.3\nD=M\n"));
+
+        let mut writer = CodeWriter::in_memory();
+        for command in ["push", "pop"] {
+            let error = writer.write_push_pop(command, "static", 5).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        }
+        writer.close().unwrap();
+        assert!(!String::from_utf8(writer.into_inner())
+            .unwrap()
+            .contains("@.5"));
+    }
+
+    #[test]
+    fn test_label_counter_across_files() {
+        let translate_two_files = |w: &mut CodeWriter<Vec<u8>>| {
+            w.write_arithmetic("eq").unwrap();
+            w.set_filename("Second.vm");
+            w.write_arithmetic("eq").unwrap();
+        };
+
+        let continued = generate(translate_two_files);
+        assert!(continued.contains("(EQ0)"));
+        assert!(continued.contains("(EQ1)"));
+
+        let reset = generate(|w| {
+            w.set_reset_labels_per_file(true);
+            translate_two_files(w);
+        });
+        assert_eq!(reset.matches("(EQ0)").count(), 2);
+        assert!(!reset.contains("(EQ1)"));
+    }
+
+    #[test]
+    fn test_position_independent_jumps() {
+        let absolute = generate(|w| {
+            w.write_arithmetic("lt").unwrap();
+        });
+        assert!(absolute.contains("@LT0\nD;JLT\n"));
+        assert!(absolute.contains("@ENDLT0\n0;JMP\n"));
+        assert!(!absolute.contains("relative jump"));
+
+        // R15 holds the load address; R13/R14 carry the condition and the
+        // absolute target of a conditional jump
+        let relative = generate(|w| {
+            w.set_position_independent(true);
+            w.write_arithmetic("lt").unwrap();
+        });
+        assert!(relative.contains(
+            "@R13\nM=D\n@LT0\nD=A\n@R15\nD=D+M\n@R14\nM=D\n@R13\nD=M\n@R14\nA=M\nD;JLT\n"
+        ));
+        assert!(relative.contains("@ENDLT0\nD=A\n@R15\nA=D+M\n0;JMP\n"));
+        assert!(!relative.contains("@LT0\nD;JLT"));
+    }
+
+    #[test]
+    fn test_shared_comparison_tail() {
+        let asm = generate(|w| {
+            w.set_shared_comparison_tail(true);
+            w.write_arithmetic("eq").unwrap();
+            w.write_arithmetic("gt").unwrap();
+            w.write_arithmetic("lt").unwrap();
+        });
+        // One push of D per comparison, no separate true/false pushes
+        assert_eq!(asm.matches("A=M\nM=D\n@SP\nM=M+1\n").count(), 3);
+        assert!(!asm.contains("M=0\n"));
+        assert!(!asm.contains("M=-1\n"));
+        assert!(asm.contains("@EQ0\nD;JEQ\nD=0\n@ENDEQ0\n0;JMP\n(EQ0)\nD=-1\n(ENDEQ0)\n"));
+    }
+
+    #[test]
+    fn test_shared_comparison_tail_position_independent() {
+        for (x, y, command, expected) in [
+            (1, 2, "eq", 0),
+            (2, 2, "eq", 0xFFFF),
+            (1, 2, "gt", 0),
+            (3, 2, "gt", 0xFFFF),
+            (2, 1, "lt", 0),
+            (1, 2, "lt", 0xFFFF),
+        ] {
+            let asm = generate(|w| {
+                w.set_position_independent(true);
+                w.set_shared_comparison_tail(true);
+                w.write_push_pop("push", "constant", x).unwrap();
+                w.write_push_pop("push", "constant", y).unwrap();
+                w.write_arithmetic(command).unwrap();
+            });
+            assert!(asm.contains("relative jump"));
+            let simulator = run(&asm);
+            assert_eq!(simulator.ram(0), 257, "{x} {command} {y}");
+            assert_eq!(simulator.ram(256), expected, "{x} {command} {y}");
+        }
+    }
+
+    #[test]
+    fn test_if_not_jumps_when_false() {
+        let asm = generate(|w| w.write_if_not("Main.then", "Main.else").unwrap());
+        assert!(asm.contains("// vm command:if-goto Main.then\n// vm command:goto Main.else\n"));
+        assert!(asm.ends_with("@SP\nM=M-1\nA=M\nD=M\n@Main.else\nD;JEQ\n\n"));
+    }
+
+    #[test]
+    fn test_control_flow() {
+        let asm = generate(|w| {
+            w.write_label("top").unwrap();
+            w.write_if("top").unwrap();
+            w.write_function_label("Main.loop").unwrap();
+            w.write_label("top").unwrap();
+            w.write_goto("top").unwrap();
+        });
+        assert!(asm.contains("// vm command:label top\n(top)\n"));
+        assert!(asm.contains("// vm command:if-goto top\n// get the top element of stack\n@SP\nM=M-1\nA=M\nD=M\n@top\nD;JNE\n"));
+        assert!(asm.contains("(Main.loop)\n// vm command:label top\n(Main.loop$top)\n"));
+        assert!(asm.ends_with("// vm command:goto top\n@Main.loop$top\n0;JMP\n\n"));
+    }
+
+    #[test]
+    fn test_function_call_and_return() {
+        let asm = generate(|w| {
+            w.write_call("Main.f", 0).unwrap();
+            w.write_function("Main.f", 2).unwrap();
+            w.write_call("Main.g", 3).unwrap();
+            w.write_return().unwrap();
+            assert!(w.write_function("Main.h", -1).is_err());
+        });
+        // Calls outside a function are named after the file
+        assert!(asm.contains("@Test$ret.0\nD=A\n"));
+        assert!(asm.contains("(Test$ret.0)\n"));
+        assert!(asm.contains("(Main.f)\n@SP\nA=M\nM=0\n@SP\nM=M+1\n@SP\nA=M\nM=0\n"));
+        assert!(asm.contains(
+            "@8\nD=D-A\n@ARG\nM=D\n@SP\nD=M\n@LCL\nM=D\n@Main.g\n0;JMP\n(Main.f$ret.1)\n"
+        ));
+        assert!(asm.contains("@R13\nAM=M-1\nD=M\n@LCL\nM=D\n@R14\nA=M\n0;JMP\n"));
+    }
+
+    #[test]
+    fn test_bootstrap() {
+        let asm = generate(|w| w.write_init().unwrap());
+        assert!(asm.starts_with(
+            "// bootstrap: SP = 256\n@256\nD=A\n@SP\nM=D\n// vm command:call Sys.init 0\n"
+        ));
+        assert!(asm.contains("@5\nD=D-A\n@ARG\nM=D\n"));
+        assert!(asm.ends_with("@Sys.init\n0;JMP\n(Test$ret.0)\n\n"));
+    }
+
+    /// Assembles `asm` and runs it with the stack at 256, returning the CPU
+    fn run(asm: &str) -> project6::simulator::Simulator {
+        let lines = project6::parser::read_source_lines(asm.as_bytes()).unwrap();
+        let rom = project6::assemble_to_u16(&lines).unwrap();
+        let mut simulator = project6::simulator::Simulator::new(rom);
+        simulator.set_ram(0, 256);
+        simulator.run_until_halt(10_000);
+        simulator
+    }
+
+    #[test]
+    fn test_comparisons_with_opposite_signs() {
+        // (x, y) pushed as constants, negated when negative; `neg 32767` then
+        // `sub 1` reaches -32768
+        let cases: [(&[&str], &[&str], &str, u16); 6] = [
+            (&["32767"], &["1", "neg"], "gt", 0xFFFF),
+            (&["32767"], &["1", "neg"], "lt", 0),
+            (&["1", "neg"], &["32767"], "lt", 0xFFFF),
+            (&["32767", "neg", "1", "sub"], &["1"], "gt", 0),
+            (&["32767", "neg", "1", "sub"], &["1"], "lt", 0xFFFF),
+            (&["5"], &["3"], "gt", 0xFFFF),
+        ];
+        for (x, y, command, expected) in cases {
+            for pic in [false, true] {
+                let asm = generate(|w| {
+                    w.set_position_independent(pic);
+                    for operand in x.iter().chain(y) {
+                        match operand.parse() {
+                            Ok(value) => w.write_push_pop("push", "constant", value).unwrap(),
+                            Err(_) => w.write_arithmetic(operand).unwrap(),
+                        }
+                    }
+                    w.write_arithmetic(command).unwrap();
+                });
+                let simulator = run(&asm);
+                assert_eq!(simulator.ram(0), 257, "{x:?} {command} {y:?}");
+                assert_eq!(simulator.ram(256), expected, "{x:?} {command} {y:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_binary_ops_in_place() {
+        // x = 12 (0b1100) is pushed first, so each op computes x OP y
+        for (command, expected) in [("add", 17), ("sub", 7), ("and", 4), ("or", 13)] {
+            for shared in [false, true] {
+                let asm = generate(|w| {
+                    w.set_shared_arithmetic(shared);
+                    w.write_push_pop("push", "constant", 12).unwrap();
+                    w.write_push_pop("push", "constant", 5).unwrap();
+                    w.write_arithmetic(command).unwrap();
+                });
+                assert!(!asm.contains("@R13"), "{command}");
+                let simulator = run(&asm);
+                assert_eq!(simulator.ram(0), 257, "{command}");
+                assert_eq!(simulator.ram(256), expected, "{command}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_subroutines() {
+        let program = |subroutines: bool| {
+            generate(|w| {
+                w.set_use_subroutines(subroutines);
+                w.write_init().unwrap();
+                w.write_function("Sys.init", 0).unwrap();
+                for (x, y, command, index) in [(3, 5, "lt", 0), (7, 2, "gt", 1), (1, 2, "eq", 2)] {
+                    w.write_push_pop("push", "constant", x).unwrap();
+                    w.write_push_pop("push", "constant", y).unwrap();
+                    w.write_call("Test.compare", 2).unwrap();
+                    w.write_push_pop("push", "constant", 0).unwrap();
+                    w.write_arithmetic(command).unwrap();
+                    w.write_push_pop("pop", "static", index).unwrap();
+                }
+                w.write_label("END").unwrap();
+                w.write_goto("END").unwrap();
+                w.write_function("Test.compare", 0).unwrap();
+                w.write_push_pop("push", "argument", 0).unwrap();
+                w.write_push_pop("push", "argument", 1).unwrap();
+                w.write_arithmetic("sub").unwrap();
+                w.write_return().unwrap();
+            })
+        };
+        let inline = program(false);
+        let shared = program(true);
+
+        assert_eq!(shared.matches("(SHARED_EQ)\n").count(), 1);
+        assert_eq!(shared.matches("(SHARED_RETURN)\n").count(), 1);
+        assert!(shared.contains("// vm command:return\n@SHARED_RETURN\n0;JMP\n"));
+        assert!(!inline.contains("SHARED_"));
+        for asm in [&inline, &shared] {
+            let simulator = run(asm);
+            // x - y compared with 0: -2 < 0, 5 > 0, -1 == 0
+            assert_eq!(simulator.ram(16), 0xFFFF);
+            assert_eq!(simulator.ram(17), 0xFFFF);
+            assert_eq!(simulator.ram(18), 0);
+        }
+    }
+
+    #[test]
+    fn test_small_constants() {
+        let instructions = |small: bool| {
+            let asm = generate(|w| {
+                w.set_small_constants(small);
+                w.write_push_pop("push", "constant", 0).unwrap();
+            });
+            asm.lines()
+                .filter(|line| !line.is_empty() && !line.starts_with("//"))
+                .count()
+        };
+        assert_eq!(instructions(false) - instructions(true), 2);
+
+        fn push_all(w: &mut CodeWriter<Vec<u8>>) {
+            for value in [0, 1, 2] {
+                w.write_push_pop("push", "constant", value).unwrap();
+            }
+        }
+        let plain = run(&generate(push_all));
+        let small = run(&generate(|w| {
+            w.set_small_constants(true);
+            push_all(w);
+            w.write_push_pop("push", "constant", -1).unwrap();
+        }));
+        assert_eq!(small.ram(0), 260);
+        for address in 1..259 {
+            assert_eq!(plain.ram(address), small.ram(address), "RAM[{address}]");
+        }
+        assert_eq!(small.ram(259), 0xFFFF);
+    }
+
+    #[test]
+    fn test_invalid_scratch_registers() {
+        generate(|w| {
+            assert!(w.set_scratch_registers(16, 1).is_err());
+            assert!(w.set_scratch_registers(3, 3).is_err());
+            assert!(w.set_scratch_registers(0, 15).is_ok());
+        });
+    }
+
+    #[test]
+    fn test_shared_arithmetic_bodies_emitted_once() {
+        let asm = generate(|w| {
+            w.set_shared_arithmetic(true);
+            for op in [
+                "add", "sub", "and", "or", "neg", "not", "add", "neg", "not", "sub",
+            ] {
+                w.write_arithmetic(op).unwrap();
+            }
+        });
+        for body in [
+            "M=D+M\n", "M=M-D\n", "M=D&M\n", "M=D|M\n", "D=A-D\n", "D=!D\n",
+        ] {
+            assert_eq!(asm.matches(body).count(), 1, "{body}");
+        }
+        assert_eq!(asm.matches("(SHARED_ADD)\n").count(), 1);
+        assert_eq!(asm.matches("@SHARED_ADD\n0;JMP\n").count(), 2);
+        assert!(asm.contains(
+            "@SHARED_ADD_RETURN0\nD=A\n@R15\nM=D\n@SHARED_ADD\n0;JMP\n(SHARED_ADD_RETURN0)\n"
+        ));
+        assert!(asm.find("(SHARED_ARITHMETIC_HALT)").unwrap() < asm.find("(SHARED_ADD)").unwrap());
+        assert!(asm.ends_with("@R15\nA=M\n0;JMP\n\n"));
+    }
+
+    #[test]
+    fn test_memory_map_header() {
+        let program = |w: &mut CodeWriter<Vec<u8>>| {
+            w.write_push_pop("push", "static", 3).unwrap();
+            w.write_push_pop("pop", "static", 0).unwrap();
+            w.write_push_pop("push", "static", 3).unwrap();
+        };
+        let plain = generate(program);
+        assert!(plain.starts_with("// vm command:push static 3\n"));
+        assert!(!plain.contains("Memory map"));
+
+        let asm = generate(|w| {
+            w.set_emit_header(true);
+            program(w);
+        });
+        assert!(asm.starts_with("// Memory map\n"));
+        assert!(asm.contains("//   RAM[13], RAM[14] scratch registers\n"));
+        assert!(asm.contains(
+            "// static 3 -> Test.3 at RAM[16]\n// static 0 -> Test.0 at RAM[17]\n\n// vm command:"
+        ));
+        assert_eq!(asm.matches("// static ").count(), 2);
+    }
 }
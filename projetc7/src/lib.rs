@@ -0,0 +1,209 @@
+//! VM translator for the Hack platform (`Nand2Tetris` Project 7)
+//!
+//! [`Parser`] reads VM commands and [`CodeWriter`] turns them into Hack
+//! assembly. [`translate_vm`] runs both in memory, for callers that have the
+//! source as a string and want the assembly back as one.
+
+use std::fmt;
+use std::io::Write;
+
+pub mod checks;
+pub mod code_writer;
+pub mod parser;
+pub mod verify;
+
+pub use checks::SegmentChecker;
+pub use code_writer::CodeWriter;
+pub use parser::{CommandType, ParseError, Parser};
+
+/// Why a VM program could not be translated
+#[derive(Debug)]
+pub enum TranslateError {
+    /// A command with missing or malformed arguments
+    Parse(ParseError),
+    /// A command the writer can't translate, or a failed write
+    Write(std::io::Error),
+}
+
+impl fmt::Display for TranslateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TranslateError::Parse(error) => write!(f, "{}", error),
+            TranslateError::Write(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for TranslateError {}
+
+impl From<ParseError> for TranslateError {
+    fn from(error: ParseError) -> Self {
+        TranslateError::Parse(error)
+    }
+}
+
+impl From<std::io::Error> for TranslateError {
+    fn from(error: std::io::Error) -> Self {
+        TranslateError::Write(error)
+    }
+}
+
+/// Translates the VM program in `source` to Hack assembly
+///
+/// `class_name` names the program's static variables (`Main.0`, ...), as the
+/// file name `Main.vm` would. No bootstrap code is written.
+///
+/// # Example
+/// ```
+/// let asm = projetc7::translate_vm("push static 3\n", "Main").unwrap();
+/// assert!(asm.contains("@Main.3\nD=M\n"));
+/// ```
+pub fn translate_vm(source: &str, class_name: &str) -> Result<String, TranslateError> {
+    let mut parser: Parser = source.parse()?;
+    let mut code_writer = CodeWriter::in_memory();
+    code_writer.set_filename(class_name);
+    write_commands(&mut parser, &mut code_writer, None, false)?;
+    code_writer.close()?;
+
+    let bytes = code_writer.into_inner();
+    Ok(String::from_utf8(bytes).expect("the code writer only emits ASCII"))
+}
+
+/// Translates every remaining command of `parser` into `code_writer`
+///
+/// Each command is first passed to `checker`, if given. With `optimize`,
+/// an `if-goto` that only skips a `goto` is folded into one inverted jump.
+/// The writer is not closed.
+pub fn write_commands(
+    parser: &mut Parser,
+    code_writer: &mut CodeWriter<impl Write>,
+    mut checker: Option<&mut SegmentChecker>,
+    optimize: bool,
+) -> Result<(), TranslateError> {
+    while parser.has_more_commands() {
+        parser.advance();
+        code_writer.set_source_line(parser.current_line_number());
+
+        if let Some(checker) = checker.as_mut() {
+            match parser.command_type() {
+                CommandType::Function => checker.enter_function(parser.arg1()?, parser.arg2()?),
+                CommandType::Call => checker.record_call(parser.arg1()?, parser.arg2()?),
+                CommandType::Push | CommandType::Pop => checker.check_access(
+                    parser.arg1()?,
+                    parser.arg2()?,
+                    parser.current_line_number(),
+                ),
+                _ => {}
+            }
+        }
+
+        let fallthrough = (optimize && parser.command_type() == CommandType::If)
+            .then(|| fallthrough_goto(parser))
+            .flatten();
+        if let Some(target) = fallthrough {
+            code_writer.write_if_not(parser.arg1()?, &target)?;
+            // The `goto` is folded in; the label after it is translated as usual
+            parser.advance();
+            continue;
+        }
+
+        match parser.command_type() {
+            CommandType::Arithmetic => {
+                let command = parser.arg1()?;
+                code_writer.write_arithmetic(command)?;
+            }
+            CommandType::Push => {
+                let segment = parser.arg1()?;
+                let index = parser.arg2()?;
+                code_writer.write_push_pop("push", segment, index)?;
+            }
+            CommandType::Pop => {
+                let segment = parser.arg1()?;
+                let index = parser.arg2()?;
+                code_writer.write_push_pop("pop", segment, index)?;
+            }
+            CommandType::Label => code_writer.write_label(parser.arg1()?)?,
+            CommandType::Goto => code_writer.write_goto(parser.arg1()?)?,
+            CommandType::If => code_writer.write_if(parser.arg1()?)?,
+            CommandType::Function => code_writer.write_function(parser.arg1()?, parser.arg2()?)?,
+            CommandType::Call => code_writer.write_call(parser.arg1()?, parser.arg2()?)?,
+            CommandType::Return => code_writer.write_return()?,
+            CommandType::Unknown => return Err(parser.unrecognized().into()),
+        }
+    }
+    Ok(())
+}
+
+/// Recognizes `if-goto L` / `goto M` / `label L` at the current `if-goto`
+///
+/// Jumping to `L` when the condition holds only skips the `goto`, so the
+/// pair is equivalent to jumping to `M` when it doesn't. Returns `M`. The
+/// three commands must be adjacent: a label in between could be jumped to
+/// from elsewhere, and `label L` stays in the output for other jumps.
+fn fallthrough_goto(parser: &Parser) -> Option<String> {
+    let mut goto = parser.peek(0)?.split_whitespace();
+    let mut label = parser.peek(1)?.split_whitespace();
+
+    let is_goto = goto.next() == Some("goto");
+    let target = goto.next()?;
+    let condition = parser.arg1().ok()?;
+    let is_label = label.next() == Some("label") && label.next() == Some(condition);
+
+    (is_goto && is_label).then(|| target.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `source` and advances to its first command
+    fn parser_at_first(source: &str) -> Parser {
+        let mut parser: Parser = source.parse().unwrap();
+        parser.advance();
+        parser
+    }
+
+    #[test]
+    fn test_translate_vm() {
+        let asm = translate_vm("// Adds\npush constant 7\npush constant 8\nadd\n", "Main").unwrap();
+        assert!(asm.starts_with("// vm command:push constant 7\n@7\nD=A\n"));
+        assert!(asm.contains("// vm command:add\n"));
+
+        let error = translate_vm("push constant 1\npop constant 0\n", "Main").unwrap_err();
+        assert!(matches!(
+            error,
+            TranslateError::Parse(ParseError { line: 2, .. })
+        ));
+        let error = translate_vm("push constant 1\nfrobnicate\n", "Main").unwrap_err();
+        assert!(matches!(
+            error,
+            TranslateError::Parse(ParseError { line: 2, .. })
+        ));
+        assert!(error
+            .to_string()
+            .contains("unrecognized command 'frobnicate'"));
+    }
+
+    #[test]
+    fn test_fallthrough_goto() {
+        let parser =
+            parser_at_first("if-goto THEN\ngoto ELSE\nlabel THEN\npush constant 1\nlabel ELSE\n");
+        assert_eq!(fallthrough_goto(&parser).as_deref(), Some("ELSE"));
+    }
+
+    #[test]
+    fn test_fallthrough_goto_needs_the_exact_pattern() {
+        for (name, source) in [
+            ("other_label", "if-goto THEN\ngoto ELSE\nlabel OTHER\n"),
+            (
+                "label_between",
+                "if-goto THEN\nlabel MID\ngoto ELSE\nlabel THEN\n",
+            ),
+            ("no_goto", "if-goto THEN\npush constant 0\nlabel THEN\n"),
+            ("at_end", "if-goto THEN\ngoto ELSE\n"),
+        ] {
+            let parser = parser_at_first(source);
+            assert_eq!(fallthrough_goto(&parser), None, "{name}");
+        }
+    }
+}
@@ -1,23 +1,22 @@
 use std::env;
 use std::path::Path;
 
-mod code_writer;
-mod parser;
-
-use code_writer::CodeWriter;
-use parser::{CommandType, Parser};
+use projetc7::{verify, write_commands, CodeWriter, Parser, SegmentChecker};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() != 2 {
-        eprintln!("Usage: {} <input.vm>", args[0]);
+    let Some(options) = parse_args(&args[1..]) else {
+        eprintln!(
+            "Usage: {} <input.vm> [--source-lines] [--check-locals] [--optimize] [--position-independent] [--shared-arithmetic] [--header] [--verify-asm] [--bootstrap] [--compact] [--subroutines]",
+            args[0]
+        );
         std::process::exit(1);
-    }
+    };
 
-    let input_file = &args[1];
+    let input_file = &options.input;
     let output_file = get_output_filename(input_file);
-    if let Err(e) = translate(input_file, &output_file) {
+    if let Err(e) = translate(input_file, &output_file, &options) {
         eprintln!("Error: {}", e);
         std::process::exit(1);
     }
@@ -25,42 +24,105 @@ fn main() {
     println!("Translation complete: {} -> {}", input_file, output_file);
 }
 
-fn translate(input_file: &str, output_file: &str) -> Result<(), Box<dyn std::error::Error>> {
+/// Command-line options
+#[derive(Default)]
+struct Options {
+    input: String,
+    /// Annotate each block with its VM source line
+    source_lines: bool,
+    /// Warn about `local`/`argument` indices beyond what a function declares
+    check_locals: bool,
+    /// Apply peephole optimizations to the generated code
+    optimize: bool,
+    /// Experimental: jump relative to the load address held in R15
+    position_independent: bool,
+    /// Emit arithmetic ops once as shared blocks and call them
+    shared_arithmetic: bool,
+    /// Start the output with a memory map comment block
+    header: bool,
+    /// Assemble the output afterwards and fail if it is not valid Hack
+    verify_asm: bool,
+    /// Start with code that sets up the stack and calls `Sys.init`
+    bootstrap: bool,
+    /// Leave out comments and blank lines
+    compact: bool,
+    /// Call shared subroutines for comparisons and `return`
+    subroutines: bool,
+}
+
+fn parse_args(args: &[String]) -> Option<Options> {
+    let mut options = Options::default();
+    let mut positional = Vec::new();
+
+    for arg in args {
+        match arg.as_str() {
+            "--source-lines" => options.source_lines = true,
+            "--check-locals" => options.check_locals = true,
+            "--optimize" => options.optimize = true,
+            "--position-independent" => options.position_independent = true,
+            "--shared-arithmetic" => options.shared_arithmetic = true,
+            "--header" => options.header = true,
+            "--verify-asm" => options.verify_asm = true,
+            "--bootstrap" => options.bootstrap = true,
+            "--compact" => options.compact = true,
+            "--subroutines" => options.subroutines = true,
+            flag if flag.starts_with("--") => return None,
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    if positional.len() != 1 {
+        return None;
+    }
+    options.input = positional.pop()?;
+    Some(options)
+}
+
+fn translate(
+    input_file: &str,
+    output_file: &str,
+    options: &Options,
+) -> Result<(), Box<dyn std::error::Error>> {
     let mut parser = Parser::new(input_file)?;
     let mut code_writer = CodeWriter::new(output_file)?;
 
     // Set the filename for static variables
     code_writer.set_filename(input_file);
+    code_writer.set_emit_source_lines(options.source_lines);
+    code_writer.set_position_independent(options.position_independent);
+    code_writer.set_shared_arithmetic(options.shared_arithmetic);
+    code_writer.set_use_subroutines(options.subroutines);
+    code_writer.set_emit_header(options.header);
+    code_writer.set_small_constants(options.optimize);
+    code_writer.set_emit_comments(!options.compact);
+    let mut checker = options.check_locals.then(SegmentChecker::new);
 
-    while parser.has_more_commands() {
-        parser.advance();
+    if options.bootstrap {
+        code_writer.write_init()?;
+    }
 
-        match parser.command_type() {
-            CommandType::Arithmetic => {
-                let command = parser.arg1();
-                code_writer.write_arithmetic(command)?;
-            }
-            CommandType::Push => {
-                let segment = parser.arg1();
-                let index = parser.arg2();
-                code_writer.write_push_pop("push", segment, index)?;
-            }
-            CommandType::Pop => {
-                let segment = parser.arg1();
-                let index = parser.arg2();
-                code_writer.write_push_pop("pop", segment, index)?;
-            }
-            _ => {
-                // Other command types not implemented yet
-                eprintln!(
-                    "Warning: Command type not implemented: {:?}",
-                    parser.command_type()
-                );
-            }
+    write_commands(
+        &mut parser,
+        &mut code_writer,
+        checker.as_mut(),
+        options.optimize,
+    )?;
+    code_writer.close()?;
+
+    if options.verify_asm {
+        let problems = verify::verify_assembly(&std::fs::read_to_string(output_file)?);
+        if !problems.is_empty() {
+            return Err(format!(
+                "generated assembly does not verify:\n  {}",
+                problems.join("\n  ")
+            )
+            .into());
         }
     }
 
-    code_writer.close()?;
+    for warning in checker.map(SegmentChecker::finish).unwrap_or_default() {
+        eprintln!("Warning: {}", warning);
+    }
     Ok(())
 }
 
@@ -90,3 +152,73 @@ fn get_output_filename(input_file: &str) -> String {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_malformed_input_returns_errors_without_panicking() {
+        for (name, source) in [
+            ("unknown_op", "push constant 1\nfrobnicate\n"),
+            ("unknown_segment", "push heap 0\n"),
+            ("pop_constant", "pop constant 0\n"),
+            ("missing_index", "push local\n"),
+            ("bad_index", "push local x\n"),
+            ("negative_index", "push constant -1\n"),
+            ("temp_overflow", "push temp 8\n"),
+            ("pointer_overflow", "pop pointer 2\n"),
+            ("missing_segment", "push\n"),
+            ("bad_function", "function Main.main\n"),
+        ] {
+            let dir = env::temp_dir();
+            let input = dir.join(format!("projetc7_{}_{name}.vm", std::process::id()));
+            let output = input.with_extension("asm");
+            std::fs::write(&input, source).unwrap();
+            let options = Options {
+                check_locals: true,
+                ..Options::default()
+            };
+
+            let result = std::panic::catch_unwind(|| {
+                translate(input.to_str().unwrap(), output.to_str().unwrap(), &options)
+                    .map_err(|error| error.to_string())
+            });
+            std::fs::remove_file(&input).ok();
+            std::fs::remove_file(&output).ok();
+
+            let error = result
+                .unwrap_or_else(|_| panic!("{name} panicked"))
+                .unwrap_err();
+            assert!(!error.is_empty(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_translated_program_assembles() {
+        let dir = env::temp_dir();
+        let input = dir.join(format!("projetc7_{}_Verify.vm", std::process::id()));
+        let output = input.with_extension("asm");
+        std::fs::write(
+            &input,
+            "push constant 10\npop local 0\nlabel LOOP\npush local 0\npush constant 1\nsub\n\
+             pop local 0\npush local 0\npush static 2\nlt\nif-goto LOOP\ngoto END\nlabel END\n",
+        )
+        .unwrap();
+        let options = Options {
+            optimize: true,
+            verify_asm: true,
+            ..Options::default()
+        };
+
+        let result = translate(input.to_str().unwrap(), output.to_str().unwrap(), &options)
+            .map_err(|error| error.to_string());
+        let asm = std::fs::read_to_string(&output).unwrap();
+        std::fs::remove_file(&input).ok();
+        std::fs::remove_file(&output).ok();
+
+        assert_eq!(result, Ok(()));
+        let lines = project6::parser::read_source_lines(asm.as_bytes()).unwrap();
+        assert!(project6::validate_program(&lines).is_empty());
+    }
+}
@@ -1,6 +1,12 @@
+use crate::code_writer::SegmentSymbol;
+use std::fmt;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
+/// The nine arithmetic and logical commands, which take no arguments
+pub const ARITHMETIC_COMMANDS: [&str; 9] =
+    ["add", "sub", "neg", "eq", "gt", "lt", "and", "or", "not"];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CommandType {
     Arithmetic,
@@ -12,23 +18,100 @@ pub enum CommandType {
     Function,
     Return,
     Call,
+    /// A line whose first word is no VM command, such as a misspelled `puhs`
+    Unknown,
+}
+
+impl CommandType {
+    /// The VM keyword of this command type, or `arithmetic` for `add`..`not`
+    /// and `unknown` for anything unrecognized
+    ///
+    /// # Example
+    /// ```
+    /// use projetc7::CommandType;
+    /// assert_eq!(CommandType::If.as_str(), "if-goto");
+    /// assert_eq!(CommandType::Arithmetic.to_string(), "arithmetic");
+    /// ```
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommandType::Arithmetic => "arithmetic",
+            CommandType::Push => "push",
+            CommandType::Pop => "pop",
+            CommandType::Label => "label",
+            CommandType::Goto => "goto",
+            CommandType::If => "if-goto",
+            CommandType::Function => "function",
+            CommandType::Return => "return",
+            CommandType::Call => "call",
+            CommandType::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for CommandType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A command whose arguments don't make sense, with where it came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based source line of the command
+    pub line: usize,
+    /// The command as written, without its comment
+    pub command: String,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {}: `{}`",
+            self.line, self.message, self.command
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for std::io::Error {
+    fn from(error: ParseError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+    }
 }
 
 pub struct Parser {
     lines: Vec<String>,
+    /// 1-based source line number of each entry in `lines`
+    line_numbers: Vec<usize>,
     current_line: usize,
     current_command: String,
     /// Cached parts of the current command to avoid repeated parsing
     cached_parts: Vec<String>,
 }
 
+/// Parses VM source held in memory
+impl std::str::FromStr for Parser {
+    type Err = std::io::Error;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Self::from_reader(source.as_bytes())
+    }
+}
+
 impl Parser {
     pub fn new(filename: &str) -> Result<Self, std::io::Error> {
         let file = File::open(filename)?;
-        let reader = BufReader::new(file);
+        Self::from_reader(BufReader::new(file))
+    }
 
+    /// Reads every command from `reader`, dropping comments and blank lines
+    pub fn from_reader(reader: impl BufRead) -> Result<Self, std::io::Error> {
         let mut lines = Vec::new();
-        for line in reader.lines() {
+        let mut line_numbers = Vec::new();
+        for (number, line) in reader.lines().enumerate() {
             let line = line?;
             // Remove comments and whitespace
             let line = if let Some(pos) = line.find("//") {
@@ -42,11 +125,13 @@ impl Parser {
             // Skip empty lines
             if !trimmed.is_empty() {
                 lines.push(trimmed.to_string());
+                line_numbers.push(number + 1);
             }
         }
 
         Ok(Parser {
             lines,
+            line_numbers,
             current_line: 0,
             current_command: String::new(),
             cached_parts: Vec::new(),
@@ -79,6 +164,24 @@ impl Parser {
         }
     }
 
+    /// Returns the command `offset` places after the current one, if any
+    ///
+    /// `peek(0)` is the command the next `advance` will move to.
+    #[inline]
+    pub fn peek(&self, offset: usize) -> Option<&str> {
+        self.lines
+            .get(self.current_line + offset)
+            .map(String::as_str)
+    }
+
+    /// Returns the 1-based source line of the current command (0 before `advance`)
+    #[inline]
+    pub fn current_line_number(&self) -> usize {
+        self.current_line
+            .checked_sub(1)
+            .map_or(0, |index| self.line_numbers[index])
+    }
+
     #[inline]
     pub fn command_type(&self) -> CommandType {
         debug_assert!(!self.cached_parts.is_empty(), "Empty command");
@@ -92,32 +195,178 @@ impl Parser {
             "function" => CommandType::Function,
             "return" => CommandType::Return,
             "call" => CommandType::Call,
-            _ => CommandType::Arithmetic,
+            command if ARITHMETIC_COMMANDS.contains(&command) => CommandType::Arithmetic,
+            _ => CommandType::Unknown,
         }
     }
 
+    /// Returns the first argument (the command itself for arithmetic)
+    ///
+    /// Fails on `return`, which has no arguments, on a missing argument and
+    /// on an unrecognized command. The segment of `push`/`pop` must exist,
+    /// and `constant` can't be popped.
     #[inline]
-    pub fn arg1(&self) -> &str {
-        let cmd_type = self.command_type();
-        match cmd_type {
-            CommandType::Arithmetic => &self.cached_parts[0],
-            CommandType::Return => panic!("arg1 should not be called for Return"),
-            _ => {
-                debug_assert!(self.cached_parts.len() > 1, "No arg1 found");
-                &self.cached_parts[1]
+    pub fn arg1(&self) -> Result<&str, ParseError> {
+        match self.command_type() {
+            CommandType::Arithmetic => Ok(&self.cached_parts[0]),
+            CommandType::Unknown => Err(self.unrecognized()),
+            CommandType::Return => Err(self.invalid("`return` takes no arguments")),
+            CommandType::Push | CommandType::Pop => {
+                let segment = self
+                    .cached_parts
+                    .get(1)
+                    .ok_or_else(|| self.invalid("missing argument"))?;
+                match SegmentSymbol::from_str(segment) {
+                    None => Err(self.invalid("unknown segment")),
+                    Some(SegmentSymbol::Constant) if self.command_type() == CommandType::Pop => {
+                        Err(self.invalid("cannot pop to the constant segment"))
+                    }
+                    Some(_) => Ok(segment),
+                }
             }
+            _ => self
+                .cached_parts
+                .get(1)
+                .map(String::as_str)
+                .ok_or_else(|| self.invalid("missing argument")),
         }
     }
 
+    /// Returns the numeric second argument of `push`/`pop`/`function`/`call`
+    ///
+    /// Segment indices and local/argument counts are never negative in the
+    /// VM language, so a negative value is an error like a non-number. Indices
+    /// into `temp` and `pointer` must also lie within those segments.
     #[inline]
-    pub fn arg2(&self) -> i32 {
-        let cmd_type = self.command_type();
-        match cmd_type {
+    pub fn arg2(&self) -> Result<i32, ParseError> {
+        match self.command_type() {
             CommandType::Push | CommandType::Pop | CommandType::Function | CommandType::Call => {
-                debug_assert!(self.cached_parts.len() > 2, "No arg2 found");
-                self.cached_parts[2].parse().expect("Invalid arg2")
+                let arg = self
+                    .cached_parts
+                    .get(2)
+                    .ok_or_else(|| self.invalid("missing second argument"))?;
+                let value: i32 = arg
+                    .parse()
+                    .map_err(|_| self.invalid("second argument must be an integer"))?;
+                if value < 0 {
+                    return Err(self.invalid("second argument must not be negative"));
+                }
+                if matches!(self.command_type(), CommandType::Push | CommandType::Pop) {
+                    let segment = &self.cached_parts[1];
+                    if let Some(max) =
+                        SegmentSymbol::from_str(segment).and_then(SegmentSymbol::max_index)
+                    {
+                        if value > max {
+                            return Err(self.invalid(format!(
+                                "{} index must be between 0 and {}",
+                                segment, max
+                            )));
+                        }
+                    }
+                }
+                Ok(value)
+            }
+            CommandType::Unknown => Err(self.unrecognized()),
+            command_type => {
+                Err(self.invalid(format!("`{}` takes no second argument", command_type)))
             }
-            _ => panic!("arg2 should not be called for this command type"),
         }
     }
+
+    /// Builds an error describing the current command
+    fn invalid(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line: self.current_line_number(),
+            command: self.current_command.clone(),
+            message: message.into(),
+        }
+    }
+
+    /// The error for a [`CommandType::Unknown`] command
+    pub(crate) fn unrecognized(&self) -> ParseError {
+        self.invalid(format!("unrecognized command '{}'", self.cached_parts[0]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `source` and advances to its first command
+    fn parser_at_first(source: &str) -> Parser {
+        let mut parser: Parser = source.parse().unwrap();
+        parser.advance();
+        parser
+    }
+
+    #[test]
+    fn test_arg2_errors_carry_the_source_line() {
+        let mut parser = parser_at_first("push constant 1\n\n// note\npop local -2\n");
+        assert_eq!(parser.arg2().unwrap(), 1);
+        parser.advance();
+        let error = parser.arg2().unwrap_err();
+        assert_eq!(error.line, 4);
+        assert_eq!(error.command, "pop local -2");
+        assert_eq!(
+            error.to_string(),
+            "line 4: second argument must not be negative: `pop local -2`"
+        );
+    }
+
+    #[test]
+    fn test_segment_access_is_validated() {
+        let parser = parser_at_first("pop constant 5\n");
+        assert_eq!(
+            parser.arg1().unwrap_err().to_string(),
+            "line 1: cannot pop to the constant segment: `pop constant 5`"
+        );
+        let parser = parser_at_first("push temp 99\n");
+        assert_eq!(
+            parser.arg2().unwrap_err().message,
+            "temp index must be between 0 and 7"
+        );
+        let parser = parser_at_first("push pointer 1\n");
+        assert_eq!(parser.arg1().unwrap(), "pointer");
+        assert_eq!(parser.arg2().unwrap(), 1);
+        let parser = parser_at_first("push heap 0\n");
+        assert_eq!(parser.arg1().unwrap_err().message, "unknown segment");
+    }
+
+    #[test]
+    fn test_command_type_display() {
+        let parser = parser_at_first("if-goto LOOP\n");
+        assert_eq!(parser.command_type().to_string(), "if-goto");
+        assert_eq!(
+            parser.arg2().unwrap_err().message,
+            "`if-goto` takes no second argument"
+        );
+        assert_eq!(CommandType::Arithmetic.as_str(), "arithmetic");
+    }
+
+    #[test]
+    fn test_unrecognized_commands() {
+        for command in ARITHMETIC_COMMANDS {
+            let parser = parser_at_first(command);
+            assert_eq!(parser.command_type(), CommandType::Arithmetic);
+            assert_eq!(parser.arg1().unwrap(), command);
+        }
+
+        let mut parser = parser_at_first(
+            "push constant 5
+// typo
+puhs constant 5
+Add
+",
+        );
+        parser.advance();
+        assert_eq!(parser.command_type(), CommandType::Unknown);
+        let error = parser.arg1().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 3: unrecognized command 'puhs': `puhs constant 5`"
+        );
+        assert_eq!(parser.arg2().unwrap_err(), error);
+        parser.advance();
+        assert_eq!(parser.command_type(), CommandType::Unknown);
+    }
 }
@@ -0,0 +1,67 @@
+use project6::code::parse_a_constant;
+use project6::parser::read_source_lines;
+use project6::program::Program;
+use project6::symbol_table::is_predefined;
+use project6::validate_program;
+
+/// Self-test run by `--verify-asm`: checks that generated assembly is valid
+/// Hack, returning one message per problem.
+///
+/// Besides anything the assembler rejects, this reports symbols that are
+/// neither labels, predefined symbols nor statics (`File.3`). The assembler
+/// would quietly turn them into variables, but the translator emits no other
+/// variables, so each one is a jump to a label that was never written.
+pub fn verify_assembly(asm: &str) -> Vec<String> {
+    let lines = match read_source_lines(asm.as_bytes()) {
+        Ok(lines) => lines,
+        Err(error) => return vec![error.to_string()],
+    };
+
+    let errors = validate_program(&lines);
+    if !errors.is_empty() {
+        return errors.iter().map(ToString::to_string).collect();
+    }
+
+    // The program assembled, so it parses
+    let Ok(program) = Program::from_lines(&lines) else {
+        return Vec::new();
+    };
+    let mut undefined: Vec<&str> = program
+        .instructions
+        .iter()
+        .filter_map(|instruction| instruction.symbol())
+        .filter(|&symbol| {
+            parse_a_constant(symbol).is_none()
+                && !is_predefined(symbol)
+                && !is_static(symbol)
+                && program.label_address(symbol).is_none()
+        })
+        .collect();
+    undefined.sort_unstable();
+    undefined.dedup();
+
+    undefined
+        .into_iter()
+        .map(|symbol| format!("undefined symbol `{}`", symbol))
+        .collect()
+}
+
+/// Returns `true` for a static variable symbol such as `Main.3`
+fn is_static(symbol: &str) -> bool {
+    symbol.rsplit_once('.').is_some_and(|(file, index)| {
+        !file.is_empty() && !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undefined_labels_are_reported() {
+        let asm = "(Main.f)\n@Main.f$end\n0;JMP\n@Main.3\nM=D\n@SCREEN\nM=-1\n@Main.f\n0;JMP\n";
+        assert_eq!(verify_assembly(asm), ["undefined symbol `Main.f$end`"]);
+        assert!(verify_assembly("(END)\n@END\n0;JMP\n").is_empty());
+        assert_eq!(verify_assembly("D=Q\n").len(), 1);
+    }
+}
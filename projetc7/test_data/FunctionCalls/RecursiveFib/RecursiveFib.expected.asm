@@ -0,0 +1,478 @@
+// vm command:function RecursiveFib.main 0
+(RecursiveFib.main)
+
+// vm command:push constant 10
+@10
+D=A
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// vm command:call RecursiveFib.fib 1
+// push the return address
+@RecursiveFib.main$ret.0
+D=A
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@LCL
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@ARG
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THIS
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THAT
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// ARG = SP - 6, LCL = SP
+@SP
+D=M
+@6
+D=D-A
+@ARG
+M=D
+@SP
+D=M
+@LCL
+M=D
+@RecursiveFib.fib
+0;JMP
+(RecursiveFib.main$ret.0)
+
+// vm command:pop static 0
+// get the top element of stack
+@SP
+M=M-1
+A=M
+D=M
+@RecursiveFib.temp.0
+M=D
+
+// vm command:label END
+(RecursiveFib.main$END)
+
+// vm command:goto END
+@RecursiveFib.main$END
+0;JMP
+
+// vm command:function RecursiveFib.fib 0
+(RecursiveFib.fib)
+
+// vm command:push argument 0
+@ARG
+D=M
+@0
+A=D+A
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// vm command:push constant 2
+@2
+D=A
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// vm command:lt
+// get the top element of stack
+@SP
+M=M-1
+A=M
+D=M
+// store the result temporarily
+@R14
+M=D
+// get the top element of stack
+@SP
+M=M-1
+A=M
+D=M
+// store the result temporarily
+@R13
+M=D
+// compare the signs first: x - y overflows when they differ
+@NEGLT1
+D;JLT
+@SP
+A=M+1
+D=M
+@SUBLT1
+D;JGE
+@FALSELT1
+0;JMP
+(NEGLT1)
+@SP
+A=M+1
+D=M
+@SUBLT1
+D;JLT
+@LT1
+0;JMP
+(SUBLT1)
+@SP
+A=M
+D=M
+A=A+1
+D=D-M
+@LT1
+D;JLT
+(FALSELT1)
+// push the value into stack
+@SP
+A=M
+M=0
+@SP
+M=M+1
+@ENDLT1
+0;JMP
+(LT1)
+// push the value into stack
+@SP
+A=M
+M=-1
+@SP
+M=M+1
+(ENDLT1)
+
+// vm command:if-goto BASE
+// get the top element of stack
+@SP
+M=M-1
+A=M
+D=M
+@RecursiveFib.fib$BASE
+D;JNE
+
+// vm command:push argument 0
+@ARG
+D=M
+@0
+A=D+A
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// vm command:push constant 1
+@1
+D=A
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// vm command:sub
+// pop y into D and point at x
+@SP
+AM=M-1
+D=M
+A=A-1
+M=M-D
+
+// vm command:call RecursiveFib.fib 1
+// push the return address
+@RecursiveFib.fib$ret.2
+D=A
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@LCL
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@ARG
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THIS
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THAT
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// ARG = SP - 6, LCL = SP
+@SP
+D=M
+@6
+D=D-A
+@ARG
+M=D
+@SP
+D=M
+@LCL
+M=D
+@RecursiveFib.fib
+0;JMP
+(RecursiveFib.fib$ret.2)
+
+// vm command:push argument 0
+@ARG
+D=M
+@0
+A=D+A
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// vm command:push constant 2
+@2
+D=A
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// vm command:sub
+// pop y into D and point at x
+@SP
+AM=M-1
+D=M
+A=A-1
+M=M-D
+
+// vm command:call RecursiveFib.fib 1
+// push the return address
+@RecursiveFib.fib$ret.3
+D=A
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@LCL
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@ARG
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THIS
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THAT
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// ARG = SP - 6, LCL = SP
+@SP
+D=M
+@6
+D=D-A
+@ARG
+M=D
+@SP
+D=M
+@LCL
+M=D
+@RecursiveFib.fib
+0;JMP
+(RecursiveFib.fib$ret.3)
+
+// vm command:add
+// pop y into D and point at x
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+
+// vm command:return
+// frame = LCL, return address = *(frame - 5)
+@LCL
+D=M
+@R13
+M=D
+@5
+A=D-A
+D=M
+@R14
+M=D
+// get the top element of stack
+@SP
+M=M-1
+A=M
+D=M
+// *ARG = return value, SP = ARG + 1
+@ARG
+A=M
+M=D
+@ARG
+D=M+1
+@SP
+M=D
+@R13
+AM=M-1
+D=M
+@THAT
+M=D
+@R13
+AM=M-1
+D=M
+@THIS
+M=D
+@R13
+AM=M-1
+D=M
+@ARG
+M=D
+@R13
+AM=M-1
+D=M
+@LCL
+M=D
+@R14
+A=M
+0;JMP
+
+// vm command:label BASE
+(RecursiveFib.fib$BASE)
+
+// vm command:push argument 0
+@ARG
+D=M
+@0
+A=D+A
+D=M
+// push the value into stack
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// vm command:return
+// frame = LCL, return address = *(frame - 5)
+@LCL
+D=M
+@R13
+M=D
+@5
+A=D-A
+D=M
+@R14
+M=D
+// get the top element of stack
+@SP
+M=M-1
+A=M
+D=M
+// *ARG = return value, SP = ARG + 1
+@ARG
+A=M
+M=D
+@ARG
+D=M+1
+@SP
+M=D
+@R13
+AM=M-1
+D=M
+@THAT
+M=D
+@R13
+AM=M-1
+D=M
+@THIS
+M=D
+@R13
+AM=M-1
+D=M
+@ARG
+M=D
+@R13
+AM=M-1
+D=M
+@LCL
+M=D
+@R14
+A=M
+0;JMP
+
@@ -221,32 +221,12 @@ M=D
 M=M+1
 
 // vm command:add
-// get the top element of stack
-@SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R14
-M=D
-// get the top element of stack
+// pop y into D and point at x
 @SP
-M=M-1
-A=M
+AM=M-1
 D=M
-// store the result temporarily
-@R13
-M=D
-@R13
-D=M
-@R14
-D=D+M
-// push the value into stack
-@SP
-A=M
-M=D
-@SP
-M=M+1
+A=A-1
+M=D+M
 
 // vm command:push argument 1
 @ARG
@@ -262,32 +242,12 @@ M=D
 M=M+1
 
 // vm command:sub
-// get the top element of stack
+// pop y into D and point at x
 @SP
-M=M-1
-A=M
+AM=M-1
 D=M
-// store the result temporarily
-@R14
-M=D
-// get the top element of stack
-@SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R13
-M=D
-@R13
-D=M
-@R14
-D=D-M
-// push the value into stack
-@SP
-A=M
-M=D
-@SP
-M=M+1
+A=A-1
+M=M-D
 
 // vm command:push this 6
 @THIS
@@ -316,60 +276,20 @@ M=D
 M=M+1
 
 // vm command:add
-// get the top element of stack
+// pop y into D and point at x
 @SP
-M=M-1
-A=M
+AM=M-1
 D=M
-// store the result temporarily
-@R14
-M=D
-// get the top element of stack
-@SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R13
-M=D
-@R13
-D=M
-@R14
-D=D+M
-// push the value into stack
-@SP
-A=M
-M=D
-@SP
-M=M+1
+A=A-1
+M=D+M
 
 // vm command:sub
-// get the top element of stack
-@SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R14
-M=D
-// get the top element of stack
+// pop y into D and point at x
 @SP
-M=M-1
-A=M
+AM=M-1
 D=M
-// store the result temporarily
-@R13
-M=D
-@R13
-D=M
-@R14
-D=D-M
-// push the value into stack
-@SP
-A=M
-M=D
-@SP
-M=M+1
+A=A-1
+M=M-D
 
 // vm command:push temp 6
 @R5
@@ -385,30 +305,10 @@ M=D
 M=M+1
 
 // vm command:add
-// get the top element of stack
-@SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R14
-M=D
-// get the top element of stack
+// pop y into D and point at x
 @SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R13
-M=D
-@R13
+AM=M-1
 D=M
-@R14
-D=D+M
-// push the value into stack
-@SP
-A=M
-M=D
-@SP
-M=M+1
+A=A-1
+M=D+M
 
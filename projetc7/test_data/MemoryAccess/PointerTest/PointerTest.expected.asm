@@ -137,32 +137,12 @@ M=D
 M=M+1
 
 // vm command:add
-// get the top element of stack
-@SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R14
-M=D
-// get the top element of stack
+// pop y into D and point at x
 @SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R13
-M=D
-@R13
+AM=M-1
 D=M
-@R14
-D=D+M
-// push the value into stack
-@SP
-A=M
-M=D
-@SP
-M=M+1
+A=A-1
+M=D+M
 
 // vm command:push this 2
 @THIS
@@ -178,32 +158,12 @@ M=D
 M=M+1
 
 // vm command:sub
-// get the top element of stack
-@SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R14
-M=D
-// get the top element of stack
+// pop y into D and point at x
 @SP
-M=M-1
-A=M
+AM=M-1
 D=M
-// store the result temporarily
-@R13
-M=D
-@R13
-D=M
-@R14
-D=D-M
-// push the value into stack
-@SP
-A=M
-M=D
-@SP
-M=M+1
+A=A-1
+M=M-D
 
 // vm command:push that 6
 @THAT
@@ -219,30 +179,10 @@ M=D
 M=M+1
 
 // vm command:add
-// get the top element of stack
+// pop y into D and point at x
 @SP
-M=M-1
-A=M
+AM=M-1
 D=M
-// store the result temporarily
-@R14
-M=D
-// get the top element of stack
-@SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R13
-M=D
-@R13
-D=M
-@R14
-D=D+M
-// push the value into stack
-@SP
-A=M
-M=D
-@SP
-M=M+1
+A=A-1
+M=D+M
 
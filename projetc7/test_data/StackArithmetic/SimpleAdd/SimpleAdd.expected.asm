@@ -19,30 +19,10 @@ M=D
 M=M+1
 
 // vm command:add
-// get the top element of stack
+// pop y into D and point at x
 @SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R14
-M=D
-// get the top element of stack
-@SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R13
-M=D
-@R13
+AM=M-1
 D=M
-@R14
-D=D+M
-// push the value into stack
-@SP
-A=M
-M=D
-@SP
-M=M+1
+A=A-1
+M=D+M
 
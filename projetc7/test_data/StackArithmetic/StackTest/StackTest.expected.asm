@@ -215,12 +215,33 @@ D=M
 // store the result temporarily
 @R13
 M=D
-@R13
+// compare the signs first: x - y overflows when they differ
+@NEGLT3
+D;JLT
+@SP
+A=M+1
 D=M
-@R14
+@SUBLT3
+D;JGE
+@FALSELT3
+0;JMP
+(NEGLT3)
+@SP
+A=M+1
+D=M
+@SUBLT3
+D;JLT
+@LT3
+0;JMP
+(SUBLT3)
+@SP
+A=M
+D=M
+A=A+1
 D=D-M
 @LT3
 D;JLT
+(FALSELT3)
 // push the value into stack
 @SP
 A=M
@@ -275,12 +296,33 @@ D=M
 // store the result temporarily
 @R13
 M=D
-@R13
+// compare the signs first: x - y overflows when they differ
+@NEGLT4
+D;JLT
+@SP
+A=M+1
 D=M
-@R14
+@SUBLT4
+D;JGE
+@FALSELT4
+0;JMP
+(NEGLT4)
+@SP
+A=M+1
+D=M
+@SUBLT4
+D;JLT
+@LT4
+0;JMP
+(SUBLT4)
+@SP
+A=M
+D=M
+A=A+1
 D=D-M
 @LT4
 D;JLT
+(FALSELT4)
 // push the value into stack
 @SP
 A=M
@@ -335,12 +377,33 @@ D=M
 // store the result temporarily
 @R13
 M=D
-@R13
+// compare the signs first: x - y overflows when they differ
+@NEGLT5
+D;JLT
+@SP
+A=M+1
 D=M
-@R14
+@SUBLT5
+D;JGE
+@FALSELT5
+0;JMP
+(NEGLT5)
+@SP
+A=M+1
+D=M
+@SUBLT5
+D;JLT
+@LT5
+0;JMP
+(SUBLT5)
+@SP
+A=M
+D=M
+A=A+1
 D=D-M
 @LT5
 D;JLT
+(FALSELT5)
 // push the value into stack
 @SP
 A=M
@@ -395,12 +458,33 @@ D=M
 // store the result temporarily
 @R13
 M=D
-@R13
+// compare the signs first: x - y overflows when they differ
+@NEGGT6
+D;JLT
+@SP
+A=M+1
 D=M
-@R14
+@SUBGT6
+D;JGE
+@GT6
+0;JMP
+(NEGGT6)
+@SP
+A=M+1
+D=M
+@SUBGT6
+D;JLT
+@FALSEGT6
+0;JMP
+(SUBGT6)
+@SP
+A=M
+D=M
+A=A+1
 D=D-M
 @GT6
 D;JGT
+(FALSEGT6)
 // push the value into stack
 @SP
 A=M
@@ -455,12 +539,33 @@ D=M
 // store the result temporarily
 @R13
 M=D
-@R13
+// compare the signs first: x - y overflows when they differ
+@NEGGT7
+D;JLT
+@SP
+A=M+1
 D=M
-@R14
+@SUBGT7
+D;JGE
+@GT7
+0;JMP
+(NEGGT7)
+@SP
+A=M+1
+D=M
+@SUBGT7
+D;JLT
+@FALSEGT7
+0;JMP
+(SUBGT7)
+@SP
+A=M
+D=M
+A=A+1
 D=D-M
 @GT7
 D;JGT
+(FALSEGT7)
 // push the value into stack
 @SP
 A=M
@@ -515,12 +620,33 @@ D=M
 // store the result temporarily
 @R13
 M=D
-@R13
+// compare the signs first: x - y overflows when they differ
+@NEGGT8
+D;JLT
+@SP
+A=M+1
 D=M
-@R14
+@SUBGT8
+D;JGE
+@GT8
+0;JMP
+(NEGGT8)
+@SP
+A=M+1
+D=M
+@SUBGT8
+D;JLT
+@FALSEGT8
+0;JMP
+(SUBGT8)
+@SP
+A=M
+D=M
+A=A+1
 D=D-M
 @GT8
 D;JGT
+(FALSEGT8)
 // push the value into stack
 @SP
 A=M
@@ -569,32 +695,12 @@ M=D
 M=M+1
 
 // vm command:add
-// get the top element of stack
+// pop y into D and point at x
 @SP
-M=M-1
-A=M
+AM=M-1
 D=M
-// store the result temporarily
-@R14
-M=D
-// get the top element of stack
-@SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R13
-M=D
-@R13
-D=M
-@R14
-D=D+M
-// push the value into stack
-@SP
-A=M
-M=D
-@SP
-M=M+1
+A=A-1
+M=D+M
 
 // vm command:push constant 112
 @112
@@ -607,32 +713,12 @@ M=D
 M=M+1
 
 // vm command:sub
-// get the top element of stack
+// pop y into D and point at x
 @SP
-M=M-1
-A=M
+AM=M-1
 D=M
-// store the result temporarily
-@R14
-M=D
-// get the top element of stack
-@SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R13
-M=D
-@R13
-D=M
-@R14
-D=D-M
-// push the value into stack
-@SP
-A=M
-M=D
-@SP
-M=M+1
+A=A-1
+M=M-D
 
 // vm command:neg
 // get the top element of stack
@@ -650,32 +736,12 @@ M=D
 M=M+1
 
 // vm command:and
-// get the top element of stack
+// pop y into D and point at x
 @SP
-M=M-1
-A=M
+AM=M-1
 D=M
-// store the result temporarily
-@R14
-M=D
-// get the top element of stack
-@SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R13
-M=D
-@R13
-D=M
-@R14
-D=D&M
-// push the value into stack
-@SP
-A=M
-M=D
-@SP
-M=M+1
+A=A-1
+M=D&M
 
 // vm command:push constant 82
 @82
@@ -688,32 +754,12 @@ M=D
 M=M+1
 
 // vm command:or
-// get the top element of stack
-@SP
-M=M-1
-A=M
-D=M
-// store the result temporarily
-@R14
-M=D
-// get the top element of stack
+// pop y into D and point at x
 @SP
-M=M-1
-A=M
+AM=M-1
 D=M
-// store the result temporarily
-@R13
-M=D
-@R13
-D=M
-@R14
-D=D|M
-// push the value into stack
-@SP
-A=M
-M=D
-@SP
-M=M+1
+A=A-1
+M=D|M
 
 // vm command:not
 // get the top element of stack
@@ -284,6 +284,28 @@ fn test_all_vm_files() {
     }
 }
 
+/// Translate a recursive function and run the result on the Hack simulator
+#[test]
+fn test_recursive_function_runs() {
+    let vm_file = get_project_root().join("test_data/FunctionCalls/RecursiveFib/RecursiveFib.vm");
+    let temp_asm = run_translator_to_temp(&vm_file).expect("translation failed");
+    let asm = fs::read_to_string(&temp_asm).unwrap();
+    fs::remove_file(&temp_asm).ok();
+
+    let lines = project6::parser::read_source_lines(asm.as_bytes()).unwrap();
+    let rom = project6::assemble_to_u16(&lines).expect("generated assembly is invalid");
+    let mut simulator = project6::simulator::Simulator::new(rom);
+    // No bootstrap code: start with the stack at 256 and run main directly
+    simulator.set_ram(0, 256);
+
+    let result = simulator.run_until_halt(1_000_000);
+    assert_eq!(result.status, project6::simulator::RunStatus::Halted);
+    // static 0 is the program's only variable, allocated at RAM 16
+    assert_eq!(simulator.ram(16), 55);
+    // Every call returned, leaving main's stack empty
+    assert_eq!(simulator.ram(0), 256);
+}
+
 /// Cleanup test to remove any lingering temp files and generated .asm files
 /// Run this with: cargo test cleanup_all_test_files -- --ignored
 #[test]